@@ -50,14 +50,10 @@ fn main() {
     println!("{json}");
 }
 
-fn assign_ids(sidewalks: &mut Vec<Walkable>) {
-    let _ = sidewalks
-        .iter_mut()
-        .map(|sidewalk| {
-            sidewalk.id = Some(Uuid::new_v4());
-            sidewalk
-        })
-        .collect::<Vec<_>>();
+fn assign_ids(sidewalks: &mut [Walkable]) {
+    for sidewalk in sidewalks.iter_mut() {
+        sidewalk.id = Some(Uuid::new_v4());
+    }
 }
 
 fn get_intersection_points(sidewalks: &mut Vec<Walkable>) {
@@ -114,7 +110,7 @@ fn associate_ids(sidewalks: &Vec<Walkable>) -> Vec<Walkable> {
         for intersection_point in sidewalk.intersection_points.clone() {
             let intersecting_ids = find_inter_ids(
                 &intersection_point.intersection_point,
-                &sidewalks,
+                sidewalks,
                 sidewalk.id.unwrap(),
             );
 
@@ -127,10 +123,13 @@ fn associate_ids(sidewalks: &Vec<Walkable>) -> Vec<Walkable> {
         }
 
         let walkable = Walkable {
-            id: sidewalk.id.clone(),
+            id: sidewalk.id,
             segments: sidewalk.segments.clone(),
             lights: sidewalk.lights.clone(),
             intersection_points: intersections,
+            buildings: sidewalk.buildings.clone(),
+            street_name: sidewalk.street_name.clone(),
+            properties: sidewalk.properties.clone(),
         }; 
 
         pb.inc(1); 
@@ -73,6 +73,9 @@ fn main() {
                 segments: sidewalk.segments.clone(),
                 lights: light_points,
                 intersection_points: sidewalk.intersection_points.clone(),
+                buildings: sidewalk.buildings.clone(),
+                street_name: sidewalk.street_name.clone(),
+                properties: sidewalk.properties.clone(),
             }
         })
         .collect::<Vec<_>>();
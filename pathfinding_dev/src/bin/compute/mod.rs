@@ -35,18 +35,12 @@ pub mod calculate_nearest {
      *                 the part of the street that is querying the spatial r-tree. Therefore, a HashSet
      *                 is used to avoid duplicates.
      */
-    #[derive(Eq, Hash)]
+    #[derive(PartialEq, Eq, Hash)]
     struct HashablePoint {
         x: F64Wrapper,
         y: F64Wrapper,
     }
 
-    impl PartialEq for HashablePoint {
-        fn eq(&self, other: &Self) -> bool {
-            self.x.0.to_bits() == other.x.0.to_bits() && self.y.0.to_bits() == other.y.0.to_bits()
-        }
-    }
-
     pub fn get_mid_point(start: Point, end: Point) -> Point {
         const DEG_TO_RADS: f64 = std::f64::consts::PI / 180.0;
         const RADS_TO_DEGS: f64 = 180.0 / std::f64::consts::PI;
@@ -179,7 +173,7 @@ pub mod calculate_nearest {
                 let mut curr_neighbors: Vec<Point> = vec![];
 
                 let point_nearest_neighbors = spatial_tree
-                    .nearest_neighbor_iter_with_distance_2(&point)
+                    .nearest_neighbor_iter_with_distance_2(point)
                     .collect::<Vec<_>>();
 
                 for i in 0..std::cmp::min(k, point_nearest_neighbors.len() as u32) {
@@ -54,7 +54,7 @@ fn main() {
     };
 
     let sidewalks = get_features(&sidewalk_file_contents);
-    assert!(sidewalks.len() > 0);
+    assert!(!sidewalks.is_empty());
 
     let segmented_sidewalks: Vec<Walkable> = sidewalks
         .par_iter()
@@ -0,0 +1,227 @@
+#[allow(dead_code)]
+pub mod dem {
+    use std::io::Cursor;
+    use tiff::decoder::{Decoder, DecodingResult};
+    use tiff::tags::Tag;
+
+    /* extensions read as ESRI ASCII grids - any other file is read as a GeoTIFF */
+    const ASCII_GRID_EXTENSIONS: [&str; 2] = [".asc", ".txt"];
+
+    /*
+     *  Dem        : digital elevation model, a grid of elevations in meters
+     *  purpose    : the grid has to be in longitude / latitude (EPSG:4326) - reproject other
+     *               models first, e.g. with gdalwarp -t_srs EPSG:4326
+     *  origin     : [lng, lat] of the center of the top left cell
+     *  cell_size  : [width, height] of a cell in degrees
+     *  elevations : row major, top row first - None where the model has no data
+     */
+    pub struct Dem {
+        width: usize,
+        height: usize,
+        origin: [f64; 2],
+        cell_size: [f64; 2],
+        elevations: Vec<Option<f64>>,
+    }
+
+    impl Dem {
+        /* bilinear interpolation between the four closest cell centers - None outside the model or when a cell with weight has no data */
+        pub fn sample(&self, lng: f64, lat: f64) -> Option<f64> {
+            let column = (lng - self.origin[0]) / self.cell_size[0];
+            let row = (self.origin[1] - lat) / self.cell_size[1];
+            if column < 0.0 || row < 0.0 || column > (self.width - 1) as f64 || row > (self.height - 1) as f64 {
+                return None;
+            }
+
+            let (left, top) = (column.floor() as usize, row.floor() as usize);
+            let (right, bottom) = ((left + 1).min(self.width - 1), (top + 1).min(self.height - 1));
+            let (dx, dy) = (column - left as f64, row - top as f64);
+
+            let upper = blend(self.at(left, top), self.at(right, top), dx);
+            let lower = blend(self.at(left, bottom), self.at(right, bottom), dx);
+            blend(upper, lower, dy)
+        }
+
+        fn at(&self, column: usize, row: usize) -> Option<f64> {
+            self.elevations[row * self.width + column]
+        }
+    }
+
+    /* the path only tells the format apart, the model is parsed from the contents already read */
+    pub fn load_dem(path: &str, contents: &[u8]) -> Dem {
+        if ASCII_GRID_EXTENSIONS.iter().any(|extension| path.to_lowercase().ends_with(extension)) {
+            let contents = match std::str::from_utf8(contents) {
+                Ok(contents) => contents,
+                Err(err) => panic!("elevation model {} is not a text file: {}", path, err),
+            };
+            load_ascii_grid(contents)
+        } else {
+            load_geotiff(contents)
+        }
+    }
+
+    /*
+     *  ESRI ASCII grid: a header of ncols, nrows, xllcorner (or xllcenter), yllcorner (or yllcenter),
+     *  cellsize and an optional NODATA_value, followed by the rows of the grid, top row first
+     */
+    fn load_ascii_grid(contents: &str) -> Dem {
+        let mut tokens = contents.split_whitespace().peekable();
+        let mut header: Vec<(String, f64)> = vec![];
+
+        while let Some(token) = tokens.peek() {
+            if token.parse::<f64>().is_ok() {
+                break;
+            }
+            let key = tokens.next().unwrap().to_lowercase();
+            let value = tokens
+                .next()
+                .and_then(|value| value.parse::<f64>().ok())
+                .expect("expected every ASCII grid header key to be followed by a number");
+            header.push((key, value));
+        }
+
+        let value = |key: &str| header.iter().find(|(name, _)| name == key).map(|(_, value)| *value);
+        let width = value("ncols").expect("ASCII grid header is missing ncols") as usize;
+        let height = value("nrows").expect("ASCII grid header is missing nrows") as usize;
+        let cell_size = value("cellsize").expect("ASCII grid header is missing cellsize");
+        let nodata = value("nodata_value");
+
+        /* corners are the outer edge of the bottom left cell, centers the middle of it */
+        let left = match (value("xllcenter"), value("xllcorner")) {
+            (Some(center), _) => center,
+            (None, Some(corner)) => corner + cell_size / 2.0,
+            (None, None) => panic!("ASCII grid header is missing xllcorner or xllcenter"),
+        };
+        let bottom = match (value("yllcenter"), value("yllcorner")) {
+            (Some(center), _) => center,
+            (None, Some(corner)) => corner + cell_size / 2.0,
+            (None, None) => panic!("ASCII grid header is missing yllcorner or yllcenter"),
+        };
+
+        let elevations: Vec<Option<f64>> = tokens
+            .map(|token| token.parse::<f64>().expect("expected the ASCII grid to only contain numbers"))
+            .map(|elevation| if Some(elevation) == nodata { None } else { Some(elevation) })
+            .collect();
+        assert!(elevations.len() == width * height, "ASCII grid has {} values but its header announces {}x{}", elevations.len(), width, height);
+
+        Dem {
+            width,
+            height,
+            origin: [left, bottom + (height - 1) as f64 * cell_size],
+            cell_size: [cell_size, cell_size],
+            elevations,
+        }
+    }
+
+    /* single band GeoTIFF georeferenced by a pixel scale and a tie point, the way gdal writes them */
+    fn load_geotiff(contents: &[u8]) -> Dem {
+        let mut decoder = Decoder::new(Cursor::new(contents)).expect("elevation model is not a valid TIFF file");
+
+        let (width, height) = decoder.dimensions().unwrap();
+        let scale = decoder
+            .get_tag_f64_vec(Tag::ModelPixelScaleTag)
+            .expect("GeoTIFF has no pixel scale - it is not georeferenced");
+        let tiepoint = decoder
+            .get_tag_f64_vec(Tag::ModelTiepointTag)
+            .expect("GeoTIFF has no tie point - it is not georeferenced");
+        let nodata = decoder
+            .get_tag_ascii_string(Tag::GdalNodata)
+            .ok()
+            .and_then(|nodata| nodata.trim_matches(char::from(0)).trim().parse::<f64>().ok());
+
+        let values: Vec<f64> = match decoder.read_image().expect("could not decode the elevation model") {
+            DecodingResult::U8(values) => values.into_iter().map(f64::from).collect(),
+            DecodingResult::U16(values) => values.into_iter().map(f64::from).collect(),
+            DecodingResult::U32(values) => values.into_iter().map(f64::from).collect(),
+            DecodingResult::U64(values) => values.into_iter().map(|value| value as f64).collect(),
+            DecodingResult::F32(values) => values.into_iter().map(f64::from).collect(),
+            DecodingResult::F64(values) => values,
+            DecodingResult::I8(values) => values.into_iter().map(f64::from).collect(),
+            DecodingResult::I16(values) => values.into_iter().map(f64::from).collect(),
+            DecodingResult::I32(values) => values.into_iter().map(f64::from).collect(),
+            DecodingResult::I64(values) => values.into_iter().map(|value| value as f64).collect(),
+        };
+        assert!(values.len() == (width * height) as usize, "expected a single band elevation model");
+
+        /* the tie point maps the corner of pixel (i, j) onto (x, y) */
+        let [column, row, _, x, y] = [tiepoint[0], tiepoint[1], tiepoint[2], tiepoint[3], tiepoint[4]];
+
+        Dem {
+            width: width as usize,
+            height: height as usize,
+            origin: [x + (0.5 - column) * scale[0], y - (0.5 - row) * scale[1]],
+            cell_size: [scale[0], scale[1]],
+            elevations: values
+                .into_iter()
+                .map(|elevation| if elevation.is_nan() || Some(elevation) == nodata { None } else { Some(elevation) })
+                .collect(),
+        }
+    }
+
+    fn lerp(start: f64, end: f64, fraction: f64) -> f64 {
+        start + (end - start) * fraction
+    }
+
+    /* the end has no weight at a fraction of 0, so missing data there does not matter */
+    fn blend(start: Option<f64>, end: Option<f64>, fraction: f64) -> Option<f64> {
+        if fraction == 0.0 {
+            return start;
+        }
+        Some(lerp(start?, end?, fraction))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        /* 3x3 grid of 1 degree cells centered on (0, 0), with no data in the bottom right cell */
+        fn grid() -> Dem {
+            load_ascii_grid(
+                "ncols 3\nnrows 3\nxllcenter -1\nyllcenter -1\ncellsize 1\nNODATA_value -9999\n\
+                 10 20 30\n\
+                 40 50 60\n\
+                 70 80 -9999\n",
+            )
+        }
+
+        #[test]
+        fn samples_cell_centers() {
+            let dem = grid();
+            assert_eq!(dem.sample(-1.0, 1.0), Some(10.0));
+            assert_eq!(dem.sample(-1.0, 0.0), Some(40.0));
+            assert_eq!(dem.sample(-1.0, -1.0), Some(70.0));
+        }
+
+        #[test]
+        fn interpolates_between_cell_centers() {
+            let dem = grid();
+            assert_eq!(dem.sample(-0.5, 1.0), Some(15.0));
+            assert_eq!(dem.sample(-0.5, 0.5), Some(30.0));
+        }
+
+        #[test]
+        fn has_no_elevation_outside_the_grid() {
+            let dem = grid();
+            assert_eq!(dem.sample(-1.5, 0.0), None);
+            assert_eq!(dem.sample(0.0, 1.5), None);
+            assert_eq!(dem.sample(1.01, 0.0), None);
+        }
+
+        #[test]
+        fn has_no_elevation_next_to_missing_data() {
+            let dem = grid();
+            assert_eq!(dem.sample(1.0, -1.0), None);
+            assert_eq!(dem.sample(0.5, -0.5), None);
+            assert_eq!(dem.sample(0.5, -1.0), None);
+            assert_eq!(dem.sample(0.5, 0.5), Some(40.0));
+        }
+
+        #[test]
+        fn ignores_missing_data_without_weight() {
+            let dem = grid();
+            assert_eq!(dem.sample(0.0, 0.0), Some(50.0));
+            assert_eq!(dem.sample(0.0, -1.0), Some(80.0));
+            assert_eq!(dem.sample(0.0, -0.5), Some(65.0));
+            assert_eq!(dem.sample(1.0, 0.0), Some(60.0));
+        }
+    }
+}
@@ -51,7 +51,7 @@ pub mod format_json {
         map.insert("type".into(), Value::String("FeatureCollection".to_owned()));
 
         for walkable in walkables {
-            feature_collection.push(format_walkable_feature(&walkable));
+            feature_collection.push(format_walkable_feature(walkable));
         }
 
         map.insert(
@@ -84,6 +84,12 @@ pub mod format_json {
                 "intersection points": intersection_points,
                 "lights": walkable.lights.iter().map(|point| [point.x(), point.y()]).collect::<Vec<_>>(),
                 "id": walkable.id.as_ref().map_or("NONE".to_string(), |id| id.to_string()), 
+                "buildings": walkable.buildings.iter().map(|point| [point.x(), point.y()]).collect::<Vec<_>>(),
+                "street_name": walkable.street_name,
+                "sidewalk_width": walkable.properties.sidewalk_width,
+                "curb_ramps": walkable.properties.curb_ramps,
+                "stairs": walkable.properties.stairs,
+                "surface": walkable.properties.surface,
                 /* uncomment the bottom lines for color options for debugging */
                 // "stroke": random_hex_color(),
                 // "stroke-width": 2
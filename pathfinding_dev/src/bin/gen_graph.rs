@@ -2,6 +2,7 @@
  *  internal modules
  */
 
+mod elevation;
 mod format_information;
 mod load;
 mod utils;
@@ -12,6 +13,8 @@ use std::io::Write;
 /*
  *  internal crates
  */
+use elevation::dem::load_dem;
+use graph_format::{write_graph, BinaryEdge, GraphBody, GraphHeader, SourceHash, GRAPH_FORMAT_VERSION};
 use load::types::{F64Wrapper, HashablePoint, Walkable};
 use load::updated_load::get_features;
 // use serde_json::{json, Value};
@@ -21,6 +24,7 @@ use utils::utility_fns::{get_base_dir, open_file};
 /*
  *  external crates
  */
+use geo::{algorithm::convex_hull::qhull::quick_hull, coord, Coord, EuclideanDistance, HaversineDistance, HaversineLength};
 use petgraph::{
     graph::{NodeIndex, UnGraph},
     Undirected,
@@ -31,33 +35,79 @@ use rocket::serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use flate2::write::GzEncoder;
 use flate2::Compression;
+use sha2::{Digest, Sha256};
 
-/**
- *  !! edges that are between nodes that belong to the same street have an edge weight of 1.0
+/*
+ *  GraphEdge        : weight stored on every edge of the navigation graph
+ *  length           : haversine length of the edge in meters
+ *  safety           : extra cost (in meters) added on top of the length for poorly lit sidewalks.
+ *                     keeping it separate from the length lets the backend report real walking
+ *                     distances while still preferring well lit routes.
+ *  light_density    : lights per 100 meters along the sidewalk the edge belongs to
+ *  building_density : buildings per 100 meters along the sidewalk the edge belongs to
+ *  street_name      : name of the street the sidewalk runs along, when the source data has one
+ *  light_count      : lights of the sidewalk that are closest to this edge
+ *  building_count   : buildings of the sidewalk that are closest to this edge
+ *  sidewalk_width   : narrowest clear width of the sidewalk in meters, when known
+ *  curb_ramps       : whether the sidewalk has curb ramps, when known
+ *  stairs           : the edge is part of a flight of steps
+ *  surface          : surface type of the sidewalk, when known
  */
-const INTERNAL_SIDEWALK_EDGE_WEIGHT: f64 = 1.0;
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "rocket::serde")]
+struct GraphEdge {
+    length: f64,
+    safety: f64,
+    light_density: f64,
+    building_density: f64,
+    street_name: Option<String>,
+    light_count: usize,
+    building_count: usize,
+    sidewalk_width: Option<f64>,
+    curb_ramps: Option<bool>,
+    stairs: bool,
+    surface: Option<String>,
+}
 
+/*
+ *  GraphSerialize : navigation graph written to output.json.gz with --json
+ *  service_area   : closed ring of the polygon the graph covers - the backend rejects routes
+ *                   starting or ending outside of it
+ *  elevations     : elevation in meters of every node, in the order of nodes
+ */
 #[derive(Serialize, Deserialize)]
 #[serde(crate = "rocket::serde")]
 struct GraphSerialize {
     nodes: Vec<geo::Point>,
-    edges: Vec<(geo::Point, geo::Point, f64)>,
+    edges: Vec<(geo::Point, geo::Point, GraphEdge)>,
+    service_area: Vec<geo::Point>,
+    elevations: Vec<Option<f64>>,
 }
 
 use std::env;
 
-const CORRECT_SCRIPT_PARAM_NUM: usize = 2;
+const MIN_SCRIPT_PARAM_NUM: usize = 2;
+const MAX_SCRIPT_PARAM_NUM: usize = 3;
 const DATA_FILE_INDICATOR: usize = 1;
+const POLYGON_FILE_INDICATOR: usize = 2;
+/* also writes the old gzipped JSON graph, e.g. to inspect it or to feed an older backend */
+const JSON_EXPORT_FLAG: &str = "--json";
+/* --dem=path_to_elevation_model samples the elevation of every node from a GeoTIFF or ESRI ASCII grid */
+const DEM_FLAG: &str = "--dem=";
+const BINARY_OUTPUT_FILE: &str = "output.graph";
+const JSON_OUTPUT_FILE: &str = "output.json.gz";
 fn main() {
     let base_path = get_base_dir();
 
-    let args: Vec<String> = env::args().collect();
+    let export_json = env::args().any(|arg| arg == JSON_EXPORT_FLAG);
+    let dem_file = env::args().find_map(|arg| arg.strip_prefix(DEM_FLAG).map(|file| file.to_owned()));
+    let args: Vec<String> = env::args().filter(|arg| arg != JSON_EXPORT_FLAG && !arg.starts_with(DEM_FLAG)).collect();
 
-    if args.len() != CORRECT_SCRIPT_PARAM_NUM {
-        panic!("usage: cargo run [--release] --bin gen_graph -- path_to_geojson_file.geojson");
+    if args.len() < MIN_SCRIPT_PARAM_NUM || args.len() > MAX_SCRIPT_PARAM_NUM {
+        panic!("usage: cargo run [--release] --bin gen_graph -- path_to_geojson_file.geojson [path_to_bounding_polygon.json] [--json] [--dem=path_to_elevation_model.tif]");
     }
 
-    let filepath = base_path + "/" + &args[DATA_FILE_INDICATOR];
+    let filepath = base_path.clone() + "/" + &args[DATA_FILE_INDICATOR];
 
     let file_contents = match open_file(&filepath) {
         Ok(contents) => contents,
@@ -65,7 +115,7 @@ fn main() {
     };
 
     let sidewalks = get_features(&file_contents);
-    assert!(sidewalks.len() > 0);
+    assert!(!sidewalks.is_empty());
 
     let walkable_map = create_walkable_map(&sidewalks);
 
@@ -77,7 +127,7 @@ fn main() {
     let mut index_to_point_map: std::collections::HashMap<NodeIndex, geo::Point> =
         std::collections::HashMap::new();
 
-    let mut graph: petgraph::Graph<geo::Point, f64, Undirected> = UnGraph::new_undirected();
+    let mut graph: petgraph::Graph<geo::Point, GraphEdge, Undirected> = UnGraph::new_undirected();
 
     create_paths(
         &mut graph,
@@ -97,36 +147,46 @@ fn main() {
         .node_indices()
         .map(|n| geo::Point::new(graph[n].x(), graph[n].y()))
         .collect::<Vec<_>>();
-    let edges: Vec<(geo::Point, geo::Point, f64)> = graph
-        .edge_indices()
-        .map(|e| {
-            let (source, target) = graph.edge_endpoints(e).unwrap();
-            let weight = *graph.edge_weight(e).unwrap();
-            (
-                geo::Point::new(graph[source].x(), graph[source].y()),
-                geo::Point::new(graph[target].x(), graph[target].y()),
-                weight,
-            )
-        })
-        .collect();
 
-    let serializeable_graph = GraphSerialize {
-        nodes: nodes,
-        edges: edges,
+    let mut source_hashes = vec![hash_source(&args[DATA_FILE_INDICATOR], file_contents.as_bytes())];
+    let elevations: Vec<Option<f64>> = match &dem_file {
+        Some(dem_file) => {
+            let dem_path = base_path.clone() + "/" + dem_file;
+            let dem_contents = match std::fs::read(&dem_path) {
+                Ok(contents) => contents,
+                Err(err) => panic!("Could not get elevation model contents with error: {}", err),
+            };
+            source_hashes.push(hash_source(dem_file, &dem_contents));
+
+            let dem = load_dem(&dem_path, &dem_contents);
+            let elevations: Vec<Option<f64>> = nodes.iter().map(|point| dem.sample(point.x(), point.y())).collect();
+            println!(
+                "sampled the elevation of {} of {} nodes",
+                elevations.iter().filter(|elevation| elevation.is_some()).count(),
+                nodes.len()
+            );
+            elevations
+        }
+        None => vec![None; nodes.len()],
+    };
+    let service_area = match args.get(POLYGON_FILE_INDICATOR) {
+        Some(polygon_file) => {
+            let polygon_path = base_path + "/" + polygon_file;
+            let polygon_contents = match open_file(&polygon_path) {
+                Ok(contents) => contents,
+                Err(err) => panic!("Could not get polygon file contents with error: {}", err),
+            };
+            source_hashes.push(hash_source(polygon_file, polygon_contents.as_bytes()));
+            load_service_area(&polygon_contents)
+        }
+        None => hull_service_area(&nodes),
     };
 
-    let json = serde_json::to_string_pretty(&serializeable_graph).unwrap();
-
-    // let minified_json = json.replace(" ", "").replace("\n", "");
-
-    // println!("{minified_json}");
-
-    let compressed_file = std::fs::File::create("output.json.gz").unwrap();
-    let mut encoder = GzEncoder::new(compressed_file, Compression::default());
+    write_binary_graph(&graph, &nodes, &service_area, &elevations, source_hashes);
 
-    // Write the compressed data
-    encoder.write_all(json.as_bytes()).unwrap();
-    encoder.finish().unwrap();
+    if export_json {
+        write_json_graph(&graph, nodes, service_area, elevations);
+    }
 
     // let starting_point = point_to_index_map.get(&HashablePoint {
     //     x: F64Wrapper(-71.0521206843562),
@@ -186,16 +246,18 @@ fn main() {
 
 #[allow(dead_code)]
 fn create_paths(
-    graph: &mut petgraph::Graph<geo::Point, f64, Undirected>,
+    graph: &mut petgraph::Graph<geo::Point, GraphEdge, Undirected>,
     sidewalks: &Vec<Walkable>,
     point_to_index_map: &mut std::collections::HashMap<HashablePoint, NodeIndex>,
     index_to_point_map: &mut std::collections::HashMap<NodeIndex, geo::Point>,
 ) {
     for sidewalk in sidewalks {
         let line_string = sidewalk.segments.clone();
+        let light_counts = count_per_segment(&sidewalk.lights, sidewalk);
+        let building_counts = count_per_segment(&sidewalk.buildings, sidewalk);
 
         let mut prev: Option<NodeIndex> = None;
-        for point in line_string.points() {
+        for (position, point) in line_string.points().enumerate() {
             let hashable_point = HashablePoint {
                 x: F64Wrapper(point.x()),
                 y: F64Wrapper(point.y()),
@@ -210,7 +272,7 @@ fn create_paths(
                 }
             };
 
-            let _ = match index_to_point_map.get(&point_index) {
+            match index_to_point_map.get(&point_index) {
                 Some(_) => (),
                 None => {
                     index_to_point_map.insert(point_index, point);
@@ -218,7 +280,9 @@ fn create_paths(
             };
 
             if let Some(prev_index) = prev {
-                graph.add_edge(prev_index, point_index, INTERNAL_SIDEWALK_EDGE_WEIGHT);
+                let length = graph[prev_index].haversine_distance(&point);
+                let edge = create_edge(sidewalk, length, light_counts[position - 1], building_counts[position - 1]);
+                graph.add_edge(prev_index, point_index, edge);
             }
 
             prev = Some(point_index);
@@ -261,7 +325,7 @@ fn create_walkable_map(sidewalks: &Vec<Walkable>) -> HashMap<Uuid, Walkable> {
         - update the other hashmap for pathfinding result
 */
 fn connect_intersections(
-    graph: &mut petgraph::Graph<geo::Point, f64, Undirected>,
+    graph: &mut petgraph::Graph<geo::Point, GraphEdge, Undirected>,
     sidewalks: &Vec<Walkable>,
     point_to_index_map: &mut std::collections::HashMap<HashablePoint, NodeIndex>,
     walkable_map: &HashMap<Uuid, Walkable>,
@@ -286,7 +350,7 @@ fn connect_intersections(
                 };
 
                 let intersecting_sidewalk_point = match find_point(
-                    &intersecting_sidewalk,
+                    intersecting_sidewalk,
                     &intersection_point.intersection_point,
                 ) {
                     Some(point) => point,
@@ -323,16 +387,17 @@ fn connect_intersections(
                     }
                 };
 
-                let rating = assign_rating(intersecting_sidewalk);
-
                 if graph.contains_edge(intersecting_index_point, intersecting_sidewalk_index_point)
                 {
                     continue;
                 } else {
+                    let length = graph[intersecting_index_point]
+                        .haversine_distance(&graph[intersecting_sidewalk_index_point]);
+                    let edge = create_edge(intersecting_sidewalk, length, 0, 0);
                     graph.add_edge(
                         intersecting_index_point,
                         intersecting_sidewalk_index_point,
-                        rating,
+                        edge,
                     );
                 }
             }
@@ -340,37 +405,209 @@ fn connect_intersections(
     }
 }
 
-fn assign_rating(intersecting_sidewalk: &Walkable) -> f64 {
-    const DEFAULT_SCORE: f64 = 1.0;
-    const MAX_SCORE: f64 = 10.0;
-    const SCALING_FACTOR: f64 = 60.0;
-    const MULTIPLYER: usize = 2;
+/* reads the bounding polygon produced by make_polygon - it is stored as a single LineString feature */
+fn load_service_area(file_contents: &str) -> Vec<geo::Point> {
+    let polygon_ls: Vec<Walkable> = get_features(file_contents);
+    assert!(polygon_ls.len() == 1);
 
-    if intersecting_sidewalk.lights.len() == 0 {
-        return DEFAULT_SCORE;
+    let mut ring: Vec<geo::Point> = polygon_ls[0].segments.points().collect();
+    if ring.first() != ring.last() {
+        ring.push(ring[0]);
     }
+    ring
+}
 
-    /* a simple formula for determining edge weights -> capped at a max score of 10 */
-    let raw_rating = (0 + (intersecting_sidewalk.lights.len() * MULTIPLYER)) as f64;
-    let rating = (raw_rating / SCALING_FACTOR).round();
+/* sha256 of an input file, stored in the graph header to tell which data a graph was built from */
+fn hash_source(file: &str, contents: &[u8]) -> SourceHash {
+    let digest = Sha256::digest(contents);
 
-    if rating > MAX_SCORE {
-        return MAX_SCORE;
-    } else {
-        return rating;
+    SourceHash {
+        file: file.to_owned(),
+        sha256: digest.iter().map(|byte| format!("{:02x}", byte)).collect(),
     }
 }
 
-fn is_point_equivalent(point1: &geo::Point, point2: &geo::Point) -> bool {
-    point1.x() == point2.x() && point1.y() == point2.y()
+/* [min_lng, min_lat, max_lng, max_lat] of the nodes */
+fn bounding_box(nodes: &[geo::Point]) -> [f64; 4] {
+    nodes.iter().fold(
+        [f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY],
+        |[min_x, min_y, max_x, max_y], point| {
+            [min_x.min(point.x()), min_y.min(point.y()), max_x.max(point.x()), max_y.max(point.y())]
+        },
+    )
 }
 
-fn find_point(sidewalk: &Walkable, inter_point: &geo::Point) -> Option<geo::Point> {
-    for point in sidewalk.segments.clone().points() {
-        if is_point_equivalent(&point, &inter_point) {
-            return Some(point);
+/* nodes are written in petgraph order so a node's id is its NodeIndex */
+fn write_binary_graph(
+    graph: &petgraph::Graph<geo::Point, GraphEdge, Undirected>,
+    nodes: &[geo::Point],
+    service_area: &[geo::Point],
+    elevations: &[Option<f64>],
+    source_hashes: Vec<SourceHash>,
+) {
+    let edges: Vec<BinaryEdge> = graph
+        .edge_indices()
+        .map(|e| {
+            let (source, target) = graph.edge_endpoints(e).unwrap();
+            let weight = &graph[e];
+            BinaryEdge {
+                source: source.index() as u32,
+                target: target.index() as u32,
+                length: weight.length,
+                safety: weight.safety,
+                light_density: weight.light_density,
+                building_density: weight.building_density,
+                street_name: weight.street_name.clone(),
+                light_count: weight.light_count as u32,
+                building_count: weight.building_count as u32,
+                sidewalk_width: weight.sidewalk_width,
+                curb_ramps: weight.curb_ramps,
+                stairs: weight.stairs,
+                surface: weight.surface.clone(),
+            }
+        })
+        .collect();
+
+    let built_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let header = GraphHeader {
+        format_version: GRAPH_FORMAT_VERSION,
+        built_at,
+        source_hashes,
+        bounding_box: bounding_box(nodes),
+        node_count: nodes.len() as u64,
+        edge_count: edges.len() as u64,
+    };
+
+    let body = GraphBody {
+        nodes: nodes.iter().map(|point| [point.x(), point.y()]).collect(),
+        edges,
+        service_area: service_area.iter().map(|point| [point.x(), point.y()]).collect(),
+        elevations: elevations.to_vec(),
+    };
+
+    if let Err(err) = write_graph(BINARY_OUTPUT_FILE, &header, &body) {
+        panic!("could not write the binary graph: {}", err);
+    }
+}
+
+fn write_json_graph(
+    graph: &petgraph::Graph<geo::Point, GraphEdge, Undirected>,
+    nodes: Vec<geo::Point>,
+    service_area: Vec<geo::Point>,
+    elevations: Vec<Option<f64>>,
+) {
+    let edges: Vec<(geo::Point, geo::Point, GraphEdge)> = graph
+        .edge_indices()
+        .map(|e| {
+            let (source, target) = graph.edge_endpoints(e).unwrap();
+            let weight = graph.edge_weight(e).unwrap().clone();
+            (
+                geo::Point::new(graph[source].x(), graph[source].y()),
+                geo::Point::new(graph[target].x(), graph[target].y()),
+                weight,
+            )
+        })
+        .collect();
+
+    let serializeable_graph = GraphSerialize {
+        nodes,
+        edges,
+        service_area,
+        elevations,
+    };
+
+    let json = serde_json::to_string_pretty(&serializeable_graph).unwrap();
+
+    let compressed_file = std::fs::File::create(JSON_OUTPUT_FILE).unwrap();
+    let mut encoder = GzEncoder::new(compressed_file, Compression::default());
+
+    // Write the compressed data
+    encoder.write_all(json.as_bytes()).unwrap();
+    encoder.finish().unwrap();
+}
+
+/* without a bounding polygon the service area is the convex hull of the graph's nodes */
+fn hull_service_area(nodes: &[geo::Point]) -> Vec<geo::Point> {
+    let mut coords: Vec<Coord> = nodes
+        .iter()
+        .map(|point| -> Coord {
+            coord! {x: point.x(), y: point.y()}
+        })
+        .collect();
+
+    quick_hull(&mut coords).points().collect()
+}
+
+fn create_edge(sidewalk: &Walkable, length: f64, light_count: usize, building_count: usize) -> GraphEdge {
+    let light_density = get_density(sidewalk.lights.len(), sidewalk);
+
+    GraphEdge {
+        length,
+        safety: assign_safety_cost(light_density, length),
+        light_density,
+        building_density: get_density(sidewalk.buildings.len(), sidewalk),
+        street_name: sidewalk.street_name.clone(),
+        light_count,
+        building_count,
+        sidewalk_width: sidewalk.properties.sidewalk_width,
+        curb_ramps: sidewalk.properties.curb_ramps,
+        stairs: sidewalk.properties.stairs,
+        surface: sidewalk.properties.surface.clone(),
+    }
+}
+
+/* number of features closest to every segment (pair of consecutive points) of the sidewalk */
+fn count_per_segment(features: &Vec<geo::Point>, sidewalk: &Walkable) -> Vec<usize> {
+    let lines: Vec<geo::Line> = sidewalk.segments.lines().collect();
+    let mut counts = vec![0; lines.len()];
+
+    for feature in features {
+        let closest = lines
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| a.euclidean_distance(feature).total_cmp(&b.euclidean_distance(feature)));
+
+        if let Some((position, _)) = closest {
+            counts[position] += 1;
         }
     }
 
-    None
+    counts
+}
+
+/* number of features per 100 meters of sidewalk */
+fn get_density(feature_count: usize, sidewalk: &Walkable) -> f64 {
+    const DENSITY_DISTANCE: f64 = 100.0;
+
+    let sidewalk_length = sidewalk.segments.haversine_length();
+    if sidewalk_length == 0.0 {
+        return 0.0;
+    }
+
+    feature_count as f64 / (sidewalk_length / DENSITY_DISTANCE)
+}
+
+fn assign_safety_cost(light_density: f64, length: f64) -> f64 {
+    const DARKNESS_PENALTY: f64 = 1.0;
+    const WELL_LIT_DENSITY: f64 = 4.0;
+
+    /*
+     * a sidewalk without lights costs DARKNESS_PENALTY extra meters for every meter walked.
+     * the penalty shrinks linearly until the sidewalk reaches WELL_LIT_DENSITY lights per 100 meters
+     */
+    let lit_factor = (light_density / WELL_LIT_DENSITY).min(1.0);
+
+    length * DARKNESS_PENALTY * (1.0 - lit_factor)
+}
+
+fn is_point_equivalent(point1: &geo::Point, point2: &geo::Point) -> bool {
+    point1.x() == point2.x() && point1.y() == point2.y()
+}
+
+fn find_point(sidewalk: &Walkable, inter_point: &geo::Point) -> Option<geo::Point> {
+    sidewalk.segments.clone().points().find(|&point| is_point_equivalent(&point, inter_point))
 }
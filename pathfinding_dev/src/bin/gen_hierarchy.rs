@@ -0,0 +1,235 @@
+/*
+ *  internal modules
+ */
+
+#[allow(dead_code)]
+mod utils;
+
+use ordered_float::OrderedFloat;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::env;
+
+/*
+ *  internal crates
+ */
+use graph_format::{
+    read_binary_graph, write_hierarchy, HierarchyArc, HierarchyBody, HierarchyHeader, HIERARCHY_FORMAT_VERSION,
+};
+use utils::utility_fns::get_base_dir;
+
+/*
+ *  external crates
+ */
+use indicatif::{ProgressBar, ProgressStyle};
+
+const CORRECT_SCRIPT_PARAM_NUM: usize = 2;
+const GRAPH_FILE_INDICATOR: usize = 1;
+const HIERARCHY_OUTPUT_FILE: &str = "output.hierarchy";
+/* witness searches give up after settling this many nodes - a missed witness only adds an unneeded shortcut */
+const WITNESS_SETTLE_LIMIT: usize = 500;
+
+/*
+ *  contraction hierarchy over the walking distance of the graph written by gen_graph.
+ *
+ *  nodes are contracted one at a time, least important first. Contracting a node removes it from
+ *  the remaining graph and adds a shortcut between two of its neighbors whenever the path through
+ *  the node is the only shortest path between them. The arcs a node still has when it is
+ *  contracted all lead to more important nodes - the backend only searches along those arcs from
+ *  both ends of a route, which settles a tiny fraction of the nodes plain A* has to look at.
+ *
+ *  the hierarchy only holds for the plain walking distance. The backend falls back to A* whenever
+ *  a route is weighted by anything else (safety preference, hazards, alternatives).
+ */
+fn main() {
+    let base_path = get_base_dir();
+
+    let args: Vec<String> = env::args().collect();
+
+    if args.len() != CORRECT_SCRIPT_PARAM_NUM {
+        panic!("usage: cargo run [--release] --bin gen_hierarchy -- path_to_output.graph");
+    }
+
+    let (graph_header, graph_body) = match read_binary_graph(&(base_path + "/" + &args[GRAPH_FILE_INDICATOR])) {
+        Ok(graph) => graph,
+        Err(err) => panic!("could not read the graph: {}", err),
+    };
+    let node_count = graph_body.nodes.len();
+
+    let mut remaining: Vec<HashMap<u32, HierarchyArc>> = vec![HashMap::new(); node_count];
+    for edge in graph_body.edges.iter() {
+        if edge.source != edge.target {
+            add_arc(&mut remaining, edge.source, edge.target, edge.length, None);
+        }
+    }
+
+    let mut contracted_neighbors = vec![0usize; node_count];
+    let mut queue: BinaryHeap<Reverse<QueueEntry>> = (0..node_count as u32)
+        .map(|node| QueueEntry::queued(priority(node, &remaining, &contracted_neighbors), node))
+        .collect();
+
+    let pb = ProgressBar::new(node_count as u64);
+
+    pb.set_style(
+        ProgressStyle::with_template(
+            "[{elapsed_precise}] {bar:40.cyan/blue} {pos:>7}/{len:7} {msg}",
+        )
+        .unwrap()
+        .progress_chars("##-"),
+    );
+
+    let mut ranks = vec![0u32; node_count];
+    let mut upward: Vec<Vec<HierarchyArc>> = vec![vec![]; node_count];
+    let mut rank = 0;
+
+    while let Some(Reverse(entry)) = queue.pop() {
+        /* contracting the neighbors may have changed the priority since the node was queued */
+        let current_priority = priority(entry.node, &remaining, &contracted_neighbors);
+        if let Some(Reverse(next)) = queue.peek() {
+            if current_priority > *next.cost {
+                queue.push(QueueEntry::queued(current_priority, entry.node));
+                continue;
+            }
+        }
+
+        for (from, to, weight) in find_shortcuts(entry.node, &remaining) {
+            add_arc(&mut remaining, from, to, weight, Some(entry.node));
+        }
+
+        let arcs: Vec<HierarchyArc> = remaining[entry.node as usize].drain().map(|(_, arc)| arc).collect();
+        for arc in arcs.iter() {
+            remaining[arc.target as usize].remove(&entry.node);
+            contracted_neighbors[arc.target as usize] += 1;
+        }
+
+        upward[entry.node as usize] = arcs;
+        ranks[entry.node as usize] = rank;
+        rank += 1;
+        pb.inc(1);
+    }
+
+    pb.finish();
+
+    let mut first_arc = Vec::with_capacity(node_count + 1);
+    let mut arcs = vec![];
+    for node_arcs in upward {
+        first_arc.push(arcs.len() as u32);
+        arcs.extend(node_arcs);
+    }
+    first_arc.push(arcs.len() as u32);
+
+    let shortcut_count = arcs.iter().filter(|arc| arc.middle.is_some()).count();
+    println!("contracted {} nodes with {} shortcuts", node_count, shortcut_count);
+
+    let header = HierarchyHeader {
+        format_version: HIERARCHY_FORMAT_VERSION,
+        graph_built_at: graph_header.built_at,
+        node_count: node_count as u64,
+        shortcut_count: shortcut_count as u64,
+    };
+
+    let body = HierarchyBody {
+        ranks,
+        first_arc,
+        arcs,
+    };
+
+    if let Err(err) = write_hierarchy(HIERARCHY_OUTPUT_FILE, &header, &body) {
+        panic!("could not write the contraction hierarchy: {}", err);
+    }
+}
+
+/*
+ *  QueueEntry: entry of the contraction queue and of the witness search, wrapped in Reverse so the heap pops the lowest cost first
+ *  cost      : priority of the node in the contraction queue, distance in the witness search
+ */
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct QueueEntry {
+    cost: OrderedFloat<f64>,
+    node: u32,
+}
+
+impl QueueEntry {
+    fn queued(cost: f64, node: u32) -> Reverse<QueueEntry> {
+        Reverse(QueueEntry { cost: OrderedFloat(cost), node })
+    }
+}
+
+/* keeps the cheapest arc between two nodes in both directions */
+fn add_arc(remaining: &mut [HashMap<u32, HierarchyArc>], from: u32, to: u32, weight: f64, middle: Option<u32>) {
+    let is_cheaper = match remaining[from as usize].get(&to) {
+        Some(arc) => weight < arc.weight,
+        None => true,
+    };
+
+    if is_cheaper {
+        remaining[from as usize].insert(to, HierarchyArc { target: to, weight, middle });
+        remaining[to as usize].insert(from, HierarchyArc { target: from, weight, middle });
+    }
+}
+
+/* edge difference plus the number of contracted neighbors, which spreads the contraction across the graph */
+fn priority(node: u32, remaining: &[HashMap<u32, HierarchyArc>], contracted_neighbors: &[usize]) -> f64 {
+    let shortcuts = find_shortcuts(node, remaining).len() as f64;
+    let degree = remaining[node as usize].len() as f64;
+
+    shortcuts - degree + contracted_neighbors[node as usize] as f64
+}
+
+/* pairs of neighbors whose shortest connection goes through the node */
+fn find_shortcuts(node: u32, remaining: &[HashMap<u32, HierarchyArc>]) -> Vec<(u32, u32, f64)> {
+    let neighbors: Vec<(u32, f64)> = remaining[node as usize].values().map(|arc| (arc.target, arc.weight)).collect();
+    let mut shortcuts = vec![];
+
+    for (position, (from, from_weight)) in neighbors.iter().enumerate() {
+        let targets = &neighbors[position + 1..];
+        let max_cost = match targets.iter().map(|(_, weight)| *weight).max_by(|a, b| a.total_cmp(b)) {
+            Some(weight) => from_weight + weight,
+            None => continue,
+        };
+
+        let distances = witness_search(*from, node, max_cost, remaining);
+        for (to, to_weight) in targets {
+            let through_node = from_weight + to_weight;
+            if distances.get(to).is_none_or(|distance| *distance > through_node) {
+                shortcuts.push((*from, *to, through_node));
+            }
+        }
+    }
+
+    shortcuts
+}
+
+/* dijkstra from a neighbor of the node that avoids the node, limited to max_cost */
+fn witness_search(source: u32, avoided: u32, max_cost: f64, remaining: &[HashMap<u32, HierarchyArc>]) -> HashMap<u32, f64> {
+    let mut distances: HashMap<u32, f64> = HashMap::new();
+    let mut open = BinaryHeap::new();
+    let mut settled = 0;
+
+    distances.insert(source, 0.0);
+    open.push(QueueEntry::queued(0.0, source));
+
+    while let Some(Reverse(entry)) = open.pop() {
+        if *entry.cost > max_cost || settled >= WITNESS_SETTLE_LIMIT {
+            break;
+        }
+        if *entry.cost > *distances.get(&entry.node).unwrap_or(&f64::INFINITY) {
+            continue;
+        }
+        settled += 1;
+
+        for arc in remaining[entry.node as usize].values() {
+            if arc.target == avoided {
+                continue;
+            }
+
+            let cost = *entry.cost + arc.weight;
+            if cost < *distances.get(&arc.target).unwrap_or(&f64::INFINITY) {
+                distances.insert(arc.target, cost);
+                open.push(QueueEntry::queued(cost, arc.target));
+            }
+        }
+    }
+
+    distances
+}
@@ -3,7 +3,7 @@ mod load;
 mod utils;
 
 /* use geo::{Contains, Intersects, LineString, Polygon}; */
-use geo::{Contains, Intersects, LineString, Polygon};
+use geo::{Intersects, LineString, Polygon};
 use load::{types::Walkable, updated_load::get_features, updated_load::load_streets_json};
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use serde_json::{json, Map, Value};
@@ -66,8 +66,10 @@ fn main() {
     let features: Vec<Value> = contained_sidewalks.par_iter().map(|sidewalk| {
         let json = json!({
             "type": "Feature", 
-            /* assuming for now that there are no properties */
-            "properties": {}, 
+            /* the street name is the only property carried over from the source dataset */
+            "properties": {
+                "street_name": sidewalk.street_name
+            }, 
             "geometry": {
                 "type": "LineString", 
                 "coordinates": sidewalk.segments.0.iter().map(|point| [point.x, point.y]).collect::<Vec<_>>()
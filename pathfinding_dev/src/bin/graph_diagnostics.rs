@@ -0,0 +1,105 @@
+/*
+ *  internal modules
+ */
+
+#[allow(dead_code)]
+mod utils;
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, VecDeque};
+use std::env;
+
+/*
+ *  internal crates
+ */
+use graph_format::read_binary_graph;
+use utils::utility_fns::get_base_dir;
+
+const CORRECT_SCRIPT_PARAM_NUM: usize = 2;
+const GRAPH_FILE_INDICATOR: usize = 1;
+/* components printed besides the largest one - the long tail is usually single sidewalks */
+const LISTED_COMPONENTS: usize = 20;
+
+/*
+ *  connectivity report of a graph written by gen_graph.
+ *
+ *  every component besides the largest one is a part of the sidewalk network routes cannot leave
+ *  or reach. They usually come from sidewalks that stop a few centimeters short of each other in
+ *  the source data. The sample coordinate of each listed component can be pasted into a map to
+ *  find the gap. The backend reports the same numbers at /api/admin/graph_diagnostics.
+ */
+fn main() {
+    let base_path = get_base_dir();
+
+    let args: Vec<String> = env::args().collect();
+
+    if args.len() != CORRECT_SCRIPT_PARAM_NUM {
+        panic!("usage: cargo run [--release] --bin graph_diagnostics -- path_to_output.graph");
+    }
+
+    let (_, graph_body) = match read_binary_graph(&(base_path + "/" + &args[GRAPH_FILE_INDICATOR])) {
+        Ok(graph) => graph,
+        Err(err) => panic!("could not read the graph: {}", err),
+    };
+    let node_count = graph_body.nodes.len();
+
+    let mut neighbors: Vec<Vec<u32>> = vec![vec![]; node_count];
+    for edge in graph_body.edges.iter() {
+        neighbors[edge.source as usize].push(edge.target);
+        if edge.source != edge.target {
+            neighbors[edge.target as usize].push(edge.source);
+        }
+    }
+
+    let mut degree_distribution: BTreeMap<usize, usize> = BTreeMap::new();
+    for node_neighbors in neighbors.iter() {
+        *degree_distribution.entry(node_neighbors.len()).or_insert(0) += 1;
+    }
+
+    /* (node count, first node) of every component */
+    let mut components: Vec<(usize, usize)> = vec![];
+    let mut visited = vec![false; node_count];
+    for start in 0..node_count {
+        if visited[start] {
+            continue;
+        }
+
+        visited[start] = true;
+        let mut size = 0;
+        let mut queue = VecDeque::from([start]);
+        while let Some(node) = queue.pop_front() {
+            size += 1;
+            for neighbor in neighbors[node].iter() {
+                if !visited[*neighbor as usize] {
+                    visited[*neighbor as usize] = true;
+                    queue.push_back(*neighbor as usize);
+                }
+            }
+        }
+        components.push((size, start));
+    }
+    components.sort_by_key(|component| Reverse(component.0));
+
+    let largest = components.first().map(|(size, _)| *size).unwrap_or(0);
+    println!("nodes:                   {}", node_count);
+    println!("edges:                   {}", graph_body.edges.len());
+    println!("connected components:    {}", components.len());
+    println!("isolated nodes:          {}", degree_distribution.get(&0).unwrap_or(&0));
+    println!(
+        "largest component share: {:.2}%",
+        if node_count > 0 { 100.0 * largest as f64 / node_count as f64 } else { 0.0 }
+    );
+
+    println!("\ndegree distribution:");
+    for (degree, count) in degree_distribution.iter() {
+        println!("  {:>3}: {}", degree, count);
+    }
+
+    if components.len() > 1 {
+        println!("\nlargest components cut off from the main network (nodes at [lng, lat]):");
+        for (size, node) in components.iter().skip(1).take(LISTED_COMPONENTS) {
+            let [lng, lat] = graph_body.nodes[*node];
+            println!("  {:>6} nodes at [{:.6}, {:.6}]", size, lng, lat);
+        }
+    }
+}
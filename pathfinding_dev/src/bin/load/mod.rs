@@ -12,14 +12,25 @@ pub mod types {
         pub segments: LineString,
         pub lights: Vec<Point>,
         pub intersection_points: Vec<IntersectionPoint>,
+        pub buildings: Vec<Point>,
+        pub street_name: Option<String>,
+        pub properties: Properties,
     }
-    
-    #[derive(Debug, Clone)]
+
+    /*
+     *  Properties     : accessibility attributes of a sidewalk, when the source data has them
+     *  sidewalk_width : narrowest clear width of the sidewalk in meters
+     *  curb_ramps     : whether the sidewalk has curb ramps where it meets a crossing
+     *  stairs         : the walkable is a flight of steps
+     *  surface        : lowercase surface type, e.g. concrete, asphalt, brick or gravel
+     */
+    #[derive(Debug, Clone, Default)]
     #[allow(dead_code)]
     pub struct Properties {
-        left_sidewalk_width: u32, 
-        right_sidewalk_width: u32, 
-        street_length: u32
+        pub sidewalk_width: Option<f64>,
+        pub curb_ramps: Option<bool>,
+        pub stairs: bool,
+        pub surface: Option<String>,
     }
 
     #[derive(Debug, Clone)]
@@ -61,18 +72,12 @@ pub mod types {
      *                 the part of the street that is querying the spatial r-tree. Therefore, a HashSet
      *                 is used to avoid duplicates.
      */
-    #[derive(Eq, Hash, Debug)]
+    #[derive(PartialEq, Eq, Hash, Debug)]
     #[allow(dead_code)]
     pub struct HashablePoint {
         pub x: F64Wrapper,
         pub y: F64Wrapper,
     }
-
-    impl PartialEq for HashablePoint {
-        fn eq(&self, other: &Self) -> bool {
-            self.x.0.to_bits() == other.x.0.to_bits() && self.y.0.to_bits() == other.y.0.to_bits()
-        }
-    }
 }
 
 #[allow(dead_code)]
@@ -82,7 +87,15 @@ pub mod updated_load {
     use serde_json::{Map, Value};
     use std::collections::HashMap;
 
-    use super::types::{IntersectionPoint, Walkable};
+    use super::types::{IntersectionPoint, Properties, Walkable};
+
+    /* property names that hold the street name in the source street / sidewalk datasets */
+    const STREET_NAME_PROPERTIES: [&str; 5] = ["street_name", "name", "STREETNAME", "ST_NAME", "FULL_NAME"];
+    /* property names that hold a sidewalk width in meters - the narrowest one is kept */
+    const SIDEWALK_WIDTH_PROPERTIES: [&str; 4] = ["sidewalk_width", "width", "left_sidewalk_width", "right_sidewalk_width"];
+    /* property names that tell whether a sidewalk has curb ramps - kerb is the OpenStreetMap tag */
+    const CURB_RAMP_PROPERTIES: [&str; 3] = ["curb_ramps", "curb_ramp", "kerb"];
+    const SURFACE_PROPERTIES: [&str; 3] = ["surface", "SURFACE", "material"];
 
     fn load_coords(coord: &Value) -> geo::Coord {
         let curr_pair = coord.as_array().unwrap();
@@ -102,7 +115,7 @@ pub mod updated_load {
 
     fn get_geojson_contents(content: Map<String, Value>) -> Vec<Value> {
         /* get only the features part of geojson, ignores all other fields */
-        let (_, features): (&String, &Value) = content.iter().map(|obj| obj).nth(0).unwrap();
+        let (_, features): (&String, &Value) = content.iter().next().unwrap();
         assert!(features.is_array());
         features.as_array().unwrap().clone()
     }
@@ -119,6 +132,57 @@ pub mod updated_load {
         Some(points)
     }
 
+    fn get_street_name(feature: &Value) -> Option<String> {
+        STREET_NAME_PROPERTIES
+            .iter()
+            .filter_map(|property| feature["properties"][*property].as_str())
+            .map(|name| name.trim())
+            .find(|name| !name.is_empty())
+            .map(|name| name.to_owned())
+    }
+
+    /* sidewalk attributes used by the accessibility profiles of the backend - missing values stay unknown */
+    fn get_properties(feature: &Value) -> Properties {
+        let properties = &feature["properties"];
+
+        let sidewalk_width = SIDEWALK_WIDTH_PROPERTIES
+            .iter()
+            .filter_map(|property| match &properties[*property] {
+                Value::Number(width) => width.as_f64(),
+                Value::String(width) => width.trim().parse::<f64>().ok(),
+                _ => None,
+            })
+            .filter(|width| *width > 0.0)
+            .reduce(f64::min);
+
+        let curb_ramps = CURB_RAMP_PROPERTIES.iter().find_map(|property| match &properties[*property] {
+            Value::Bool(curb_ramps) => Some(*curb_ramps),
+            Value::String(curb_ramps) => match curb_ramps.trim().to_lowercase().as_str() {
+                "yes" | "true" | "lowered" | "flush" => Some(true),
+                "no" | "false" | "raised" => Some(false),
+                _ => None,
+            },
+            _ => None,
+        });
+
+        let stairs = properties["stairs"].as_bool().unwrap_or(false)
+            || properties["stairs"].as_str() == Some("yes")
+            || properties["highway"].as_str() == Some("steps");
+
+        let surface = SURFACE_PROPERTIES
+            .iter()
+            .filter_map(|property| properties[*property].as_str())
+            .map(|surface| surface.trim().to_lowercase())
+            .find(|surface| !surface.is_empty());
+
+        Properties {
+            sidewalk_width,
+            curb_ramps,
+            stairs,
+            surface,
+        }
+    }
+
     pub fn get_features(data: &str) -> Vec<Walkable> {
         let data: HashMap<&str, Value> = match serde_json::from_str(data) {
             Ok(data) => data,
@@ -156,7 +220,7 @@ pub mod updated_load {
 
                         IntersectionPoint {
                             intersection_point: point,
-                            intersecting_street_ids: intersecting_street_ids,
+                            intersecting_street_ids,
                         }
                     })
                     .collect(),
@@ -166,13 +230,10 @@ pub mod updated_load {
                 }
             };
 
-            let light_points = match collect_points(&sidewalk_map, "lights") {
-                Some(points) => points,
-                None => {
-                    // eprintln!("THERE WERE NO LIGHT POINTS FOUND (i.e property with name lights was none) - MAKE SURE THIS IS INTENTIONAL");
-                    vec![]
-                }
-            };
+            // eprintln!("THERE WERE NO LIGHT POINTS FOUND (i.e property with name lights was none) - MAKE SURE THIS IS INTENTIONAL");
+            let light_points = collect_points(&sidewalk_map, "lights").unwrap_or_default();
+
+            let building_points = collect_points(&sidewalk_map, "buildings").unwrap_or_default();
 
             let id_string: Option<uuid::Uuid> = match sidewalk_map["properties"]["id"].as_str() {
                 Some(id) => {
@@ -192,7 +253,10 @@ pub mod updated_load {
                 id: id_string,
                 segments: LineString::new(coord_points),
                 lights: light_points,
-                intersection_points: intersection_points,
+                intersection_points,
+                buildings: building_points,
+                street_name: get_street_name(&value),
+                properties: get_properties(&value),
             };
 
             sidewalks.push(curr_sidewalk);
@@ -245,7 +309,7 @@ pub mod updated_load {
 
                     IntersectionPoint {
                         intersection_point: point,
-                        intersecting_street_ids: intersecting_street_ids,
+                        intersecting_street_ids,
                     }
                 })
                 .collect();
@@ -258,7 +322,10 @@ pub mod updated_load {
                 },
                 segments: LineString::new(coord_points),
                 lights: light_points,
-                intersection_points: intersection_points,
+                intersection_points,
+                buildings: vec![],
+                street_name: get_street_name(&value),
+                properties: get_properties(&value),
             };
 
             walkables.push(curr_sidewalk);
@@ -302,7 +369,7 @@ pub mod updated_load {
     // }
 
     pub fn load_lights(file_contents: &str) -> Vec<geo::Coord<f64>> {
-        let json: serde_json::Value = serde_json::from_str(&file_contents)
+        let json: serde_json::Value = serde_json::from_str(file_contents)
             .expect("light JSON - expected JSON, input was not well formatted");
 
         let lights = json["transformed_coordinates"].as_array().unwrap();
@@ -408,6 +475,9 @@ pub mod updated_load {
                 segments: LineString::new(points),
                 lights: vec![],
                 intersection_points: vec![],
+                buildings: vec![],
+                street_name: get_street_name(feature),
+                properties: get_properties(feature),
             };
 
             streets.push(curr_street);
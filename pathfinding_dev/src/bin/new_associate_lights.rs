@@ -1,3 +1,6 @@
+/* still being written: the lights are loaded but not yet matched to the sidewalks */
+#![allow(unused_imports, unused_variables, unused_mut)]
+
 /*
  *  internal mods / crates
  */
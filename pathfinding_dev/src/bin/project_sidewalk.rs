@@ -75,10 +75,10 @@ fn main() {
         .map(|sidewalk| sidewalk.segments.clone())
         .collect();
 
-    let filtered_residential_streets: Vec<LineString> = residential_streets
+    let filtered_residential_streets: Vec<Walkable> = residential_streets
         .iter()
         .filter(|sidewalk| polygon.contains(&sidewalk.segments.to_owned()))
-        .map(|sidewalk| sidewalk.segments.clone())
+        .cloned()
         .collect();
 
     let street_spatial_tree = RTree::bulk_load(streets);
@@ -98,17 +98,17 @@ fn main() {
         .progress_chars("##-"),
     );
 
-    let only_sidewalkless_streets: Vec<&LineString> = filtered_copy
+    let only_sidewalkless_streets: Vec<&Walkable> = filtered_copy
         .par_iter()
         .filter(|sidewalk| {
-            let points: Vec<Point> = sidewalk.points().collect();
+            let points: Vec<Point> = sidewalk.segments.points().collect();
 
             for point in points {
                 let mut distances =
                     street_spatial_tree.nearest_neighbor_iter_with_distance_2(&point);
 
                 /* have to skip itself */
-                distances.next().unwrap().1;
+                distances.next().unwrap();
 
                 let distance = distances.next().unwrap().1;
                 // eprintln!("{distance}");
@@ -122,7 +122,7 @@ fn main() {
             false
         })
         .collect();
-    assert!(only_sidewalkless_streets.len() > 0);
+    assert!(!only_sidewalkless_streets.is_empty());
 
     pb.finish_with_message("finished filtering through sidewalkless residential streets"); 
 
@@ -140,7 +140,7 @@ fn main() {
     let sidewalks: Vec<SideWalk> = only_sidewalkless_streets
         .par_iter()
         .map(|sidewalk| {
-            let (left_sidewalk, right_sidewalk) = compute_projection(sidewalk, 0.00003);
+            let (left_sidewalk, right_sidewalk) = compute_projection(&sidewalk.segments, 0.00003);
 
             pb.inc(1); 
 
@@ -150,12 +150,18 @@ fn main() {
                     id: None,
                     segments: left_sidewalk,
                     intersection_points: vec![],
+                    buildings: vec![],
+                    street_name: sidewalk.street_name.clone(),
+                    properties: sidewalk.properties.clone(),
                 },
                 right: Walkable {
                     lights: vec![],
                     id: None,
                     segments: right_sidewalk,
                     intersection_points: vec![],
+                    buildings: vec![],
+                    street_name: sidewalk.street_name.clone(),
+                    properties: sidewalk.properties.clone(),
                 },
             }
         })
@@ -1,4 +1,4 @@
-/**
+/*
  * this script was created to swap the coordinates of the lights file 
  * This script is not intended to be used in the data generation procedure. 
  */
@@ -11,7 +11,7 @@ pub mod utility_fns {
                 file.read_to_string(&mut contents)?;
                 Ok(contents)
             }
-            Err(err) => return Err(err),
+            Err(err) => Err(err),
         }
     }
 
@@ -10,7 +10,7 @@ use rocket_db_pools::{sqlx, Connection};
 /*
  * Internal imports
  */
-use application::{auth, utils, onboarding};
+use application::{auth, utils};
 use infrastructure::database::Db;
 use models::session::Session;
 use models::user::{RegisterUser, User, UserRequest, UserSession};
@@ -32,7 +32,7 @@ pub async fn register(
     request: Json<RegisterUser>,
 ) -> Result<Response, ErrorResponse> {
     let data = request.into_inner();
-    if auth::user_exists(&mut **db, data.email.clone()).await? {
+    if auth::user_exists(&mut db, data.email.clone()).await? {
         return Err(ErrorResponse {
             status: Status::Conflict,
             message: "User already exists".to_owned(),
@@ -51,9 +51,9 @@ pub async fn register(
             message: "Failed to hash password".to_owned(),
         })?;
     let user = User::new(data.email, hashed_password, data.name, session.clone());
-    let user_id = auth::insert_user(&mut **db, user).await?;
-    auth::insert_session(&mut **db, session.clone(), user_id.clone()).await?;
-    auth::insert_user_ip(&mut **db, data.ip, user_id).await?;
+    let user_id = auth::insert_user(&mut db, user).await?;
+    auth::insert_session(&mut db, session.clone(), user_id.clone()).await?;
+    auth::insert_user_ip(&mut db, data.ip, user_id).await?;
 
     Ok(Response {
         status: Status::Ok,
@@ -71,7 +71,7 @@ pub async fn login(
     request: Json<UserRequest>,
 ) -> Result<Response, ErrorResponse> {
     let data = request.into_inner();
-    let hashed_password = auth::get_user_password(&mut **db, data.email.clone()).await?;
+    let hashed_password = auth::get_user_password(&mut db, data.email.clone()).await?;
     if !utils::verify_password(hashed_password, data.password.clone()).map_err(|_| {
         ErrorResponse {
             status: Status::InternalServerError,
@@ -83,9 +83,9 @@ pub async fn login(
             message: "Invalid credentials".to_owned(),
         });
     }
-    let user_id = auth::get_user_id(&mut **db, data.email.clone()).await?;
+    let user_id = auth::get_user_id(&mut db, data.email.clone()).await?;
     let response =
-        auth::new_refresh_token(&mut **db, user_id.clone(), TOKEN_LENGTH, REFRESH_EXP_DAYS).await;
+        auth::new_refresh_token(&mut db, user_id.clone(), TOKEN_LENGTH, REFRESH_EXP_DAYS).await;
     let refresh_token = match response {
         Ok(token) => token,
         Err(_) => {
@@ -95,20 +95,20 @@ pub async fn login(
                 utils::create_token(TOKEN_LENGTH),
                 Utc::now() + Duration::days(REFRESH_EXP_DAYS),
             );
-            auth::remove_session(&mut **db, user_id.clone()).await?;
-            auth::insert_session(&mut **db, session.clone(), user_id.clone()).await?;
+            auth::remove_session(&mut db, user_id.clone()).await?;
+            auth::insert_session(&mut db, session.clone(), user_id.clone()).await?;
             session.refresh_token()
         }
     };
     let access_token = auth::new_access_token(
-        &mut **db,
+        &mut db,
         refresh_token.clone(),
         TOKEN_LENGTH,
         ACCESS_EXP_MIN,
     )
     .await?;
 
-    auth::insert_user_ip(&mut **db, data.ip, user_id).await?;
+    auth::insert_user_ip(&mut db, data.ip, user_id).await?;
 
     Ok(Response {
         status: Status::Ok,
@@ -145,7 +145,7 @@ pub async fn csrf_token(
     mut db: Connection<Db>,
     session: UserSession,
 ) -> Result<Response, ErrorResponse> {
-    let token = auth::new_csrf_token(&mut **db, session.access_token, TOKEN_LENGTH).await?;
+    let token = auth::new_csrf_token(&mut db, session.access_token, TOKEN_LENGTH).await?;
     Ok(Response {
         status: Status::Ok,
         body: ResponseBody::Token(TokenResponse {
@@ -181,7 +181,7 @@ pub async fn refresh_access(
         });
     }
     let refresh_token_str = refresh_token.unwrap().value().to_string();
-    if !auth::valid_refresh_token(&mut **db, refresh_token_str.clone()).await? {
+    if !auth::valid_refresh_token(&mut db, refresh_token_str.clone()).await? {
         return Err(ErrorResponse {
             status: Status::Unauthorized,
             message: "Invalid refresh token".to_owned(),
@@ -189,7 +189,7 @@ pub async fn refresh_access(
     }
 
     let access_token = auth::new_access_token(
-        &mut **db,
+        &mut db,
         refresh_token_str.clone(),
         TOKEN_LENGTH,
         ACCESS_EXP_MIN,
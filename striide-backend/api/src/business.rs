@@ -17,7 +17,7 @@ pub async fn open_businesses(mut db: Connection<Db>) -> Result<Response, ErrorRe
         status: Status::Ok,
         body: ResponseBody::BusinessResponse(BusinessResponse {
             message: "Successfully retrieved open businesses".to_owned(),
-            ids: get_open_businesses(&mut **db).await?,
+            ids: get_open_businesses(&mut db).await?,
         }),
     })
 }
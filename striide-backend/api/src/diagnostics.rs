@@ -0,0 +1,221 @@
+/*
+ * Internal Imports
+ */
+use models::user::{Role, UserSession};
+use shared::response_models::{ErrorResponse, RouteError};
+use crate::snapping::Snap;
+use crate::{GraphState, NavGraph, NavEdge};
+
+/*
+ * External Imports
+ */
+use petgraph::graph::{NodeIndex, UnGraph};
+use rocket::http::Status;
+use rocket::serde::{json::Value, Serialize};
+use rocket::State;
+use serde_json::json;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, VecDeque};
+
+/*
+ * constants
+ */
+/* components listed in the diagnostics, largest first - the long tail is usually single sidewalks */
+const LISTED_COMPONENTS: usize = 10;
+
+/*
+ *  ComponentSummary : one connected part of the sidewalk network
+ *  sample           : [lng, lat] of one of its nodes so it can be found on a map
+ */
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct ComponentSummary {
+    pub nodes: usize,
+    pub sample: [f64; 2],
+}
+
+/*
+ *  GraphDiagnostics        : connectivity of a navigation graph
+ *  isolated_nodes          : nodes without any edge
+ *  largest_component_share : fraction of the nodes in the largest component
+ *  degree_distribution     : number of nodes with each degree
+ *  components              : the LISTED_COMPONENTS largest components
+ */
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct GraphDiagnostics {
+    pub node_count: usize,
+    pub edge_count: usize,
+    pub component_count: usize,
+    pub isolated_nodes: usize,
+    pub largest_component_share: f64,
+    pub degree_distribution: BTreeMap<usize, usize>,
+    pub components: Vec<ComponentSummary>,
+}
+
+/*
+ *  connected_components: component of every node, indexed by node
+ *  purpose             : computed once when the graph is loaded so route queries can tell that two
+ *                        stops are on parts of the network that are not connected before searching
+ */
+pub fn connected_components(graph: &UnGraph<geo::Point, NavEdge>) -> Vec<u32> {
+    let mut components = vec![u32::MAX; graph.node_count()];
+    let mut next_component = 0;
+
+    for start in graph.node_indices() {
+        if components[start.index()] != u32::MAX {
+            continue;
+        }
+
+        components[start.index()] = next_component;
+        let mut queue = VecDeque::from([start]);
+        while let Some(node) = queue.pop_front() {
+            for neighbor in graph.neighbors(node) {
+                if components[neighbor.index()] == u32::MAX {
+                    components[neighbor.index()] = next_component;
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+        next_component += 1;
+    }
+
+    components
+}
+
+pub fn diagnose(navgraph: &NavGraph) -> GraphDiagnostics {
+    let graph = &navgraph.graph;
+    let node_count = graph.node_count();
+
+    let mut degree_distribution = BTreeMap::new();
+    for node in graph.node_indices() {
+        *degree_distribution.entry(graph.edges(node).count()).or_insert(0) += 1;
+    }
+
+    let component_count = navgraph.components.iter().map(|component| *component as usize + 1).max().unwrap_or(0);
+    let mut sizes: Vec<(usize, NodeIndex)> = vec![(0, NodeIndex::new(0)); component_count];
+    for (index, component) in navgraph.components.iter().enumerate() {
+        let size = &mut sizes[*component as usize];
+        if size.0 == 0 {
+            size.1 = NodeIndex::new(index);
+        }
+        size.0 += 1;
+    }
+    sizes.sort_by_key(|size| Reverse(size.0));
+
+    GraphDiagnostics {
+        node_count,
+        edge_count: graph.edge_count(),
+        component_count,
+        isolated_nodes: *degree_distribution.get(&0).unwrap_or(&0),
+        largest_component_share: match sizes.first() {
+            Some((size, _)) if node_count > 0 => *size as f64 / node_count as f64,
+            _ => 0.0,
+        },
+        degree_distribution,
+        components: sizes
+            .iter()
+            .take(LISTED_COMPONENTS)
+            .map(|(size, node)| ComponentSummary {
+                nodes: *size,
+                sample: [graph[*node].x(), graph[*node].y()],
+            })
+            .collect(),
+    }
+}
+
+/*
+ *  ensure_connected: rejects routes whose stops are on parts of the network that are not connected
+ *  purpose         : without this the pathfinder searches the whole component of the origin and
+ *                    the route comes back empty without saying why
+ */
+pub fn ensure_connected(stops: &[Snap], fields: &[String], navgraph: &NavGraph) -> Result<(), RouteError> {
+    let component = |stop: &Snap| navgraph.graph.edge_endpoints(stop.edge).map(|(source, _)| navgraph.components[source.index()]);
+    let origin_component = match stops.first() {
+        Some(origin) => component(origin),
+        None => return Ok(()),
+    };
+
+    match stops.iter().zip(fields.iter()).skip(1).find(|(stop, _)| component(stop) != origin_component) {
+        Some((_, field)) => Err(RouteError {
+            status: Status::UnprocessableEntity,
+            code: "disconnected_stops".to_owned(),
+            message: format!(
+                "{} is on a part of the sidewalk network that is not connected to the origin - no walking route exists between them",
+                field
+            ),
+            field: Some(field.clone()),
+        }),
+        None => Ok(()),
+    }
+}
+
+/* connectivity of the graph of every region, or of one region (admin only) */
+#[get("/admin/graph_diagnostics?<region>")]
+pub fn graph_diagnostics(
+    region: Option<String>,
+    session: UserSession,
+    graph_state: &State<GraphState>,
+) -> Result<Value, ErrorResponse> {
+    if !matches!(session.role, Role::Admin) {
+        return Err(ErrorResponse {
+            status: Status::Forbidden,
+            message: "only admins can see the graph diagnostics".to_owned(),
+        });
+    }
+
+    let regions: Vec<_> = graph_state
+        .regions()
+        .iter()
+        .filter(|candidate| region.as_ref().is_none_or(|key| &candidate.config.key == key))
+        .collect();
+    if regions.is_empty() {
+        return Err(ErrorResponse {
+            status: Status::NotFound,
+            message: "unknown region - see /regions for the supported regions".to_owned(),
+        });
+    }
+
+    let diagnostics: Vec<Value> = regions
+        .into_iter()
+        .map(|region| {
+            json!({
+                "region": region.config.key,
+                "diagnostics": diagnose(&region.current()),
+            })
+        })
+        .collect();
+
+    Ok(json!({ "regions": diagnostics }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_graph::{navgraph, snap};
+
+    #[test]
+    fn counts_components_and_isolated_nodes() {
+        let diagnostics = diagnose(&navgraph());
+
+        assert_eq!(diagnostics.node_count, 12);
+        assert_eq!(diagnostics.component_count, 3);
+        assert_eq!(diagnostics.isolated_nodes, 1);
+        assert_eq!(diagnostics.degree_distribution.get(&0), Some(&1));
+        assert!((diagnostics.largest_component_share - 0.75).abs() < 1e-9);
+        let sizes: Vec<usize> = diagnostics.components.iter().map(|component| component.nodes).collect();
+        assert_eq!(sizes, [9, 2, 1]);
+    }
+
+    #[test]
+    fn rejects_stops_on_another_component() {
+        let navgraph = navgraph();
+        let stops = vec![snap(&navgraph, 0, 1, 0.5), snap(&navgraph, 4, 5, 0.5), snap(&navgraph, 9, 10, 0.5)];
+        let fields: Vec<String> = ["origin", "stops[0]", "destination"].map(str::to_owned).to_vec();
+
+        assert!(ensure_connected(&stops[..2], &fields[..2], &navgraph).is_ok());
+        let err = ensure_connected(&stops, &fields, &navgraph).unwrap_err();
+        assert_eq!(err.code, "disconnected_stops");
+        assert_eq!(err.field.as_deref(), Some("destination"));
+    }
+}
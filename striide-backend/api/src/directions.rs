@@ -0,0 +1,195 @@
+/*
+ * Internal Imports
+ */
+use shared::response_models::Maneuver;
+use crate::pathfinder::RouteSegment;
+use crate::NavGraph;
+
+/*
+ * External Imports
+ */
+use geo::{HaversineBearing, Point};
+
+/*
+ * constants
+ */
+/* changes in direction below this angle are not announced */
+const TURN_THRESHOLD_DEGREES: f64 = 30.0;
+const SLIGHT_TURN_DEGREES: f64 = 60.0;
+const SHARP_TURN_DEGREES: f64 = 135.0;
+/* edges shorter than this are too short to give a reliable bearing */
+const MIN_BEARING_LENGTH: f64 = 0.5;
+const CARDINAL_DIRECTIONS: [&str; 8] = [
+    "north", "northeast", "east", "southeast", "south", "southwest", "west", "northwest",
+];
+
+/*
+ *  build_maneuvers: turns the points / segments of a route into turn by turn walking instructions
+ *  purpose        : consecutive edges are merged into one maneuver until the walking direction
+ *                   changes by more than TURN_THRESHOLD_DEGREES or the route moves onto a
+ *                   differently named street. Every maneuver carries the distance walked until
+ *                   the next one.
+ */
+pub fn build_maneuvers(points: &[Point], segments: &[RouteSegment], navgraph: &NavGraph) -> Vec<Maneuver> {
+    let mut maneuvers: Vec<Maneuver> = vec![];
+    if segments.is_empty() {
+        return maneuvers;
+    }
+
+    let mut previous_bearing: Option<f64> = None;
+
+    for (position, segment) in segments.iter().enumerate() {
+        let edge = &navgraph.graph[segment.edge];
+        let start = points[position];
+        let end = points[position + 1];
+
+        if segment.length < MIN_BEARING_LENGTH {
+            if let Some(maneuver) = maneuvers.last_mut() {
+                maneuver.distance_meters += segment.length;
+            }
+            continue;
+        }
+
+        let bearing = normalize_bearing(start.haversine_bearing(end));
+
+        match previous_bearing {
+            None => maneuvers.push(create_maneuver("depart", None, bearing, bearing, &edge.street_name, start.x_y())),
+            Some(previous) => {
+                let angle = turn_angle(previous, bearing);
+                let current_street = &maneuvers[maneuvers.len() - 1].street_name;
+                let street_changed = edge.street_name.is_some() && current_street.is_some() && edge.street_name != *current_street;
+
+                if angle.abs() >= TURN_THRESHOLD_DEGREES {
+                    maneuvers.push(create_maneuver("turn", Some(turn_modifier(angle)), previous, bearing, &edge.street_name, start.x_y()));
+                } else if street_changed {
+                    maneuvers.push(create_maneuver("continue", Some("straight"), previous, bearing, &edge.street_name, start.x_y()));
+                } else if current_street.is_none() && edge.street_name.is_some() {
+                    let last = maneuvers.len() - 1;
+                    maneuvers[last].street_name = edge.street_name.clone();
+                    maneuvers[last].instruction = describe(&maneuvers[last]);
+                }
+            }
+        }
+
+        if let Some(maneuver) = maneuvers.last_mut() {
+            maneuver.distance_meters += segment.length;
+        }
+        previous_bearing = Some(bearing);
+    }
+
+    let destination = points[points.len() - 1];
+    let final_bearing = previous_bearing.unwrap_or(0.0);
+    maneuvers.push(create_maneuver("arrive", None, final_bearing, final_bearing, &None, destination.x_y()));
+
+    maneuvers
+}
+
+fn create_maneuver(
+    maneuver_type: &str,
+    modifier: Option<&str>,
+    bearing_before: f64,
+    bearing_after: f64,
+    street_name: &Option<String>,
+    location: (f64, f64),
+) -> Maneuver {
+    let mut maneuver = Maneuver {
+        maneuver_type: maneuver_type.to_owned(),
+        modifier: modifier.map(|modifier| modifier.to_owned()),
+        instruction: String::new(),
+        street_name: street_name.clone(),
+        bearing_before,
+        bearing_after,
+        distance_meters: 0.0,
+        location,
+    };
+    maneuver.instruction = describe(&maneuver);
+    maneuver
+}
+
+/* human readable sentence for a maneuver - meant to be shown and spoken by the app */
+fn describe(maneuver: &Maneuver) -> String {
+    let street = maneuver.street_name.as_ref();
+
+    match (maneuver.maneuver_type.as_str(), street) {
+        ("depart", Some(street)) => format!("Head {} on {}", cardinal_direction(maneuver.bearing_after), street),
+        ("depart", None) => format!("Head {}", cardinal_direction(maneuver.bearing_after)),
+        ("turn", Some(street)) => format!("Turn {} onto {}", maneuver.modifier.as_deref().unwrap_or(""), street),
+        ("turn", None) => format!("Turn {}", maneuver.modifier.as_deref().unwrap_or("")),
+        ("continue", Some(street)) => format!("Continue onto {}", street),
+        ("continue", None) => "Continue straight".to_owned(),
+        _ => "Arrive at your destination".to_owned(),
+    }
+}
+
+/* bearings from geo are in -180..180 degrees, maneuvers use 0..360 clockwise from north */
+fn normalize_bearing(bearing: f64) -> f64 {
+    (bearing + 360.0) % 360.0
+}
+
+/* signed change of direction in -180..180 degrees, positive values are right turns */
+fn turn_angle(before: f64, after: f64) -> f64 {
+    ((after - before + 540.0) % 360.0) - 180.0
+}
+
+fn turn_modifier(angle: f64) -> &'static str {
+    let magnitude = angle.abs();
+    let is_right = angle > 0.0;
+
+    if magnitude >= SHARP_TURN_DEGREES {
+        if is_right { "sharp right" } else { "sharp left" }
+    } else if magnitude >= SLIGHT_TURN_DEGREES {
+        if is_right { "right" } else { "left" }
+    } else if is_right {
+        "slight right"
+    } else {
+        "slight left"
+    }
+}
+
+fn cardinal_direction(bearing: f64) -> &'static str {
+    let index = ((bearing + 22.5) / 45.0) as usize % CARDINAL_DIRECTIONS.len();
+    CARDINAL_DIRECTIONS[index]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn turn_angles_are_signed_changes_of_direction() {
+        assert_eq!(turn_angle(0.0, 90.0), 90.0);
+        assert_eq!(turn_angle(90.0, 0.0), -90.0);
+        assert_eq!(turn_angle(45.0, 45.0), 0.0);
+    }
+
+    #[test]
+    fn turn_angles_wrap_around_north() {
+        assert_eq!(turn_angle(350.0, 10.0), 20.0);
+        assert_eq!(turn_angle(10.0, 350.0), -20.0);
+        assert_eq!(turn_angle(270.0, 0.0), 90.0);
+    }
+
+    #[test]
+    fn turning_back_is_a_sharp_turn() {
+        assert_eq!(turn_angle(0.0, 180.0).abs(), 180.0);
+        assert!(turn_modifier(turn_angle(0.0, 180.0)).starts_with("sharp"));
+    }
+
+    #[test]
+    fn modifiers_grow_with_the_angle() {
+        assert_eq!(turn_modifier(40.0), "slight right");
+        assert_eq!(turn_modifier(-40.0), "slight left");
+        assert_eq!(turn_modifier(SLIGHT_TURN_DEGREES), "right");
+        assert_eq!(turn_modifier(-90.0), "left");
+        assert_eq!(turn_modifier(SHARP_TURN_DEGREES), "sharp right");
+        assert_eq!(turn_modifier(-150.0), "sharp left");
+    }
+
+    #[test]
+    fn bearings_are_clockwise_from_north() {
+        assert_eq!(normalize_bearing(-90.0), 270.0);
+        assert_eq!(normalize_bearing(90.0), 90.0);
+        assert_eq!(cardinal_direction(350.0), "north");
+        assert_eq!(cardinal_direction(100.0), "east");
+    }
+}
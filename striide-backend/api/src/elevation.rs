@@ -0,0 +1,156 @@
+/*
+ * Internal Imports
+ */
+use crate::pathfinder::WALKING_SPEED_METERS_PER_SECOND;
+use crate::snapping::Snap;
+use crate::NavGraph;
+
+/*
+ * External Imports
+ */
+use petgraph::graph::{EdgeIndex, NodeIndex};
+
+/*
+ * constants
+ */
+/* Tobler's hiking function: speed = 6 * e^(-3.5 * |grade + 0.05|) km/h, fastest on a gentle downhill */
+const TOBLER_STEEPNESS: f64 = 3.5;
+const TOBLER_FASTEST_DOWNHILL: f64 = 0.05;
+/*
+ * steeper grades are almost always elevation model noise on short edges, or bridges and tunnels
+ * sampled at the ground underneath them
+ */
+const MAX_GRADE: f64 = 0.3;
+
+/* rise over run between two elevations - None when one of them is unknown */
+pub fn edge_grade(from: Option<f64>, to: Option<f64>, length: f64) -> Option<f64> {
+    match (from, to) {
+        (Some(from), Some(to)) if length > 0.0 => Some(((to - from) / length).clamp(-MAX_GRADE, MAX_GRADE)),
+        _ => None,
+    }
+}
+
+/* time to walk a meter at the grade relative to walking a meter on flat ground */
+pub fn slope_factor(grade: f64) -> f64 {
+    (TOBLER_STEEPNESS * ((grade + TOBLER_FASTEST_DOWNHILL).abs() - TOBLER_FASTEST_DOWNHILL)).exp()
+}
+
+/* lowest slope factor of any grade - the pathfinder scales its heuristic by it to stay admissible */
+pub fn min_slope_factor() -> f64 {
+    slope_factor(-TOBLER_FASTEST_DOWNHILL)
+}
+
+/* grade of the edge when walking along it toward one of its nodes, 0 when the elevations are unknown */
+pub fn grade_toward(index: EdgeIndex, toward: NodeIndex, navgraph: &NavGraph) -> f64 {
+    match (navgraph.graph[index].grade, navgraph.graph.edge_endpoints(index)) {
+        (Some(grade), Some((_, target))) if target == toward => grade,
+        (Some(grade), Some(_)) => -grade,
+        _ => 0.0,
+    }
+}
+
+pub fn walking_seconds(length: f64, grade: f64) -> f64 {
+    length * slope_factor(grade) / WALKING_SPEED_METERS_PER_SECOND
+}
+
+/* elevation of a point snapped onto an edge, interpolated between the edge's nodes */
+pub fn snap_elevation(snap: &Snap, navgraph: &NavGraph) -> Option<f64> {
+    let (source, target) = navgraph.graph.edge_endpoints(snap.edge)?;
+    let (from, to) = (node_elevation(source, navgraph)?, node_elevation(target, navgraph)?);
+    Some(from + (to - from) * snap.fraction)
+}
+
+pub fn node_elevation(node: NodeIndex, navgraph: &NavGraph) -> Option<f64> {
+    navgraph.elevations.get(node.index()).copied().flatten()
+}
+
+/*
+ *  Climb    : elevation summary of a route
+ *  duration : walking time in seconds, slower uphill and on steep downhills
+ *  profile  : [meters from the start, elevation in meters] of every point of the route with a known elevation
+ */
+#[derive(Debug, Clone)]
+pub struct Climb {
+    pub ascent: f64,
+    pub descent: f64,
+    pub duration: f64,
+    pub profile: Vec<(f64, f64)>,
+}
+
+/*
+ *  climb  : ascent, descent, walking time and elevation profile of a route
+ *  purpose: elevations are those of the route's points and lengths those of the segments between
+ *           consecutive points. Segments with an unknown elevation at either end are walked as if flat.
+ */
+pub fn climb(elevations: &[Option<f64>], lengths: &[f64]) -> Climb {
+    let mut climb = Climb {
+        ascent: 0.0,
+        descent: 0.0,
+        duration: 0.0,
+        profile: vec![],
+    };
+
+    let mut distance = 0.0;
+    for (index, length) in lengths.iter().enumerate() {
+        let (from, to) = (elevations[index], elevations[index + 1]);
+        if let Some(elevation) = from {
+            climb.profile.push((distance, elevation));
+        }
+        if let (Some(from), Some(to)) = (from, to) {
+            climb.ascent += (to - from).max(0.0);
+            climb.descent += (from - to).max(0.0);
+        }
+
+        climb.duration += walking_seconds(*length, edge_grade(from, to, *length).unwrap_or(0.0));
+        distance += length;
+    }
+    if let Some(Some(elevation)) = elevations.get(lengths.len()) {
+        climb.profile.push((distance, *elevation));
+    }
+
+    climb
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grades_rise_over_run() {
+        assert_eq!(edge_grade(Some(10.0), Some(10.0), 50.0), Some(0.0));
+        assert_eq!(edge_grade(Some(10.0), Some(15.0), 50.0), Some(0.1));
+        assert_eq!(edge_grade(Some(15.0), Some(10.0), 50.0), Some(-0.1));
+    }
+
+    #[test]
+    fn clamps_steep_grades() {
+        assert_eq!(edge_grade(Some(0.0), Some(40.0), 50.0), Some(MAX_GRADE));
+        assert_eq!(edge_grade(Some(40.0), Some(0.0), 50.0), Some(-MAX_GRADE));
+    }
+
+    #[test]
+    fn has_no_grade_without_both_elevations() {
+        assert_eq!(edge_grade(None, Some(10.0), 50.0), None);
+        assert_eq!(edge_grade(Some(10.0), None, 50.0), None);
+        assert_eq!(edge_grade(Some(10.0), Some(12.0), 0.0), None);
+    }
+
+    #[test]
+    fn flat_ground_costs_its_length() {
+        assert!((slope_factor(0.0) - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn uphill_is_slower_than_flat_ground() {
+        assert!(slope_factor(0.1) > 1.0);
+        assert!(slope_factor(MAX_GRADE) > slope_factor(0.1));
+    }
+
+    #[test]
+    fn gentle_downhill_is_fastest() {
+        assert!(slope_factor(-TOBLER_FASTEST_DOWNHILL) < 1.0);
+        assert_eq!(min_slope_factor(), slope_factor(-TOBLER_FASTEST_DOWNHILL));
+        assert!(slope_factor(-0.2) > min_slope_factor());
+        assert!(slope_factor(-MAX_GRADE) > 1.0);
+    }
+}
@@ -0,0 +1,229 @@
+/*
+ * Internal Imports
+ */
+use models::user::{Role, UserSession};
+use shared::response_models::{ErrorResponse, Response, ResponseBody};
+use crate::route_cache::RouteCache;
+use crate::{load_graph, NavGraph};
+
+/*
+ * External Imports
+ */
+use chrono::DateTime;
+use geo::BoundingRect;
+use rocket::fairing::AdHoc;
+use rocket::http::Status;
+use rocket::serde::{json::Value, Deserialize};
+use rocket::State;
+use serde_json::json;
+use std::sync::{Arc, RwLock};
+
+/*
+ * constants
+ */
+/* used when graph_path is not set in Rocket.toml or ROCKET_GRAPH_PATH */
+const DEFAULT_GRAPH_PATH: &str = "./api/src/output.graph";
+/* key of the only region when no regions are configured */
+const DEFAULT_REGION_KEY: &str = "default";
+
+/*
+ *  RegionConfig : entry of the regions array in Rocket.toml, e.g.
+ *                   [[default.regions]]
+ *                   key = "boston"
+ *                   city = "Boston"
+ *                   state = "MA"
+ *                   graph_path = "./api/src/boston.graph"
+ *  key          : identifier used by the frontend to ask for a region
+ *  city, state  : spelled like the landing form so demand for a city can be matched to its region
+ */
+#[derive(Deserialize, Debug, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct RegionConfig {
+    pub key: String,
+    pub city: Option<String>,
+    pub state: Option<String>,
+    pub graph_path: String,
+}
+
+/*
+ *  Region  : one area covered by its own navigation graph
+ *  purpose : the graph can be swapped out while the server is running. Handlers take a reference
+ *            counted snapshot when they start, so in-flight requests keep the graph they started
+ *            with and the old graph is dropped once the last of them finishes.
+ */
+pub struct Region {
+    pub config: RegionConfig,
+    current: RwLock<Arc<NavGraph>>,
+}
+
+impl Region {
+    /* graph to use for the whole request */
+    pub fn current(&self) -> Arc<NavGraph> {
+        self.current.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+
+    fn replace(&self, graph: NavGraph) {
+        *self.current.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = Arc::new(graph);
+    }
+}
+
+/*
+ *  GraphState : navigation graphs of every region managed by rocket
+ *  purpose    : requests are dispatched to the first region, in configuration order, whose
+ *               service area contains their origin
+ */
+pub struct GraphState {
+    regions: Vec<Region>,
+}
+
+impl GraphState {
+    pub fn new(regions: Vec<(RegionConfig, NavGraph)>) -> GraphState {
+        GraphState {
+            regions: regions
+                .into_iter()
+                .map(|(config, graph)| Region {
+                    config,
+                    current: RwLock::new(Arc::new(graph)),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn regions(&self) -> &Vec<Region> {
+        &self.regions
+    }
+
+    pub fn region(&self, key: &str) -> Option<&Region> {
+        self.regions.iter().find(|region| region.config.key == key)
+    }
+}
+
+/* regions array of the configuration, or a single region loaded from graph_path */
+fn region_configs(figment: &rocket::figment::Figment) -> Vec<RegionConfig> {
+    match figment.extract_inner::<Vec<RegionConfig>>("regions") {
+        Ok(regions) if !regions.is_empty() => regions,
+        _ => vec![RegionConfig {
+            key: DEFAULT_REGION_KEY.to_owned(),
+            city: None,
+            state: None,
+            graph_path: figment
+                .extract_inner::<String>("graph_path")
+                .unwrap_or(DEFAULT_GRAPH_PATH.to_owned()),
+        }],
+    }
+}
+
+/* loads the graph of every configured region - rocket refuses to launch unless all of them are valid */
+pub fn stage() -> AdHoc {
+    AdHoc::try_on_ignite("Navigation Graph", |rocket| async {
+        let mut regions = vec![];
+
+        for config in region_configs(rocket.figment()) {
+            match load_graph(&config.graph_path) {
+                Ok(graph) => regions.push((config, graph)),
+                Err(err) => {
+                    println!("could not load the navigation graph of region {}: {}", config.key, err);
+                    return Err(rocket);
+                }
+            }
+        }
+
+        Ok(rocket.manage(GraphState::new(regions)))
+    })
+}
+
+/*
+ *  reload_graph: reloads the navigation graph of every region from its configured path (admin only)
+ *  purpose     : lets a data refresh go live without a redeploy. The new graphs are validated while
+ *                they are loaded and the current graphs all stay in use when anything is wrong with
+ *                one of them. Cached routes of the replaced graphs are dropped.
+ */
+#[post("/admin/reload_graph")]
+pub async fn reload_graph(
+    session: UserSession,
+    graph_state: &State<GraphState>,
+    route_cache: &State<RouteCache>,
+) -> Result<Response, ErrorResponse> {
+    if !matches!(session.role, Role::Admin) {
+        return Err(ErrorResponse {
+            status: Status::Forbidden,
+            message: "only admins can reload the navigation graph".to_owned(),
+        });
+    }
+
+    let mut graphs = vec![];
+    for region in graph_state.regions() {
+        let path = region.config.graph_path.clone();
+        let loaded = rocket::tokio::task::spawn_blocking(move || load_graph(&path))
+            .await
+            .map_err(|err| ErrorResponse {
+                status: Status::InternalServerError,
+                message: format!("graph reload did not finish: {}", err),
+            })?;
+
+        let graph = loaded.map_err(|err| ErrorResponse {
+            status: Status::UnprocessableEntity,
+            message: format!(
+                "kept the current navigation graphs - the new one of region {} is invalid: {}",
+                region.config.key, err
+            ),
+        })?;
+        graphs.push(graph);
+    }
+
+    let summaries: Vec<String> = graph_state
+        .regions()
+        .iter()
+        .zip(graphs)
+        .map(|(region, graph)| {
+            let summary = format!(
+                "{} ({} nodes, {} edges)",
+                region.config.key,
+                graph.graph.node_count(),
+                graph.graph.edge_count()
+            );
+            region.replace(graph);
+            summary
+        })
+        .collect();
+    route_cache.clear();
+
+    Ok(Response {
+        status: Status::Ok,
+        body: ResponseBody::Message(format!("reloaded navigation graphs: {}", summaries.join(", "))),
+    })
+}
+
+/* supported regions with the extent and build information of their graphs */
+#[get("/regions")]
+pub fn regions(graph_state: &State<GraphState>) -> Value {
+    let regions: Vec<Value> = graph_state
+        .regions()
+        .iter()
+        .map(|region| {
+            let navgraph = region.current();
+            let bounding_box = navgraph
+                .service_area
+                .bounding_rect()
+                .map(|rect| vec![rect.min().x, rect.min().y, rect.max().x, rect.max().y]);
+            let built_at = navgraph
+                .header
+                .as_ref()
+                .and_then(|header| DateTime::from_timestamp(header.built_at as i64, 0))
+                .map(|built_at| built_at.to_rfc3339());
+
+            json!({
+                "key": region.config.key,
+                "city": region.config.city,
+                "state": region.config.state,
+                "bounding_box": bounding_box,
+                "node_count": navgraph.graph.node_count(),
+                "edge_count": navgraph.graph.edge_count(),
+                "built_at": built_at,
+                "fast_routing": navgraph.hierarchy.is_some(),
+            })
+        })
+        .collect();
+
+    json!({ "regions": regions })
+}
@@ -0,0 +1,165 @@
+/*
+ * Internal Imports
+ */
+use models::report::HazardReport;
+use shared::response_models::RouteHazard;
+use crate::pathfinder::RouteSegment;
+use crate::snapping::nearby_edges;
+use crate::NavGraph;
+
+/*
+ * External Imports
+ */
+use chrono::{DateTime, Duration, Utc};
+use geo::Point;
+use petgraph::graph::EdgeIndex;
+use std::collections::{BTreeSet, HashMap};
+
+/*
+ * constants
+ */
+/* edges checked around every report before the report's radius is applied */
+const HAZARD_EDGE_CANDIDATES: usize = 16;
+
+/*
+ *  HazardConfig : how reports of one type affect routing
+ *  report_type  : duration category picked by the reporter - reports have no other type
+ *  active_hours : how long after it was created a report is taken into account
+ *  radius       : meters around the report in which sidewalks are penalized
+ *  weight       : cost of a penalized edge is multiplied by 1 + weight (weights of overlapping reports add up)
+ *
+ *  radius and weight can be overridden with HAZARD_<TYPE>_RADIUS_METERS and HAZARD_<TYPE>_WEIGHT
+ */
+struct HazardConfig {
+    report_type: &'static str,
+    active_hours: i64,
+    radius: f64,
+    weight: f64,
+}
+
+static HAZARD_CONFIGS: [HazardConfig; 3] = [
+    HazardConfig { report_type: "Short", active_hours: 24, radius: 30.0, weight: 2.0 },
+    HazardConfig { report_type: "Medium", active_hours: 24 * 7, radius: 40.0, weight: 3.0 },
+    HazardConfig { report_type: "Long", active_hours: 24 * 30, radius: 50.0, weight: 4.0 },
+];
+
+impl HazardConfig {
+    fn radius(&self) -> f64 {
+        env_override(&format!("HAZARD_{}_RADIUS_METERS", self.report_type.to_uppercase()), self.radius)
+    }
+
+    fn weight(&self) -> f64 {
+        env_override(&format!("HAZARD_{}_WEIGHT", self.report_type.to_uppercase()), self.weight)
+    }
+}
+
+/*
+ *  Hazards        : active reports and the graph edges they affect
+ *  affected_edges : positions in reports of every report close to an edge
+ *  penalties      : cost multiplier of every affected edge
+ */
+#[derive(Debug, Default)]
+pub struct Hazards {
+    reports: Vec<HazardReport>,
+    affected_edges: HashMap<EdgeIndex, Vec<usize>>,
+    penalties: HashMap<EdgeIndex, f64>,
+}
+
+impl Hazards {
+    /* keeps the reports that are still active and penalizes the sidewalks within their radius */
+    pub fn locate(reports: Vec<HazardReport>, now: DateTime<Utc>, navgraph: &NavGraph) -> Hazards {
+        let mut hazards = Hazards::default();
+
+        for report in reports {
+            let config = config_for(&report.duration);
+            if now - report.created_at > Duration::hours(config.active_hours) {
+                continue;
+            }
+
+            let position = hazards.reports.len();
+            let radius = config.radius();
+            let weight = config.weight();
+
+            for (edge, _, distance) in nearby_edges(Point::new(report.lng, report.lat), HAZARD_EDGE_CANDIDATES, navgraph) {
+                if distance <= radius {
+                    hazards.affected_edges.entry(edge).or_insert(vec![]).push(position);
+                    *hazards.penalties.entry(edge).or_insert(1.0) += weight;
+                }
+            }
+
+            hazards.reports.push(report);
+        }
+
+        hazards
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.penalties.is_empty()
+    }
+
+    pub fn penalties(&self) -> &HashMap<EdgeIndex, f64> {
+        &self.penalties
+    }
+
+    /*
+     *  influencing: reports a route passes, plus the reports it was diverted around
+     *  purpose    : a report counts as avoided when the route found while ignoring hazards
+     *               went past it and the returned route does not
+     */
+    pub fn influencing(&self, route: &[RouteSegment], unaffected_route: &[RouteSegment]) -> Vec<RouteHazard> {
+        let passed = self.reports_along(route);
+        let avoided: BTreeSet<usize> = self.reports_along(unaffected_route).difference(&passed).copied().collect();
+
+        passed.iter()
+            .map(|position| (*position, false))
+            .chain(avoided.iter().map(|position| (*position, true)))
+            .map(|(position, avoided)| {
+                let report = &self.reports[position];
+                RouteHazard {
+                    report_id: report.id.clone(),
+                    report_type: report.duration.clone(),
+                    description: report.description.clone(),
+                    location: (report.lng, report.lat),
+                    avoided,
+                }
+            })
+            .collect()
+    }
+
+    /* ids of the reports close to an edge */
+    pub fn reports_near(&self, edge: EdgeIndex) -> Vec<String> {
+        self.affected_edges
+            .get(&edge)
+            .map(|positions| positions.iter().map(|position| self.reports[*position].id.clone()).collect())
+            .unwrap_or(vec![])
+    }
+
+    fn reports_along(&self, route: &[RouteSegment]) -> BTreeSet<usize> {
+        route.iter()
+            .filter_map(|segment| self.affected_edges.get(&segment.edge))
+            .flatten()
+            .copied()
+            .collect()
+    }
+}
+
+/* how far back reports have to be loaded to cover the longest lived report type */
+pub fn oldest_active_report(now: DateTime<Utc>) -> DateTime<Utc> {
+    let longest = HAZARD_CONFIGS.iter().map(|config| config.active_hours).max().unwrap_or(0);
+    now - Duration::hours(longest)
+}
+
+/* reports with an unknown duration are treated like short lived ones */
+fn config_for(report_type: &str) -> &'static HazardConfig {
+    HAZARD_CONFIGS
+        .iter()
+        .find(|config| config.report_type.eq_ignore_ascii_case(report_type.trim()))
+        .unwrap_or(&HAZARD_CONFIGS[0])
+}
+
+fn env_override(name: &str, default: f64) -> f64 {
+    std::env::var(name)
+        .ok()
+        .and_then(|value| value.parse::<f64>().ok())
+        .unwrap_or(default)
+}
@@ -0,0 +1,348 @@
+/*
+ * Internal Imports
+ */
+use graph_format::{read_hierarchy, GraphHeader, HierarchyArc, HierarchyBody};
+
+/*
+ * External Imports
+ */
+use petgraph::graph::NodeIndex;
+use ordered_float::OrderedFloat;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::path::Path;
+
+/*
+ *  ContractionHierarchy: speedup structure gen_hierarchy builds over the walking distance of the graph
+ *  purpose             : every node only keeps its arcs to nodes contracted after it, shortcuts
+ *                        included. A route is found by searching upwards from both ends and meeting
+ *                        at the most important node of the route, then shortcuts are unpacked back
+ *                        into graph nodes. Only valid for the plain walking distance - any other
+ *                        weighting has to use A*.
+ */
+#[derive(Debug)]
+pub struct ContractionHierarchy {
+    ranks: Vec<u32>,
+    first_arc: Vec<u32>,
+    arcs: Vec<HierarchyArc>,
+}
+
+/*
+ *  load_hierarchy: loads the hierarchy stored next to the graph file (output.graph -> output.hierarchy)
+ *  purpose       : the hierarchy is optional - routing works without it, only slower
+ */
+pub fn load_hierarchy(graph_file_path: &str, graph_header: &GraphHeader) -> Option<ContractionHierarchy> {
+    let hierarchy_path = Path::new(graph_file_path).with_extension("hierarchy");
+    if !hierarchy_path.exists() {
+        return None;
+    }
+
+    match read_hierarchy(&hierarchy_path.to_string_lossy(), graph_header) {
+        Ok(body) => Some(ContractionHierarchy::new(body)),
+        Err(err) => {
+            println!("routing without the contraction hierarchy: {}", err);
+            None
+        }
+    }
+}
+
+/*
+ *  UpwardState: entry of the open set of one direction of the search, wrapped in Reverse so the heap pops the lowest cost first
+ *  cost       : meters from the closest seed of this direction
+ */
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct UpwardState {
+    cost: OrderedFloat<f64>,
+    node: u32,
+}
+
+impl UpwardState {
+    fn queued(cost: f64, node: u32) -> Reverse<UpwardState> {
+        Reverse(UpwardState { cost: OrderedFloat(cost), node })
+    }
+}
+
+/* dijkstra along upward arcs from the seeds of one end of the route */
+struct UpwardSearch {
+    open: BinaryHeap<Reverse<UpwardState>>,
+    costs: HashMap<u32, f64>,
+    parents: HashMap<u32, u32>,
+}
+
+impl UpwardSearch {
+    fn new(seeds: &Vec<(NodeIndex, f64)>) -> UpwardSearch {
+        let mut search = UpwardSearch {
+            open: BinaryHeap::new(),
+            costs: HashMap::new(),
+            parents: HashMap::new(),
+        };
+
+        for (node, cost) in seeds {
+            let node = node.index() as u32;
+            if *cost < *search.costs.get(&node).unwrap_or(&f64::INFINITY) {
+                search.costs.insert(node, *cost);
+                search.open.push(UpwardState::queued(*cost, node));
+            }
+        }
+
+        search
+    }
+
+    /* settles the next node unless nothing cheaper than the best route found so far is left */
+    fn settle_next(&mut self, hierarchy: &ContractionHierarchy, best: f64) -> Option<UpwardState> {
+        while let Some(Reverse(state)) = self.open.pop() {
+            if *state.cost >= best {
+                self.open.clear();
+                return None;
+            }
+            if *state.cost > *self.costs.get(&state.node).unwrap_or(&f64::INFINITY) {
+                continue;
+            }
+
+            for arc in hierarchy.upward_arcs(state.node) {
+                let cost = *state.cost + arc.weight;
+                if cost < *self.costs.get(&arc.target).unwrap_or(&f64::INFINITY) {
+                    self.costs.insert(arc.target, cost);
+                    self.parents.insert(arc.target, state.node);
+                    self.open.push(UpwardState::queued(cost, arc.target));
+                }
+            }
+
+            return Some(state);
+        }
+
+        None
+    }
+
+    fn is_done(&self) -> bool {
+        self.open.is_empty()
+    }
+
+    /* nodes from the seed this direction started at to the given node */
+    fn chain_to(&self, node: u32) -> Vec<u32> {
+        let mut chain = vec![node];
+        let mut current = node;
+        while let Some(parent) = self.parents.get(&current) {
+            chain.push(*parent);
+            current = *parent;
+        }
+        chain.reverse();
+        chain
+    }
+}
+
+impl ContractionHierarchy {
+    fn new(body: HierarchyBody) -> ContractionHierarchy {
+        ContractionHierarchy {
+            ranks: body.ranks,
+            first_arc: body.first_arc,
+            arcs: body.arcs,
+        }
+    }
+
+    fn upward_arcs(&self, node: u32) -> &[HierarchyArc] {
+        let start = self.first_arc[node as usize] as usize;
+        let end = self.first_arc[node as usize + 1] as usize;
+        &self.arcs[start..end]
+    }
+
+    /*
+     *  shortest_path: shortest walking distance between any origin seed and any destination seed
+     *  purpose      : seeds carry the meters walked between the snapped point and the node, so the
+     *                 same query works for points in the middle of an edge. Returns the distance and
+     *                 the graph nodes of the path.
+     */
+    pub fn shortest_path(&self, origins: &Vec<(NodeIndex, f64)>, destinations: &Vec<(NodeIndex, f64)>) -> Option<(f64, Vec<NodeIndex>)> {
+        let mut forward = UpwardSearch::new(origins);
+        let mut backward = UpwardSearch::new(destinations);
+        let mut best = f64::INFINITY;
+        let mut meeting: Option<u32> = None;
+
+        while !forward.is_done() || !backward.is_done() {
+            if let Some(state) = forward.settle_next(self, best) {
+                if let Some(cost) = backward.costs.get(&state.node) {
+                    if *state.cost + cost < best {
+                        best = *state.cost + cost;
+                        meeting = Some(state.node);
+                    }
+                }
+            }
+
+            if let Some(state) = backward.settle_next(self, best) {
+                if let Some(cost) = forward.costs.get(&state.node) {
+                    if *state.cost + cost < best {
+                        best = *state.cost + cost;
+                        meeting = Some(state.node);
+                    }
+                }
+            }
+        }
+
+        let meeting = meeting?;
+        let mut chain = forward.chain_to(meeting);
+        let mut rest = backward.chain_to(meeting);
+        rest.pop();
+        rest.reverse();
+        chain.extend(rest);
+
+        let mut nodes = vec![NodeIndex::new(chain[0] as usize)];
+        for pair in chain.windows(2) {
+            self.unpack(pair[0], pair[1], &mut nodes);
+        }
+
+        Some((best, nodes))
+    }
+
+    /* appends the graph nodes after from up to and including to */
+    fn unpack(&self, from: u32, to: u32, nodes: &mut Vec<NodeIndex>) {
+        let (lower, higher) = if self.ranks[from as usize] < self.ranks[to as usize] { (from, to) } else { (to, from) };
+        let middle = self
+            .upward_arcs(lower)
+            .iter()
+            .find(|arc| arc.target == higher)
+            .and_then(|arc| arc.middle);
+
+        match middle {
+            Some(middle) => {
+                self.unpack(from, middle, nodes);
+                self.unpack(middle, to, nodes);
+            }
+            None => nodes.push(NodeIndex::new(to as usize)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /* 3 x 3 grid of sidewalks with a diagonal shortcut, and node 9 on its own */
+    const NODE_COUNT: usize = 10;
+    const EDGES: [(u32, u32, f64); 13] = [
+        (0, 1, 10.0), (1, 2, 12.0), (3, 4, 9.0), (4, 5, 11.0), (6, 7, 10.0), (7, 8, 10.0),
+        (0, 3, 8.0), (3, 6, 14.0), (1, 4, 10.0), (4, 7, 7.0), (2, 5, 9.0), (5, 8, 13.0),
+        (0, 4, 12.0),
+    ];
+    /* contraction order, mixed up so shortcuts are created in both directions */
+    const ORDER: [u32; NODE_COUNT] = [4, 0, 8, 2, 6, 1, 9, 3, 7, 5];
+
+    /* contracts the nodes in ORDER, adding a shortcut between every pair of neighbors - without witness searches */
+    fn contract() -> ContractionHierarchy {
+        let mut remaining: Vec<HashMap<u32, HierarchyArc>> = vec![HashMap::new(); NODE_COUNT];
+        for (source, target, length) in EDGES {
+            add_arc(&mut remaining, source, target, length, None);
+        }
+
+        let mut ranks = vec![0u32; NODE_COUNT];
+        let mut upward: Vec<Vec<HierarchyArc>> = vec![vec![]; NODE_COUNT];
+        for (rank, node) in ORDER.iter().enumerate() {
+            let neighbors: Vec<HierarchyArc> = remaining[*node as usize].values().copied().collect();
+            for first in &neighbors {
+                for second in &neighbors {
+                    if first.target != second.target {
+                        add_arc(&mut remaining, first.target, second.target, first.weight + second.weight, Some(*node));
+                    }
+                }
+            }
+            for neighbor in &neighbors {
+                remaining[neighbor.target as usize].remove(node);
+            }
+            ranks[*node as usize] = rank as u32;
+            upward[*node as usize] = neighbors;
+        }
+
+        let mut first_arc = vec![0u32];
+        let mut arcs = vec![];
+        for node_arcs in upward {
+            arcs.extend(node_arcs);
+            first_arc.push(arcs.len() as u32);
+        }
+        ContractionHierarchy::new(HierarchyBody { ranks, first_arc, arcs })
+    }
+
+    /* keeps the cheapest arc between two nodes in both directions */
+    fn add_arc(remaining: &mut [HashMap<u32, HierarchyArc>], from: u32, to: u32, weight: f64, middle: Option<u32>) {
+        for (a, b) in [(from, to), (to, from)] {
+            if remaining[a as usize].get(&b).is_none_or(|arc| weight < arc.weight) {
+                remaining[a as usize].insert(b, HierarchyArc { target: b, weight, middle });
+            }
+        }
+    }
+
+    fn edge_length(from: NodeIndex, to: NodeIndex) -> Option<f64> {
+        let (from, to) = (from.index() as u32, to.index() as u32);
+        EDGES
+            .iter()
+            .find(|(source, target, _)| (*source == from && *target == to) || (*source == to && *target == from))
+            .map(|(_, _, length)| *length)
+    }
+
+    /* plain dijkstra over the graph from every seed */
+    fn dijkstra(origins: &Vec<(NodeIndex, f64)>) -> Vec<f64> {
+        let mut distances = vec![f64::INFINITY; NODE_COUNT];
+        let mut settled = vec![false; NODE_COUNT];
+        for (node, distance) in origins {
+            distances[node.index()] = distances[node.index()].min(*distance);
+        }
+
+        let next_node = |distances: &Vec<f64>, settled: &Vec<bool>| {
+            (0..NODE_COUNT)
+                .filter(|node| !settled[*node] && distances[*node].is_finite())
+                .min_by(|a, b| distances[*a].total_cmp(&distances[*b]))
+        };
+        while let Some(node) = next_node(&distances, &settled) {
+            settled[node] = true;
+            for next in 0..NODE_COUNT {
+                if let Some(length) = edge_length(NodeIndex::new(node), NodeIndex::new(next)) {
+                    distances[next] = distances[next].min(distances[node] + length);
+                }
+            }
+        }
+        distances
+    }
+
+    fn assert_walkable(nodes: &[NodeIndex], from: NodeIndex, to: NodeIndex, distance: f64) {
+        assert_eq!(nodes.first(), Some(&from));
+        assert_eq!(nodes.last(), Some(&to));
+        let walked: f64 = nodes.windows(2).map(|pair| edge_length(pair[0], pair[1]).expect("path skips an edge")).sum();
+        assert!((walked - distance).abs() < 1e-9);
+    }
+
+    #[test]
+    fn matches_dijkstra_between_every_pair_of_nodes() {
+        let hierarchy = contract();
+        for from in 0..NODE_COUNT - 1 {
+            let origin = NodeIndex::new(from);
+            let distances = dijkstra(&vec![(origin, 0.0)]);
+            for (to, expected) in distances.iter().enumerate().take(NODE_COUNT - 1) {
+                let destination = NodeIndex::new(to);
+                let (distance, nodes) = hierarchy.shortest_path(&vec![(origin, 0.0)], &vec![(destination, 0.0)]).unwrap();
+                assert!((distance - expected).abs() < 1e-9, "{} -> {}: {} instead of {}", from, to, distance, expected);
+                assert_walkable(&nodes, origin, destination, distance);
+            }
+        }
+    }
+
+    #[test]
+    fn starts_and_ends_in_the_middle_of_edges() {
+        let hierarchy = contract();
+        /* 3 meters along 0 - 1, and 2 meters along 7 - 8 */
+        let origins = vec![(NodeIndex::new(0), 3.0), (NodeIndex::new(1), 7.0)];
+        let destinations = vec![(NodeIndex::new(7), 2.0), (NodeIndex::new(8), 8.0)];
+
+        let distances = dijkstra(&origins);
+        let expected = destinations.iter().map(|(node, seed)| distances[node.index()] + seed).fold(f64::INFINITY, f64::min);
+        let (distance, nodes) = hierarchy.shortest_path(&origins, &destinations).unwrap();
+        assert!((distance - expected).abs() < 1e-9);
+
+        let seed = |seeds: &Vec<(NodeIndex, f64)>, node: &NodeIndex| seeds.iter().find(|(seed, _)| seed == node).unwrap().1;
+        let (first, last) = (nodes[0], *nodes.last().unwrap());
+        assert_walkable(&nodes, first, last, distance - seed(&origins, &first) - seed(&destinations, &last));
+    }
+
+    #[test]
+    fn has_no_path_to_an_unconnected_node() {
+        let hierarchy = contract();
+        assert!(hierarchy.shortest_path(&vec![(NodeIndex::new(0), 0.0)], &vec![(NodeIndex::new(9), 0.0)]).is_none());
+    }
+}
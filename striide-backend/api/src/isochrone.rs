@@ -0,0 +1,292 @@
+/*
+ * Internal Imports
+ */
+use models::user::IsochroneQuery;
+use shared::response_models::RouteError;
+use crate::elevation::{grade_toward, walking_seconds};
+use crate::request_validation::{invalid_request, request_point};
+use crate::route_weights::is_well_lit;
+use crate::service_area::locate_region;
+use crate::snapping::{snap_to_edge, Snap};
+use crate::{GraphState, NavGraph};
+
+/*
+ * External Imports
+ */
+use geo::{ConcaveHull, HaversineDistance, MultiPoint, Point};
+use petgraph::graph::NodeIndex;
+use petgraph::visit::EdgeRef;
+use rocket::{serde::json::Json, serde::json::Value, State};
+use rstar::{primitives::GeomWithData, RTree};
+use serde_json::json;
+use ordered_float::OrderedFloat;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+
+/*
+ * constants
+ */
+const MAX_THRESHOLDS: usize = 5;
+const MAX_MINUTES: f64 = 60.0;
+/* relative measure of how tightly the outline wraps the reachable sidewalks - lower is tighter */
+const OUTLINE_CONCAVITY: f64 = 2.0;
+/* fewer points than this cannot outline an area */
+const MIN_OUTLINE_POINTS: usize = 3;
+/* reachable points further than this from the rest are outlined on their own - wider than a street, narrower than a river */
+const MAX_OUTLINE_GAP_METERS: f64 = 100.0;
+
+/*
+ *  Reached: entry of the search's open set, wrapped in Reverse so the heap pops the closest node first
+ *  seconds: walking time from the origin to the node
+ */
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Reached {
+    seconds: OrderedFloat<f64>,
+    node: NodeIndex,
+}
+
+impl Reached {
+    fn queued(seconds: f64, node: NodeIndex) -> Reverse<Reached> {
+        Reverse(Reached { seconds: OrderedFloat(seconds), node })
+    }
+}
+
+/*
+ *  isochrone: area reachable on foot from a point within each of the given number of minutes
+ *  purpose  : returns one GeoJSON polygon per threshold, outlining the sidewalks reachable in time,
+ *             or a multipolygon when they are in pieces that are far apart.
+ *             with well_lit_only set, only well lit sidewalks are walked - except for the sidewalk
+ *             the user is standing on.
+ */
+#[post("/isochrone", data = "<request>")]
+pub fn isochrone(request: Json<IsochroneQuery>, graph_state: &State<GraphState>) -> Result<Value, RouteError> {
+    let request = request.into_inner();
+    let origin = request_point(&request.origin, "origin")?;
+
+    if request.minutes.is_empty() || request.minutes.len() > MAX_THRESHOLDS {
+        return Err(invalid_request("invalid_minutes", &format!("minutes must contain between 1 and {} thresholds", MAX_THRESHOLDS), Some("minutes")));
+    }
+
+    if request.minutes.iter().any(|minutes| !(*minutes > 0.0 && *minutes <= MAX_MINUTES)) {
+        return Err(invalid_request("invalid_minutes", &format!("every threshold must be between 0 and {} minutes", MAX_MINUTES), Some("minutes")));
+    }
+
+    let (_, snapshot) = locate_region(origin, "origin", graph_state)?;
+    let navgraph: &NavGraph = &snapshot;
+    let snap = snap_to_edge(origin, "origin", navgraph)?;
+
+    let well_lit_only = request.well_lit_only.unwrap_or(false);
+    let mut thresholds = request.minutes.clone();
+    thresholds.sort_by(|a, b| a.total_cmp(b));
+    let budget = thresholds[thresholds.len() - 1] * 60.0;
+    let reached = walk_from(&snap, budget, well_lit_only, navgraph);
+
+    let features: Vec<Value> = thresholds.iter().map(|minutes| {
+        let points = reachable_points(&snap, &reached, minutes * 60.0, well_lit_only, navgraph);
+        let geometry = outline(points);
+
+        json!({
+            "type": "Feature",
+            "geometry": geometry,
+            "properties": {
+                "minutes": minutes,
+                "well_lit_only": well_lit_only,
+            }
+        })
+    }).collect();
+
+    Ok(json!({
+        "type": "FeatureCollection",
+        "features": features,
+    }))
+}
+
+/* dijkstra from both nodes of the snapped edge, stopping once the time budget is used up */
+fn walk_from(snap: &Snap, budget: f64, well_lit_only: bool, navgraph: &NavGraph) -> HashMap<NodeIndex, f64> {
+    let mut reached: HashMap<NodeIndex, f64> = HashMap::new();
+    let mut open: BinaryHeap<Reverse<Reached>> = BinaryHeap::new();
+
+    if let Some((source, target)) = navgraph.graph.edge_endpoints(snap.edge) {
+        for node in [source, target] {
+            let seconds = seconds_along(snap, node, navgraph);
+            if seconds <= budget && seconds < *reached.get(&node).unwrap_or(&f64::INFINITY) {
+                reached.insert(node, seconds);
+                open.push(Reached::queued(seconds, node));
+            }
+        }
+    }
+
+    while let Some(Reverse(Reached { seconds: OrderedFloat(seconds), node })) = open.pop() {
+        if seconds > *reached.get(&node).unwrap_or(&f64::INFINITY) {
+            continue;
+        }
+
+        for edge in navgraph.graph.edges(node) {
+            if well_lit_only && !is_well_lit(edge.weight()) {
+                continue;
+            }
+
+            let next = if edge.source() == node { edge.target() } else { edge.source() };
+            let next_seconds = seconds + walking_seconds(edge.weight().length, grade_toward(edge.id(), next, navgraph));
+            if next_seconds <= budget && next_seconds < *reached.get(&next).unwrap_or(&f64::INFINITY) {
+                reached.insert(next, next_seconds);
+                open.push(Reached::queued(next_seconds, next));
+            }
+        }
+    }
+
+    reached
+}
+
+/* every node reached within the threshold, plus the points where the time runs out part way along an edge */
+fn reachable_points(snap: &Snap, reached: &HashMap<NodeIndex, f64>, threshold: f64, well_lit_only: bool, navgraph: &NavGraph) -> Vec<Point> {
+    let mut points = vec![snap.point];
+
+    if let Some((source, target)) = navgraph.graph.edge_endpoints(snap.edge) {
+        for node in [source, target] {
+            let seconds = seconds_along(snap, node, navgraph);
+            if seconds > threshold {
+                points.push(point_between(snap.point, navgraph.graph[node], threshold / seconds));
+            }
+        }
+    }
+
+    for (node, seconds) in reached.iter().filter(|(_, seconds)| **seconds <= threshold) {
+        points.push(navgraph.graph[*node]);
+
+        for edge in navgraph.graph.edges(*node) {
+            if well_lit_only && !is_well_lit(edge.weight()) {
+                continue;
+            }
+
+            let next = if edge.source() == *node { edge.target() } else { edge.source() };
+            let edge_seconds = walking_seconds(edge.weight().length, grade_toward(edge.id(), next, navgraph));
+            if seconds + edge_seconds > threshold && edge_seconds > 0.0 {
+                let fraction = (threshold - seconds) / edge_seconds;
+                points.push(point_between(navgraph.graph[*node], navgraph.graph[next], fraction));
+            }
+        }
+    }
+
+    points
+}
+
+/* one ring around every piece of the reachable points, pieces too small to outline are left out */
+fn outline(points: Vec<Point>) -> Value {
+    let rings: Vec<Vec<(f64, f64)>> = pieces(points)
+        .into_iter()
+        .filter(|piece| piece.len() >= MIN_OUTLINE_POINTS)
+        .map(|piece| {
+            let hull = MultiPoint::from(piece).concave_hull(OUTLINE_CONCAVITY);
+            hull.exterior().points().map(|point| (point.x(), point.y())).collect()
+        })
+        .collect();
+
+    match rings.len() {
+        0 => Value::Null,
+        1 => json!({
+            "type": "Polygon",
+            "coordinates": rings,
+        }),
+        _ => json!({
+            "type": "MultiPolygon",
+            "coordinates": rings.into_iter().map(|ring| vec![ring]).collect::<Vec<_>>(),
+        }),
+    }
+}
+
+/*
+ *  pieces : groups of points chained together by gaps of at most MAX_OUTLINE_GAP_METERS
+ *  purpose: a single hull around both banks of a river would cover the water between them
+ */
+fn pieces(points: Vec<Point>) -> Vec<Vec<Point>> {
+    let tree = RTree::bulk_load(
+        points
+            .iter()
+            .enumerate()
+            .map(|(index, point)| GeomWithData::new([point.x(), point.y()], index))
+            .collect(),
+    );
+    let mut grouped = vec![false; points.len()];
+    let mut pieces = vec![];
+
+    for start in 0..points.len() {
+        if grouped[start] {
+            continue;
+        }
+
+        grouped[start] = true;
+        let mut piece = vec![];
+        let mut queue = VecDeque::from([start]);
+        while let Some(index) = queue.pop_front() {
+            let point = points[index];
+            piece.push(point);
+
+            /* a degree of longitude is the shortest one, so this radius covers the gap in every direction */
+            let radius = MAX_OUTLINE_GAP_METERS / point.haversine_distance(&Point::new(point.x() + 1.0, point.y()));
+            for neighbor in tree.locate_within_distance([point.x(), point.y()], radius * radius) {
+                let next = neighbor.data;
+                if !grouped[next] && point.haversine_distance(&points[next]) <= MAX_OUTLINE_GAP_METERS {
+                    grouped[next] = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+        pieces.push(piece);
+    }
+
+    pieces
+}
+
+fn seconds_along(snap: &Snap, node: NodeIndex, navgraph: &NavGraph) -> f64 {
+    walking_seconds(navgraph.graph[snap.edge].length * snap.portion_to(node, navgraph), grade_toward(snap.edge, node, navgraph))
+}
+
+/* sidewalk edges are short enough to interpolate in degrees */
+fn point_between(start: Point, end: Point, fraction: f64) -> Point {
+    Point::new(
+        start.x() + (end.x() - start.x()) * fraction,
+        start.y() + (end.y() - start.y()) * fraction,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /* corners and center of a square about 50 meters wide */
+    fn block(x: f64, y: f64) -> Vec<Point> {
+        [(0.0, 0.0), (0.0006, 0.0), (0.0006, 0.0004), (0.0, 0.0004), (0.0003, 0.0002)]
+            .iter()
+            .map(|(dx, dy)| Point::new(x + dx, y + dy))
+            .collect()
+    }
+
+    #[test]
+    fn outlines_close_points_as_one_polygon() {
+        let mut points = block(-79.400, 43.650);
+        points.extend(block(-79.399, 43.650));
+
+        let geometry = outline(points);
+        assert_eq!(geometry["type"], "Polygon");
+        assert_eq!(geometry["coordinates"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn outlines_pieces_far_apart_separately() {
+        let mut points = block(-79.400, 43.650);
+        points.extend(block(-79.390, 43.650));
+
+        let geometry = outline(points);
+        assert_eq!(geometry["type"], "MultiPolygon");
+        assert_eq!(geometry["coordinates"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn leaves_out_pieces_too_small_to_outline() {
+        let mut points = block(-79.400, 43.650);
+        points.push(Point::new(-79.390, 43.650));
+        assert_eq!(outline(points)["type"], "Polygon");
+        assert_eq!(outline(vec![Point::new(-79.390, 43.650)]), Value::Null);
+    }
+}
@@ -1,12 +1,13 @@
 use std::{collections::HashMap, io::Read, path::Path};
 
-use geo::Point;
+use geo::{ConvexHull, LineString, MultiPoint, Point, Polygon};
 use petgraph::{
-    graph::{NodeIndex, UnGraph},
+    graph::{EdgeIndex, NodeIndex, UnGraph},
     Undirected,
 };
 
 use flate2::read::GzDecoder;
+use rstar::primitives::{GeomWithData, Line};
 use rstar::RTree;
 use serde_json::Value;
 
@@ -15,20 +16,100 @@ extern crate rocket;
 
 mod auth;
 mod business;
+mod diagnostics;
+mod directions;
+mod elevation;
 mod pathfinder;
+mod request_validation;
+mod route_cache;
+mod route_export;
+mod route_matrix;
+mod route_weights;
+mod routing_provider;
+mod safe_places;
+mod saved_routes;
+mod service_area;
+mod snapping;
+mod trips;
 mod user_reports;
 mod feedback;
+mod graph_state;
+mod hazards;
+mod hierarchy;
+mod isochrone;
 mod onboarding;
+#[cfg(test)]
+mod test_graph;
+
+pub use graph_format::GraphHeader;
+use hierarchy::{load_hierarchy, ContractionHierarchy};
+pub use graph_state::{stage as graph_stage, GraphState};
+pub use route_cache::{stage as route_cache_stage, RouteCache};
+pub use routing_provider::{stage as routing_provider_stage, RoutingProvider};
+pub use trips::stage as trip_stage;
 
 extern crate shared;
-use crate::shared::types::{F64Wrapper, HashablePoint};
+use crate::shared::types::HashablePoint;
+
+/*
+ *  NavEdge          : weight of an edge in the navigation graph
+ *  length           : haversine length of the edge in meters
+ *  safety           : extra cost (in meters) for walking along a poorly lit edge
+ *  light_density    : lights per 100 meters along the sidewalk of the edge
+ *  building_density : buildings per 100 meters along the sidewalk of the edge
+ *  street_name      : name of the street the sidewalk runs along, if known
+ *  light_count      : lights next to this edge
+ *  building_count   : buildings next to this edge
+ *  sidewalk_width   : narrowest clear width of the sidewalk in meters, if known
+ *  curb_ramps       : whether the sidewalk has curb ramps, if known
+ *  stairs           : the edge is part of a flight of steps
+ *  surface          : surface type of the sidewalk, if known
+ *  grade            : rise over run from the edge's source node to its target node, if both elevations are known
+ */
+#[derive(Debug, Clone)]
+pub struct NavEdge {
+    pub length: f64,
+    pub safety: f64,
+    pub light_density: f64,
+    pub building_density: f64,
+    pub street_name: Option<String>,
+    pub light_count: usize,
+    pub building_count: usize,
+    pub sidewalk_width: Option<f64>,
+    pub curb_ramps: Option<bool>,
+    pub stairs: bool,
+    pub surface: Option<String>,
+    pub grade: Option<f64>,
+}
 
 #[derive(Debug)]
 pub struct NavGraph {
-    pub graph: petgraph::Graph<geo::Point, f64, Undirected>,
+    pub graph: petgraph::Graph<geo::Point, NavEdge, Undirected>,
     pub point_to_index_map: HashMap<HashablePoint, NodeIndex>,
     pub index_to_point_map: HashMap<NodeIndex, HashablePoint>,
     pub distance_tree: RTree<geo::Point>,
+    pub edge_tree: RTree<GeomWithData<Line<[f64; 2]>, EdgeIndex>>,
+    pub service_area: Polygon<f64>,
+    pub header: Option<GraphHeader>,
+    pub hierarchy: Option<ContractionHierarchy>,
+    /* connected component of every node, indexed by node */
+    pub components: Vec<u32>,
+    /* elevation in meters of every node, indexed by node - empty when the graph has no elevations */
+    pub elevations: Vec<Option<f64>>,
+}
+
+impl NavGraph {
+    /* build time of the graph file, saved with routes so they can be traced back to the data they were computed on */
+    pub fn version(&self) -> String {
+        match &self.header {
+            Some(header) => header.built_at.to_string(),
+            None => "unversioned".to_owned(),
+        }
+    }
+
+    pub fn has_elevations(&self) -> bool {
+        !self.elevations.is_empty()
+    }
 }
 
 pub fn routes() -> Vec<rocket::Route> {
@@ -44,6 +125,14 @@ pub fn routes() -> Vec<rocket::Route> {
         pathfinder::query_route,
         pathfinder::test_routes,
         pathfinder::test_mapbox, 
+        route_matrix::route_matrix, 
+        safe_places::nearest_safe_place, 
+        isochrone::isochrone, 
+        graph_state::reload_graph, 
+        graph_state::regions, 
+        diagnostics::graph_diagnostics, 
+        route_cache::route_cache_stats, 
+        service_area::service_area, 
         user_reports::upload_report,  
         user_reports::fetch_reports, 
         user_reports::fetch_report_draft, 
@@ -55,96 +144,206 @@ pub fn routes() -> Vec<rocket::Route> {
         user_reports::like_report,
         feedback::submit_feedback,
         onboarding::onboarder,
+        saved_routes::save_route,
+        saved_routes::saved_routes,
+        saved_routes::shared_route,
+        saved_routes::revoke_route,
+        trips::start_trip,
+        trips::report_location,
+        trips::get_trip,
+        trips::end_trip,
 
     ]
 }
 
-pub fn load_graph() -> NavGraph {
-    let graph_file_path = Path::new("./api/src/output.json.gz");
+/*
+ *  load_graph: reads and validates the navigation graph produced by gen_graph
+ *  purpose   : returns an error instead of panicking so a reload can keep the graph currently in use.
+ *              The binary format is detected by its magic bytes, anything else is read as the
+ *              gzipped JSON export. Binary graphs also pick up the contraction hierarchy built
+ *              for them, when there is one.
+ */
+pub fn load_graph(graph_file_path: &str) -> Result<NavGraph, String> {
+    if graph_format::is_binary_graph(graph_file_path)? {
+        let (header, body) = graph_format::read_binary_graph(graph_file_path)?;
+        let nodes = body.nodes.iter().map(|node| Point::new(node[0], node[1])).collect();
+        let edges = body
+            .edges
+            .into_iter()
+            .map(|edge| {
+                (
+                    edge.source as usize,
+                    edge.target as usize,
+                    NavEdge {
+                        length: edge.length,
+                        safety: edge.safety,
+                        light_density: edge.light_density,
+                        building_density: edge.building_density,
+                        street_name: edge.street_name,
+                        light_count: edge.light_count as usize,
+                        building_count: edge.building_count as usize,
+                        sidewalk_width: edge.sidewalk_width,
+                        curb_ramps: edge.curb_ramps,
+                        stairs: edge.stairs,
+                        surface: edge.surface,
+                        grade: None,
+                    },
+                )
+            })
+            .collect();
+        let service_area = body.service_area.iter().map(|point| Point::new(point[0], point[1])).collect();
 
-    let file = match std::fs::File::open(graph_file_path) {
-        Ok(file) => file,
-        Err(err) => panic!("Error opening compressed graph file: {}", err),
-    };
+        let hierarchy = load_hierarchy(graph_file_path, &header);
+        let mut navgraph = build_nav_graph(Some(header), nodes, edges, Some(service_area), body.elevations)?;
+        /* the hierarchy is built on distances, routes over hills are weighted by walking time instead */
+        navgraph.hierarchy = if navgraph.has_elevations() { None } else { hierarchy };
+        Ok(navgraph)
+    } else {
+        load_json_graph(graph_file_path)
+    }
+}
+
+/* gzipped JSON written by gen_graph --json - edges repeat the coordinates of their endpoints */
+fn load_json_graph(graph_file_path: &str) -> Result<NavGraph, String> {
+    let file = std::fs::File::open(Path::new(graph_file_path))
+        .map_err(|err| format!("Error opening compressed graph file {}: {}", graph_file_path, err))?;
     let mut decoder = GzDecoder::new(file);
 
     let mut decompressed_data = String::new();
-    match decoder.read_to_string(&mut decompressed_data) {
-        Ok(contents) => contents,
-        Err(err) => panic!("Could not decode the file - with error: {}", err),
-    };
+    decoder
+        .read_to_string(&mut decompressed_data)
+        .map_err(|err| format!("Could not decode the file - with error: {}", err))?;
 
-    let mut graph: petgraph::Graph<geo::Point, f64, Undirected> = UnGraph::new_undirected();
-    let mut point_to_index_map: HashMap<HashablePoint, NodeIndex> = HashMap::new();
-    let mut index_to_point_map: HashMap<NodeIndex, HashablePoint> = HashMap::new();
+    let graph_map: HashMap<&str, Value> = serde_json::from_str(&decompressed_data)
+        .map_err(|err| format!("could not parse JSON with error: {}", err))?;
 
-    let graph_map: HashMap<&str, Value> = match serde_json::from_str(&decompressed_data) {
-        Ok(data) => data,
-        Err(err) => panic!("could not parse JSON with error: {}", err),
-    };
+    let nodes = graph_map.get("nodes").and_then(|nodes| nodes.as_array()).ok_or("graph file has no nodes array")?;
+    let edges = graph_map.get("edges").and_then(|edges| edges.as_array()).ok_or("graph file has no edges array")?;
 
-    let nodes = graph_map["nodes"].as_array().unwrap();
-    let edges = graph_map["edges"].as_array().unwrap();
-
-    let mut points = vec![];
-
-    for node in nodes {
-        let graph_node = node.as_object().unwrap();
-        let graph_point = Point::new(
-            graph_node["x"].as_f64().unwrap(),
-            graph_node["y"].as_f64().unwrap(),
-        );
+    let points = nodes.iter().map(parse_point).collect::<Result<Vec<Point>, String>>()?;
+    let mut point_positions: HashMap<HashablePoint, usize> = HashMap::new();
+    for (position, point) in points.iter().enumerate() {
+        point_positions.insert(HashablePoint::new(point.x(), point.y()), position);
+    }
 
-        // todo: figure this out
-        let hashable_point = HashablePoint {
-            x: F64Wrapper(graph_point.x()),
-            y: F64Wrapper(graph_point.y()),
+    let mut nav_edges = vec![];
+    for edge in edges {
+        let source_point = parse_point(&edge[0])?;
+        let target_point = parse_point(&edge[1])?;
+        let weight_obj = edge[2].as_object().ok_or("edge weight is not an object")?;
+        let number = |key: &str| {
+            weight_obj
+                .get(key)
+                .and_then(|value| value.as_f64())
+                .ok_or(format!("edge weight is missing {}", key))
         };
 
-        let hashable_point_copy = HashablePoint {
-            x: F64Wrapper(graph_point.x()),
-            y: F64Wrapper(graph_point.y()),
+        let weight = NavEdge {
+            length: number("length")?,
+            safety: number("safety")?,
+            light_density: number("light_density")?,
+            building_density: number("building_density")?,
+            street_name: weight_obj.get("street_name").and_then(|name| name.as_str()).map(|name| name.to_owned()),
+            light_count: weight_obj.get("light_count").and_then(|count| count.as_u64()).unwrap_or(0) as usize,
+            building_count: weight_obj.get("building_count").and_then(|count| count.as_u64()).unwrap_or(0) as usize,
+            sidewalk_width: weight_obj.get("sidewalk_width").and_then(|width| width.as_f64()),
+            curb_ramps: weight_obj.get("curb_ramps").and_then(|curb_ramps| curb_ramps.as_bool()),
+            stairs: weight_obj.get("stairs").and_then(|stairs| stairs.as_bool()).unwrap_or(false),
+            surface: weight_obj.get("surface").and_then(|surface| surface.as_str()).map(|surface| surface.to_owned()),
+            grade: None,
         };
 
-        let node_index = graph.add_node(graph_point);
-        point_to_index_map.insert(hashable_point, node_index);
-        index_to_point_map.insert(node_index, hashable_point_copy);
+        let source = *point_positions
+            .get(&HashablePoint::new(source_point.x(), source_point.y()))
+            .ok_or("edge source point is not a node of the graph")?;
+        let target = *point_positions
+            .get(&HashablePoint::new(target_point.x(), target_point.y()))
+            .ok_or("edge target point is not a node of the graph")?;
 
-        points.push(graph_point);
+        nav_edges.push((source, target, weight));
     }
 
-    for edge in edges {
-        let source_obj = edge[0].as_object().unwrap();
-        let target_obj = edge[1].as_object().unwrap();
-        let weight = edge[2].as_f64().unwrap();
+    let service_area = match graph_map.get("service_area").and_then(|area| area.as_array()) {
+        Some(ring) => Some(ring.iter().map(parse_point).collect::<Result<Vec<Point>, String>>()?),
+        None => None,
+    };
 
-        let source_hashable_point = HashablePoint {
-            x: F64Wrapper(source_obj["x"].as_f64().unwrap()),
-            y: F64Wrapper(source_obj["y"].as_f64().unwrap()),
-        };
+    /* graphs exported without an elevation model have no elevations */
+    let elevations = match graph_map.get("elevations").and_then(|elevations| elevations.as_array()) {
+        Some(elevations) => elevations.iter().map(|elevation| elevation.as_f64()).collect(),
+        None => vec![None; points.len()],
+    };
 
-        let target_hashable_point = HashablePoint {
-            x: F64Wrapper(target_obj["x"].as_f64().unwrap()),
-            y: F64Wrapper(target_obj["y"].as_f64().unwrap()),
-        };
+    build_nav_graph(None, points, nav_edges, service_area, elevations)
+}
 
-        let source_index = match point_to_index_map.get(&source_hashable_point) {
-            Some(point) => *point,
-            None => panic!("SOURCE POINT SHOULD EXIST"),
-        };
+/* builds the graph and its lookup structures - edges reference nodes by their position in nodes */
+fn build_nav_graph(
+    header: Option<GraphHeader>,
+    nodes: Vec<Point>,
+    edges: Vec<(usize, usize, NavEdge)>,
+    service_area: Option<Vec<Point>>,
+    elevations: Vec<Option<f64>>,
+) -> Result<NavGraph, String> {
+    if nodes.is_empty() || edges.is_empty() {
+        return Err("graph file does not contain any nodes or edges".to_owned());
+    }
+    if elevations.len() != nodes.len() {
+        return Err(format!("graph file has {} elevations for {} nodes", elevations.len(), nodes.len()));
+    }
 
-        let target_index = match point_to_index_map.get(&target_hashable_point) {
-            Some(point) => *point,
-            None => panic!("TARGET POINT SHOULD EXIST"),
-        };
+    let mut graph: petgraph::Graph<geo::Point, NavEdge, Undirected> =
+        UnGraph::with_capacity(nodes.len(), edges.len());
+    let mut point_to_index_map: HashMap<HashablePoint, NodeIndex> = HashMap::with_capacity(nodes.len());
+    let mut index_to_point_map: HashMap<NodeIndex, HashablePoint> = HashMap::with_capacity(nodes.len());
 
-        graph.add_edge(source_index, target_index, weight);
+    for graph_point in nodes.iter() {
+        let node_index = graph.add_node(*graph_point);
+        point_to_index_map.insert(HashablePoint::new(graph_point.x(), graph_point.y()), node_index);
+        index_to_point_map.insert(node_index, HashablePoint::new(graph_point.x(), graph_point.y()));
     }
 
-    NavGraph {
-        graph: graph,
-        point_to_index_map: point_to_index_map,
-        index_to_point_map: index_to_point_map,
-        distance_tree: RTree::bulk_load(points),
+    let mut lines = Vec::with_capacity(edges.len());
+    for (source, target, mut weight) in edges {
+        if source >= nodes.len() || target >= nodes.len() {
+            return Err(format!("edge references node {} but the graph has {} nodes", source.max(target), nodes.len()));
+        }
+        weight.grade = elevation::edge_grade(elevations[source], elevations[target], weight.length);
+
+        let (source_point, target_point) = (nodes[source], nodes[target]);
+        let edge_index = graph.add_edge(NodeIndex::new(source), NodeIndex::new(target), weight);
+        lines.push(GeomWithData::new(
+            Line::new([source_point.x(), source_point.y()], [target_point.x(), target_point.y()]),
+            edge_index,
+        ));
+    }
+
+    /* graphs generated before the service area was stored fall back to the convex hull of their nodes */
+    let service_area = match service_area {
+        Some(ring) => Polygon::new(LineString::from(ring), vec![]),
+        None => MultiPoint::from(nodes.clone()).convex_hull(),
+    };
+
+    let components = diagnostics::connected_components(&graph);
+    let elevations = if elevations.iter().all(|elevation| elevation.is_none()) { vec![] } else { elevations };
+
+    Ok(NavGraph {
+        graph,
+        point_to_index_map,
+        index_to_point_map,
+        distance_tree: RTree::bulk_load(nodes),
+        edge_tree: RTree::bulk_load(lines),
+        service_area,
+        header,
+        hierarchy: None,
+        components,
+        elevations,
+    })
+}
+
+fn parse_point(value: &Value) -> Result<Point, String> {
+    match (value["x"].as_f64(), value["y"].as_f64()) {
+        (Some(x), Some(y)) => Ok(Point::new(x, y)),
+        _ => Err(format!("expected a point with x and y coordinates, found {}", value)),
     }
 }
@@ -1,15 +1,13 @@
-use rocket::http::{CookieJar, Status};
+use rocket::http::Status;
 use rocket::serde::json::Json;
 use rocket::serde::Deserialize;
-use rocket_db_pools::{sqlx, Connection};
-use chrono::{DateTime, Utc};
+use rocket_db_pools::Connection;
 
-use models::onboarding::{UserInfo, Gender, Transport_mode, Commmute_frequency, Travel_time, Feed_type};
-use models::user::UserSession;
+use models::onboarding::UserInfo;
 use infrastructure::database::Db;
-use application::{auth, utils, onboarding};
+use application::onboarding;
 use shared::response_models::{
-    ErrorResponse, NewToken, Response, ResponseBody, TokenResponse
+    ErrorResponse, Response, ResponseBody
 };
 
 
@@ -35,18 +33,18 @@ pub async fn onboarder(
     let email = data.email;
     let user_info = data.user_info;
     //create a table
-    match onboarding::create_user_info_table(&mut **db).await {
+    match onboarding::create_user_info_table(&mut db).await {
         Ok(_) => {
             println!("Table created successfully");
         },
-        Err(e) => {
+        Err(_) => {
             println!("Failed to create table");
         }
     }
     //print the user info
     println!("{:?}", user_info);
     
-    match onboarding::insert_user(&mut **db, email, user_info).await {
+    match onboarding::insert_user(&mut db, email, user_info).await {
         Ok(_) => {
             // Update the user's onboarding status
             
@@ -55,7 +53,7 @@ pub async fn onboarder(
                 body: ResponseBody::Message("Onboarding completed successfully".to_owned()),
             })
         },
-        Err(e) => Err(ErrorResponse {
+        Err(_) => Err(ErrorResponse {
             status: Status::InternalServerError,
             message:"Failed to save user information".to_owned()
         }),
@@ -1,309 +1,988 @@
 /*
  * Internal Imports
  */
-use models::user::UserRouteQuery; 
-use shared::response_models::{ComputedPath, ErrorResponse, Response, ResponseBody}; 
-use shared::types::HashablePoint; 
-use crate::NavGraph;
+use application::business::get_open_business_locations; 
+use application::upload_reports::get_recent_published_reports; 
+use infrastructure::database::Db; 
+use models::user::{RoutePreference, UserRouteQuery}; 
+use shared::response_models::{
+    ComputedPath, ErrorResponse, LineGeometry, Maneuver, RouteAlternative, RouteComparison,
+    RouteError, RouteHazard, RouteLeg, SegmentCollection, SegmentFeature, SegmentProperties,
+}; 
+use crate::diagnostics::ensure_connected;
+use crate::directions::build_maneuvers;
+use crate::elevation::{climb, min_slope_factor, node_elevation, snap_elevation};
+use crate::graph_state::Region;
+use crate::hazards::{oldest_active_report, Hazards};
+use crate::hierarchy::ContractionHierarchy;
+use crate::request_validation::{request_point, CORRECT_COORDINATE_AMOUNT, X_POSITION_INDICATOR, Y_POSITION_INDICATOR};
+use crate::route_cache::{CachedRoutes, RouteCache, RouteCacheKey};
+use crate::route_weights::{is_well_lit, RouteWeights};
+use crate::route_export::{export_route, route_features, ExportedRoute, RouteFormat};
+use crate::routing_provider::{compare_routes, BaselineProvider};
+use crate::service_area::{ensure_in_service_area, locate_region};
+use crate::snapping::{snap_to_accessible_edge, Snap};
+use crate::{GraphState, NavGraph};
 
 /*
 * External Imports
 */
-use reqwest::Client;
-use rocket::serde::Deserialize;
+use chrono::{DateTime, Utc};
+use rocket_db_pools::{sqlx::PgConnection, Connection};
 use rocket::{http::Status, serde::json::Json, State, serde::json::Value};
-use geo::Point;
+use geo::{HaversineDistance, Point};
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use petgraph::visit::EdgeRef;
 use serde_json::json;
+use ordered_float::OrderedFloat;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::sync::Arc;
 
 /* 
  * constants 
  */
-const CORRECT_COORDINATE_AMOUNT: usize = 2; 
-const X_POSITION_INDICATOR: usize = 0;
-const Y_POSITION_INDICATOR: usize = 1;
+/* average walking speed used for the eta of a route */
+pub const WALKING_SPEED_METERS_PER_SECOND: f64 = 1.4;
+/* most alternative routes returned alongside the main route */
+const MAX_ALTERNATIVES: usize = 3;
+/* every time an edge is used by a route its cost is multiplied by this factor */
+const ALTERNATIVE_PENALTY: f64 = 1.6;
+/* number of pathfinder runs allowed per requested alternative */
+const ALTERNATIVE_ATTEMPT_FACTOR: usize = 3;
+/* alternatives sharing more than this fraction of their length with another route are dropped */
+const MAX_SHARED_FRACTION: f64 = 0.75;
+/* alternatives longer than this factor of the main route are dropped */
+const MAX_DETOUR_RATIO: f64 = 1.5;
+/* most waypoints allowed between the origin and the destination */
+const MAX_WAYPOINTS: usize = 10;
+/* waypoint order is optimized by trying every order, so only short lists can be optimized */
+const MAX_OPTIMIZED_WAYPOINTS: usize = 6;
 
-#[post("/query_route", data = "<request>")]
-pub fn query_route(request: Json<UserRouteQuery>, navgraph: &State<NavGraph>) -> Result<Response, ErrorResponse> {
+/*
+ *  RoutePath     : result of running the pathfinder between an origin and a destination
+ *  distance      : total walking distance of the route in meters
+ *  duration      : estimated walking time of the route in seconds
+ *  lit_percentage: percentage of the distance walked along well lit sidewalks
+ *  safety_score  : 0-100 score combining lights and businesses along the route
+ *  instructions  : turn by turn walking instructions for the route
+ *  hazards       : user reports the route passes or was diverted around
+ *  legs          : parts of the route between consecutive stops
+ *  breakdown     : safety breakdown of every segment of the route
+ *  ascent        : meters climbed along the route, 0 when the graph has no elevations
+ *  descent       : meters descended along the route
+ *  elevation_profile: [meters from the start, elevation in meters] of the route's points with a known elevation
+ */
+#[derive(Clone)]
+pub struct RoutePath {
+    segments: Vec<RouteSegment>,
+    coordinates: Vec<(f64, f64)>,
+    distance: f64,
+    duration: f64,
+    lit_percentage: f64,
+    safety_score: f64,
+    instructions: Vec<Maneuver>,
+    hazards: Vec<RouteHazard>,
+    legs: Vec<RouteLeg>,
+    breakdown: Vec<SegmentFeature>,
+    ascent: f64,
+    descent: f64,
+    elevation_profile: Vec<(f64, f64)>,
+}
+
+/*
+ *  RouteSegment: part of a graph edge walked by a route
+ *  length      : meters walked along the edge - less than the edge's length where the route
+ *                starts or ends in the middle of the edge
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct RouteSegment {
+    pub edge: EdgeIndex,
+    pub length: f64,
+}
 
+/* route through the requested stops - format picks json (default), geojson, polyline6 or gpx */
+#[post("/query_route?<format>", data = "<request>")]
+pub async fn query_route(
+    request: Json<UserRouteQuery>,
+    format: Option<String>,
+    graph_state: &State<GraphState>,
+    baseline: &State<BaselineProvider>,
+    route_cache: &State<RouteCache>,
+    mut db: Connection<Db>,
+) -> Result<ExportedRoute, RouteError> {
+    let format = RouteFormat::parse(format.as_deref())?; 
     let request = request.into_inner(); 
-    if request.origin.len() != CORRECT_COORDINATE_AMOUNT {
-        return Err(ErrorResponse {
-            status: Status::BadRequest, 
-            message: "origins array must only contain user's current longitude and latitude coordinates".to_owned(),  
-        }) 
-    }
+    let (region, snapshot) = origin_region(&request, graph_state)?; 
+    let path = compute_route(&request, &region.config.key, &snapshot, baseline, route_cache, &mut db).await?; 
 
-    if request.destination.len() != CORRECT_COORDINATE_AMOUNT {
-        return Err(ErrorResponse {
-            status: Status::BadRequest, 
-            message: "destinations array must only contain user's destination longitude and latitude coordinates".to_owned(), 
-        }) 
-    }
-    // todo: figure out a way to handle the case where the user's origin coordinates are within bounding polygon (i.e. area of service) 
-    // todo: or just figure that out on the frontend and verify on the backend. 
-    let path = compute_path(&request.origin, &request.destination, navgraph)?; 
-    // let mapbox_suggestion = get_shortest_mapbox_suggestion(&request.origin, &request.destination).await?; 
-
-    Ok(Response {
-        status: Status::Ok, 
-        body: ResponseBody::ComputedPath(ComputedPath {
-            striide_route: path, 
-            mapbox_route: vec![], 
+    Ok(export_route(path, format))
+}
+
+/*
+ *  compute_route: main route and alternatives for a route query in the origin's region, with the
+ *                 baseline comparison when it was asked for
+ *  purpose      : the weights and hazards are loaded for every query since they are part of the cache
+ *                 key - only the pathfinder runs are skipped when the same route was computed recently.
+ *                 cached routes are moved onto the stops of the query, which can be up to a meter
+ *                 away from the stops they were computed for.
+ */
+pub async fn compute_route(
+    request: &UserRouteQuery,
+    region: &str,
+    navgraph: &NavGraph,
+    baseline: &BaselineProvider,
+    route_cache: &RouteCache,
+    conn: &mut PgConnection,
+) -> Result<ComputedPath, RouteError> {
+    request_point(&request.destination, "destination")?; 
+
+    let weights = build_weights(&request.preference, request.departure_time, conn, navgraph).await?.with_accessibility(request.accessibility); 
+    let stops = snap_stops(request, &weights, navgraph)?; 
+    let hazards = load_hazards(conn, navgraph).await; 
+    let optimize_waypoints = request.optimize_waypoints.unwrap_or(false); 
+    let alternative_count = request.alternatives.unwrap_or(0).min(MAX_ALTERNATIVES); 
+
+    let cache_key = RouteCacheKey::new(region, &stops, optimize_waypoints, alternative_count, &weights, &hazards, navgraph); 
+    let CachedRoutes { waypoint_order, mut routes } = match route_cache.get(&cache_key) {
+        Some(mut cached) => {
+            let stops = visiting_order(&stops, &cached.waypoint_order); 
+            for route in cached.routes.iter_mut() {
+                anchor_to_stops(route, &stops); 
+            }
+            cached
+        }
+        None => {
+            let waypoint_order = order_stops(&stops, optimize_waypoints, &weights, navgraph)?; 
+            let routes = plan_routes(&visiting_order(&stops, &waypoint_order), alternative_count, weights, &hazards, navgraph); 
+            let computed = CachedRoutes { waypoint_order, routes }; 
+            route_cache.insert(cache_key, computed.clone()); 
+            computed
+        }
+    }; 
+    let stops = visiting_order(&stops, &waypoint_order); 
+    let path = routes.remove(0); 
+    if path.segments.is_empty() {
+        return Err(RouteError {
+            status: Status::UnprocessableEntity, 
+            code: "route_not_found".to_owned(), 
+            message: "no walking route exists between the stops with the current data".to_owned(), 
+            field: None, 
         })
+    }
+    let alternatives = routes; 
+    let (mapbox_route, comparison) = if request.compare.unwrap_or(false) {
+        compare_with_baseline(&stops, &path, baseline, navgraph).await
+    } else {
+        (vec![], None)
+    }; 
+
+    Ok(ComputedPath {
+        striide_route: path.coordinates, 
+        mapbox_route, 
+        distance_meters: path.distance, 
+        eta_seconds: path.duration, 
+        lit_percentage: path.lit_percentage, 
+        safety_score: path.safety_score, 
+        origin_snap_meters: stops[0].distance, 
+        destination_snap_meters: stops[stops.len() - 1].distance, 
+        instructions: path.instructions, 
+        hazards: path.hazards, 
+        legs: path.legs, 
+        waypoint_order, 
+        segments: segment_collection(path.breakdown), 
+        ascent_meters: path.ascent, 
+        descent_meters: path.descent, 
+        elevation_profile: path.elevation_profile, 
+        alternatives: alternatives.into_iter().map(|alternative| RouteAlternative {
+            route: alternative.coordinates, 
+            distance_meters: alternative.distance, 
+            eta_seconds: alternative.duration, 
+            lit_percentage: alternative.lit_percentage, 
+            safety_score: alternative.safety_score, 
+            instructions: alternative.instructions, 
+            hazards: alternative.hazards, 
+            segments: segment_collection(alternative.breakdown), 
+            ascent_meters: alternative.ascent, 
+            descent_meters: alternative.descent, 
+            elevation_profile: alternative.elevation_profile, 
+        }).collect(), 
+        comparison, 
     })
 }
 
-fn compute_path(origin: &Vec<f64>, destination: &Vec<f64>, navgraph: &State<NavGraph>) -> Result<Vec<(f64, f64)>, ErrorResponse> {
-    let origin_x = origin[X_POSITION_INDICATOR];
-    let origin_y = origin[Y_POSITION_INDICATOR]; 
+/* baseline route of the routing provider through the same stops - comparisons are optional, so failures only skip them */
+async fn compare_with_baseline(
+    stops: &[Snap],
+    path: &RoutePath,
+    baseline: &BaselineProvider,
+    navgraph: &NavGraph,
+) -> (Vec<(f64, f64)>, Option<RouteComparison>) {
+    let provider = match &baseline.0 {
+        Some(provider) => provider, 
+        None => return (vec![], None), 
+    }; 
 
-    let destination_x = destination[X_POSITION_INDICATOR];
-    let destination_y = destination[Y_POSITION_INDICATOR];
+    let points: Vec<(f64, f64)> = stops.iter().map(|stop| (stop.point.x(), stop.point.y())).collect(); 
+    match provider.walking_route(&points).await {
+        Ok(route) => {
+            let comparison = compare_routes(provider.name(), &route, path.distance, path.lit_percentage, navgraph); 
+            (route, Some(comparison))
+        }
+        Err(err) => {
+            println!("skipping the {} comparison: {}", provider.name(), err.message); 
+            (vec![], None)
+        }
+    }
+}
 
-    let nearest_point_to_origin = navgraph.distance_tree.nearest_neighbor(&Point::new(origin_x, origin_y)).unwrap(); 
-    let nearest_point_to_destination = navgraph.distance_tree.nearest_neighbor(&Point::new(destination_x, destination_y)).unwrap();
+/*
+ *  build_weights: route weights for the user's preference at the departure time (now by default)
+ *  purpose      : sidewalks next to businesses that are open when the user walks by are preferred.
+ *                 opening hours only refine the weights, so routing still works when they cannot be loaded.
+ */
+pub async fn build_weights(
+    preference: &Option<RoutePreference>,
+    departure_time: Option<DateTime<Utc>>,
+    conn: &mut PgConnection,
+    navgraph: &NavGraph,
+) -> Result<RouteWeights, ErrorResponse> {
+    let weights = RouteWeights::from_preference(preference)?; 
+    let departure_time = departure_time.unwrap_or_else(Utc::now); 
+
+    match get_open_business_locations(conn, departure_time).await {
+        Ok(locations) => Ok(weights.with_open_businesses(&locations, navgraph)), 
+        Err(err) => {
+            println!("routing without opening hours: {}", err.message); 
+            Ok(weights)
+        }
+    }
+}
 
-    let nearest_origin_hashable = HashablePoint::new(nearest_point_to_origin.x(), nearest_point_to_origin.y());
-    let nearest_destination_hashable = HashablePoint::new(nearest_point_to_destination.x(), nearest_point_to_destination.y());
+/* active hazard reports - like opening hours they only refine routing, so failing to load them is not fatal */
+pub async fn load_hazards(conn: &mut PgConnection, navgraph: &NavGraph) -> Hazards {
+    let now = Utc::now(); 
 
-    let origin_node_index: Result<_, ErrorResponse> = match navgraph.point_to_index_map.get(&nearest_origin_hashable) {
-        Some(index) => Ok(index), 
-        None => return Err(ErrorResponse {
-            status: Status::BadRequest, 
-            message: "bad origin point request - coordinates are not found".to_owned()
-        })
-    }; 
+    match get_recent_published_reports(conn, oldest_active_report(now)).await {
+        Ok(reports) => Hazards::locate(reports, now, navgraph), 
+        Err(err) => {
+            println!("routing without hazard reports: {}", err.message); 
+            Hazards::default()
+        }
+    }
+}
 
-    let destination_node_index: Result<_, ErrorResponse> = match navgraph.point_to_index_map.get(&nearest_destination_hashable) {
-        Some(index) => Ok(index), 
-        None => return Err(ErrorResponse {
-            status: Status::BadRequest, 
-            message: "bad origin point request - coordinates are not found".to_owned()
+/*
+ *  plan_routes: main route through every stop followed by its alternatives
+ *  purpose    : edges close to active reports are penalized. When there are any, the main route is
+ *               also computed without the penalties so every route can tell which reports it avoided.
+ *               alternatives are only computed for routes without waypoints.
+ */
+fn plan_routes(
+    stops: &Vec<Snap>,
+    alternative_count: usize,
+    weights: RouteWeights,
+    hazards: &Hazards,
+    navgraph: &NavGraph,
+) -> Vec<RoutePath> {
+    let unaffected = if hazards.is_empty() { empty_route() } else { compute_stops_path(stops, &weights, navgraph) };
+    let weights = weights.with_hazards(hazards);
+
+    let path = compute_stops_path(stops, &weights, navgraph);
+    let alternatives = match stops.as_slice() {
+        [origin, destination] => compute_alternatives(&path, origin, destination, alternative_count, &weights, navgraph),
+        _ => vec![],
+    };
+
+    std::iter::once(path)
+        .chain(alternatives)
+        .map(|mut route| {
+            route.hazards = hazards.influencing(&route.segments, &unaffected.segments);
+            route.breakdown = segment_breakdown(&route, &weights, hazards, navgraph);
+            route
         })
-    }; 
+        .collect()
+}
 
-    let path = petgraph::algo::astar(
-        &navgraph.graph,
-        *origin_node_index.unwrap(),
-        |finish| finish == **destination_node_index.as_ref().unwrap(),
-        |e| *e.weight(),
-        |_| 0.0,
-    );
-
-    let mut points = vec![];
-    if let Some((_, coordinate_path)) = path {
-        for coordinate in coordinate_path {
-            let point: Result<(f64, f64), ErrorResponse> = match navgraph.index_to_point_map.get(&coordinate) {
-                Some(point) => Ok((point.x.0, point.y.0)),
-                None => return Err(ErrorResponse {
-                    status: Status::InternalServerError, 
-                    message: "bad origin point request - coordinates are not found".to_owned()
-                })
-            };
-            points.push(point.unwrap());
+/* one feature per segment - the route's coordinates hold the start and end point of every segment in order */
+fn segment_breakdown(route: &RoutePath, weights: &RouteWeights, hazards: &Hazards, navgraph: &NavGraph) -> Vec<SegmentFeature> {
+    route.segments.iter().zip(route.coordinates.windows(2)).map(|(segment, ends)| {
+        let edge = &navgraph.graph[segment.edge];
+
+        SegmentFeature {
+            feature_type: "Feature".to_owned(),
+            geometry: LineGeometry {
+                geometry_type: "LineString".to_owned(),
+                coordinates: ends.to_vec(),
+            },
+            properties: SegmentProperties {
+                street_name: edge.street_name.clone(),
+                distance_meters: segment.length,
+                light_count: edge.light_count,
+                building_count: edge.building_count,
+                open_business_count: weights.open_businesses_near(segment.edge),
+                hazard_reports: hazards.reports_near(segment.edge),
+                safety_score: 100.0 * weights.safety_factor(segment.edge, edge),
+                sidewalk_width_meters: edge.sidewalk_width,
+                curb_ramps: edge.curb_ramps,
+                stairs: edge.stairs,
+                surface: edge.surface.clone(),
+            },
         }
-    } else {
-        println!("requested route does not exist with current data configuration");
-    }
-    
-    Ok(points)
+    }).collect()
 }
 
-#[post("/test_routes", data = "<request>")]
-pub fn test_routes(request: Json<UserRouteQuery>, navgraph: &State<NavGraph>) -> Result<Value, ErrorResponse> {
-    let request = request.into_inner(); 
-    let path = compute_path_v2(request.origin.clone(), request.destination.clone(), navgraph)?; 
+/*
+ *  anchor_to_stops: moves the ends of every leg of a route onto the stops of the current query
+ *  purpose        : the route's line, the segment geometries and the depart / arrive maneuvers
+ *                   all start and end at the stops, the path in between is left as it is
+ */
+fn anchor_to_stops(route: &mut RoutePath, stops: &[Snap]) {
+    for (leg, pair) in route.legs.iter_mut().zip(stops.windows(2)) {
+        if let Some(last) = leg.route.len().checked_sub(1) {
+            leg.route[0] = pair[0].point.x_y();
+            leg.route[last] = pair[1].point.x_y();
+        }
+    }
 
-    Ok(json!({
-        "type": "FeatureCollection", 
-        "features": [
-            {
-                "type": "Feature", 
-                "geometry": {
-                    "type": "LineString", 
-                    "coordinates": path, 
-                }, 
-                "properties": {}
+    route.coordinates = vec![];
+    for leg in route.legs.iter() {
+        let skip = if route.coordinates.is_empty() { 0 } else { 1 };
+        route.coordinates.extend(leg.route.iter().skip(skip));
+    }
+    for (feature, ends) in route.breakdown.iter_mut().zip(route.coordinates.windows(2)) {
+        feature.geometry.coordinates = ends.to_vec();
+    }
+
+    let mut leg = 0;
+    for maneuver in route.instructions.iter_mut() {
+        match maneuver.maneuver_type.as_str() {
+            "depart" => maneuver.location = stops[leg].point.x_y(),
+            "arrive" | "waypoint" => {
+                maneuver.location = stops[(leg + 1).min(stops.len() - 1)].point.x_y();
+                leg += 1;
             }
-        ]
-    }))
+            _ => {}
+        }
+    }
 }
 
-fn compute_path_v2(origin: Vec<f64>, destination: Vec<f64>, navgraph: &State<NavGraph>) -> Result<Vec<(f64, f64)>, ErrorResponse> {
-    let origin_x = origin[X_POSITION_INDICATOR];
-    let origin_y = origin[Y_POSITION_INDICATOR]; 
+fn segment_collection(features: Vec<SegmentFeature>) -> SegmentCollection {
+    SegmentCollection {
+        collection_type: "FeatureCollection".to_owned(),
+        features,
+    }
+}
 
-    let destination_x = destination[X_POSITION_INDICATOR];
-    let destination_y = destination[Y_POSITION_INDICATOR];
+/* region the route starts in and its graph - the other stops have to be inside the same region */
+pub fn origin_region<'a>(request: &UserRouteQuery, graph_state: &'a GraphState) -> Result<(&'a Region, Arc<NavGraph>), RouteError> {
+    let origin = request_point(&request.origin, "origin")?; 
+    locate_region(origin, "origin", graph_state)
+}
 
-    let origin_point = Point::new(origin_x, origin_y); 
-    let destination_point = Point::new(destination_x, destination_y); 
+/*
+ *  snap_stops: origin, waypoints and destination in the requested order
+ *  purpose   : every stop must be inside the service area before it is snapped onto a sidewalk the
+ *              accessibility profile can use, and connected to the origin after
+ */
+fn snap_stops(request: &UserRouteQuery, weights: &RouteWeights, navgraph: &NavGraph) -> Result<Vec<Snap>, RouteError> {
+    let waypoints: &[Vec<f64>] = request.waypoints.as_deref().unwrap_or(&[]); 
+    if waypoints.len() > MAX_WAYPOINTS {
+        return Err(RouteError {
+            status: Status::BadRequest, 
+            code: "too_many_waypoints".to_owned(), 
+            message: format!("routes can have at most {} waypoints", MAX_WAYPOINTS), 
+            field: Some("waypoints".to_owned()), 
+        })
+    }
 
-    let nearest_point_to_origin = navgraph.distance_tree.nearest_neighbor_iter(&origin_point).next().unwrap(); 
-    let nearest_point_to_destination = navgraph.distance_tree.nearest_neighbor_iter(&destination_point).next().unwrap();
+    let stops: Vec<(String, &Vec<f64>)> = std::iter::once(("origin".to_owned(), &request.origin))
+        .chain(waypoints.iter().enumerate().map(|(index, waypoint)| (format!("waypoints[{}]", index), waypoint)))
+        .chain(std::iter::once(("destination".to_owned(), &request.destination)))
+        .collect(); 
 
-    let nearest_origin_hashable = HashablePoint::new(nearest_point_to_origin.x(), nearest_point_to_origin.y());
-    let nearest_destination_hashable = HashablePoint::new(nearest_point_to_destination.x(), nearest_point_to_destination.y());
+    let fields: Vec<String> = stops.iter().map(|(field, _)| field.clone()).collect(); 
+    let snaps = stops.into_iter().map(|(field, coordinates)| {
+        let point = request_point(coordinates, &field)?; 
+        ensure_in_service_area(point, &field, navgraph)?; 
+        snap_to_accessible_edge(point, &field, weights, navgraph)
+    }).collect::<Result<Vec<Snap>, RouteError>>()?; 
 
-    let origin_node_index: Result<_, ErrorResponse> = match navgraph.point_to_index_map.get(&nearest_origin_hashable) {
-        Some(index) => Ok(index), 
-        None => return Err(ErrorResponse {
-            status: Status::BadRequest, 
-            message: "bad origin point request - coordinates are not found".to_owned()
-        })
-    }; 
+    ensure_connected(&snaps, &fields, navgraph)?; 
+    Ok(snaps)
+}
 
-    let destination_node_index: Result<_, ErrorResponse> = match navgraph.point_to_index_map.get(&nearest_destination_hashable) {
-        Some(index) => Ok(index), 
-        None => return Err(ErrorResponse {
+/*
+ *  order_stops: visiting order of the waypoints
+ *  purpose    : when asked to, the waypoints are reordered so the whole trip is as short as possible.
+ *               every order is tried, so this is limited to MAX_OPTIMIZED_WAYPOINTS waypoints.
+ */
+fn order_stops(stops: &[Snap], optimize: bool, weights: &RouteWeights, navgraph: &NavGraph) -> Result<Vec<usize>, RouteError> {
+    let waypoint_count = stops.len() - 2; 
+    if !optimize || waypoint_count < 2 {
+        return Ok((0..waypoint_count).collect())
+    }
+
+    if waypoint_count > MAX_OPTIMIZED_WAYPOINTS {
+        return Err(RouteError {
             status: Status::BadRequest, 
-            message: "bad origin point request - coordinates are not found".to_owned()
+            code: "too_many_waypoints".to_owned(), 
+            message: format!("waypoint order can only be optimized for up to {} waypoints", MAX_OPTIMIZED_WAYPOINTS), 
+            field: Some("optimize_waypoints".to_owned()), 
         })
-    }; 
+    }
+
+    let distances: Vec<Vec<f64>> = stops.iter().enumerate().map(|(from_index, from)| {
+        stops.iter().enumerate().map(|(to_index, to)| {
+            if from_index == to_index {
+                return 0.0;
+            }
+            let leg = compute_path(from, to, weights, navgraph); 
+            if leg.segments.is_empty() { f64::INFINITY } else { leg.distance }
+        }).collect()
+    }).collect(); 
+
+    let mut best_order: Vec<usize> = (1..=waypoint_count).collect(); 
+    let mut best_distance = f64::INFINITY; 
+    search_stop_orders(&distances, &mut vec![], 0.0, &mut best_order, &mut best_distance); 
+
+    Ok(best_order.iter().map(|stop| stop - 1).collect())
+}
 
-    let path = petgraph::algo::astar(
-        &navgraph.graph,
-        *origin_node_index.unwrap(),
-        |finish| finish == **destination_node_index.as_ref().unwrap(),
-        |e| *e.weight(),
-        |_| 0.0,
-    );
-
-    let mut points = vec![];
-    if let Some((_, coordinate_path)) = path {
-        for coordinate in coordinate_path {
-            let point: Result<(f64, f64), ErrorResponse> = match navgraph.index_to_point_map.get(&coordinate) {
-                Some(point) => Ok((point.x.0, point.y.0)),
-                None => return Err(ErrorResponse {
-                    status: Status::InternalServerError, 
-                    message: "bad origin point request - coordinates are not found".to_owned()
-                })
-            };
-            points.push(point.unwrap());
+/* origin, waypoints in the visiting order and destination */
+fn visiting_order(stops: &[Snap], waypoint_order: &[usize]) -> Vec<Snap> {
+    let mut ordered = vec![stops[0]]; 
+    ordered.extend(waypoint_order.iter().map(|waypoint| stops[waypoint + 1])); 
+    ordered.push(stops[stops.len() - 1]); 
+    ordered
+}
+
+/* depth first search over the orders of the waypoints (stops 1 to n - 2), skipping orders already longer than the best one */
+fn search_stop_orders(distances: &Vec<Vec<f64>>, order: &mut Vec<usize>, distance: f64, best_order: &mut Vec<usize>, best_distance: &mut f64) {
+    if distance >= *best_distance {
+        return;
+    }
+
+    let last_stop = distances.len() - 1; 
+    let current = *order.last().unwrap_or(&0); 
+
+    if order.len() == last_stop - 1 {
+        let total = distance + distances[current][last_stop]; 
+        if total < *best_distance {
+            *best_distance = total; 
+            *best_order = order.clone(); 
         }
-    } else {
-        println!("requested route does not exist with current data configuration");
+        return;
+    }
+
+    for next in 1..last_stop {
+        if order.contains(&next) {
+            continue;
+        }
+        order.push(next); 
+        search_stop_orders(distances, order, distance + distances[current][next], best_order, best_distance); 
+        order.pop(); 
     }
-    
-    Ok(points)
 }
 
-#[derive(Deserialize, Debug)]
-#[serde(crate = "rocket::serde")]
-struct DirectionsResponse {
-    routes: Vec<Route>,
+/* stitches the routes between consecutive stops together, a trip with an unreachable stop has no route */
+fn compute_stops_path(stops: &[Snap], weights: &RouteWeights, navgraph: &NavGraph) -> RoutePath {
+    let legs: Vec<RoutePath> = stops.windows(2).map(|pair| compute_path(&pair[0], &pair[1], weights, navgraph)).collect(); 
+    if legs.iter().any(|leg| leg.segments.is_empty()) {
+        return empty_route();
+    }
+
+    let leg_count = legs.len(); 
+    if leg_count == 1 {
+        return legs.into_iter().next().unwrap();
+    }
+
+    let mut route = empty_route(); 
+    let mut lit_distance = 0.0; 
+    let mut weighted_safety = 0.0; 
+
+    for (index, leg) in legs.into_iter().enumerate() {
+        let skip = if route.coordinates.is_empty() { 0 } else { 1 }; 
+        route.coordinates.extend(leg.coordinates.iter().skip(skip)); 
+        route.segments.extend(leg.segments); 
+        /* every leg starts where the previous one ended, which is already in the profile */
+        let offset = route.distance; 
+        route.elevation_profile.extend(
+            leg.elevation_profile.iter().filter(|(distance, _)| index == 0 || *distance > 0.0).map(|(distance, elevation)| (offset + distance, *elevation))
+        ); 
+        route.ascent += leg.ascent; 
+        route.descent += leg.descent; 
+        route.distance += leg.distance; 
+        route.duration += leg.duration; 
+        lit_distance += leg.distance * leg.lit_percentage; 
+        weighted_safety += leg.distance * leg.safety_score; 
+
+        let mut instructions = leg.instructions; 
+        if index + 1 < leg_count {
+            if let Some(arrival) = instructions.last_mut() {
+                arrival.maneuver_type = "waypoint".to_owned(); 
+                arrival.instruction = format!("Arrive at stop {}", index + 1); 
+            }
+        }
+        route.instructions.extend(instructions); 
+        route.legs.extend(leg.legs); 
+    }
+
+    if route.distance > 0.0 {
+        route.lit_percentage = lit_distance / route.distance; 
+        route.safety_score = weighted_safety / route.distance; 
+    }
+
+    route
 }
 
-#[derive(Deserialize, Debug)]
-#[serde(crate = "rocket::serde")]
-struct Route {
-    geometry: Geometry,
+fn compute_path(origin: &Snap, destination: &Snap, weights: &RouteWeights, navgraph: &NavGraph) -> RoutePath {
+    match find_path(origin, destination, weights, &HashMap::new(), navgraph) {
+        Some(nodes) => summarize_path(nodes, origin, destination, weights, navgraph),
+        None => empty_route(),
+    }
 }
 
-#[derive(Deserialize, Debug)]
-#[serde(crate = "rocket::serde")]
-struct Geometry {
-    coordinates: Vec<Vec<f64>>,  
+/*
+ *  SearchState: entry of the pathfinder's open set, wrapped in Reverse so the heap pops the lowest estimate first
+ *  estimate   : cost so far plus the haversine estimate of the remaining cost
+ *  node       : graph node reached, None once the search has walked onto the destination's edge
+ */
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct SearchState {
+    estimate: OrderedFloat<f64>,
+    cost: OrderedFloat<f64>,
+    node: Option<NodeIndex>,
 }
 
-#[allow(dead_code)]
-async fn get_shortest_mapbox_suggestion(origin: &Vec<f64>, destination: &Vec<f64>) -> Result<Vec<(f64, f64)>, ErrorResponse> {
+impl SearchState {
+    fn queued(estimate: f64, cost: f64, node: Option<NodeIndex>) -> Reverse<SearchState> {
+        Reverse(SearchState { estimate: OrderedFloat(estimate), cost: OrderedFloat(cost), node })
+    }
+}
 
-    let origin_x = origin[X_POSITION_INDICATOR];
-    let origin_y = origin[Y_POSITION_INDICATOR]; 
+/*
+ *  find_path: A* between two points snapped onto sidewalk edges
+ *  purpose  : the snapped edges are split at the projected points without touching the shared
+ *             graph - the search is seeded with the cost of walking from the origin to either node
+ *             of its edge, and the destination is reached from either node of its edge. When both
+ *             points are on the same edge the direct walk between them is also a candidate, which
+ *             is returned as an empty node list.
+ *             haversine distance never overestimates the remaining cost since every edge costs at least its length,
+ *             scaled down by the cheapest slope factor on graphs with elevations since gentle downhills cost less.
+ *             routes weighted by the walking distance alone are answered by the contraction hierarchy when
+ *             the graph has one.
+ */
+pub fn find_path(
+    origin: &Snap,
+    destination: &Snap,
+    weights: &RouteWeights,
+    penalties: &HashMap<EdgeIndex, f64>,
+    navgraph: &NavGraph,
+) -> Option<Vec<NodeIndex>> {
+    if penalties.is_empty() && weights.is_distance_only() {
+        if let Some(hierarchy) = &navgraph.hierarchy {
+            return find_hierarchy_path(origin, destination, hierarchy, navgraph);
+        }
+    }
 
-    let destination_x = destination[X_POSITION_INDICATOR];
-    let destination_y = destination[Y_POSITION_INDICATOR];
+    let walk_cost = |edge: EdgeIndex, toward: NodeIndex| weights.walk_cost(edge, toward, navgraph) * penalties.get(&edge).unwrap_or(&1.0);
+    let heuristic_scale = if navgraph.has_elevations() { min_slope_factor() } else { 1.0 };
+    let remaining = |node: NodeIndex| navgraph.graph[node].haversine_distance(&destination.point) * heuristic_scale;
+
+    let mut open: BinaryHeap<Reverse<SearchState>> = BinaryHeap::new();
+    let mut best_cost: HashMap<NodeIndex, f64> = HashMap::new();
+    let mut came_from: HashMap<NodeIndex, NodeIndex> = HashMap::new();
+    let mut destination_cost = f64::INFINITY;
+    let mut destination_parent: Option<NodeIndex> = None;
+
+    let (origin_source, origin_target) = navgraph.graph.edge_endpoints(origin.edge)?;
+    for node in [origin_source, origin_target] {
+        let cost = walk_cost(origin.edge, node) * origin.portion_to(node, navgraph);
+        if cost < *best_cost.get(&node).unwrap_or(&f64::INFINITY) {
+            best_cost.insert(node, cost);
+            open.push(SearchState::queued(cost + remaining(node), cost, Some(node)));
+        }
+    }
 
-    // let mapbox_public_token = match std::env::var("MAPBOX_ACCESS_TOKEN") {
-    //     Ok(token) => token,
-    //     Err(err) => return Err(ErrorResponse {
-    //         status: Status::InternalServerError, 
-    //         message: err.to_string()
-    //     })
-    // }; 
+    if origin.edge == destination.edge {
+        let toward = if destination.fraction >= origin.fraction { origin_target } else { origin_source };
+        destination_cost = walk_cost(origin.edge, toward) * (origin.fraction - destination.fraction).abs();
+        open.push(SearchState::queued(destination_cost, destination_cost, None));
+    }
 
-    let api_query =
-        format!("https://api.mapbox.com/directions/v5/mapbox/walking/{}%2C{}%3B{}%2C{}?alternatives=true&continue_straight=true&geometries=geojson&language=en&overview=full&steps=true&access_token=pk.eyJ1Ijoic3RyaWlkZSIsImEiOiJjbHN1dml6em0wM3E0MmxzMWVwMjNvNDBsIn0.aWXVdLx6iq3ZSSavUicsZA", origin_x, origin_y, destination_x, destination_y); 
+    let (destination_source, destination_target) = navgraph.graph.edge_endpoints(destination.edge)?;
+
+    while let Some(Reverse(SearchState { cost: OrderedFloat(state_cost), node, .. })) = open.pop() {
+        let node = match node {
+            Some(node) => node,
+            None => {
+                if state_cost > destination_cost {
+                    continue;
+                }
+                let mut nodes = vec![];
+                let mut current = destination_parent;
+                while let Some(node) = current {
+                    nodes.push(node);
+                    current = came_from.get(&node).copied();
+                }
+                nodes.reverse();
+                return Some(nodes);
+            }
+        };
 
-    let client = Client::new();
-    let res = client.get(&api_query).send().await.unwrap();
+        if state_cost > *best_cost.get(&node).unwrap_or(&f64::INFINITY) {
+            continue;
+        }
 
-    if res.status().is_success() {
-        let directions: DirectionsResponse = res.json().await.unwrap();
+        if node == destination_source || node == destination_target {
+            let toward = if node == destination_source { destination_target } else { destination_source };
+            let cost = state_cost + walk_cost(destination.edge, toward) * destination.portion_to(node, navgraph);
+            if cost < destination_cost {
+                destination_cost = cost;
+                destination_parent = Some(node);
+                open.push(SearchState::queued(cost, cost, None));
+            }
+        }
 
-        if let Some(route) = directions.routes.first() {
-            return Ok(route.geometry.coordinates.iter().map(|coord| (coord[0], coord[1])).collect()); 
-        } else {
-            return Err(ErrorResponse {
-                status: Status::NotFound, 
-                message: "NO ROUTES EXIST".to_owned()
-            })
+        for edge in navgraph.graph.edges(node) {
+            let next = if edge.source() == node { edge.target() } else { edge.source() };
+            let cost = state_cost + walk_cost(edge.id(), next);
+            if cost < *best_cost.get(&next).unwrap_or(&f64::INFINITY) {
+                best_cost.insert(next, cost);
+                came_from.insert(next, node);
+                open.push(SearchState::queued(cost + remaining(next), cost, Some(next)));
+            }
         }
+    }
+
+    None
+}
+
+/* same contract as find_path - the seeds are the nodes of the snapped edges with the meters walked to reach them */
+fn find_hierarchy_path(origin: &Snap, destination: &Snap, hierarchy: &ContractionHierarchy, navgraph: &NavGraph) -> Option<Vec<NodeIndex>> {
+    let seeds = |snap: &Snap| -> Option<Vec<(NodeIndex, f64)>> {
+        let (source, target) = navgraph.graph.edge_endpoints(snap.edge)?;
+        Some([source, target]
+            .iter()
+            .map(|node| (*node, navgraph.graph[snap.edge].length * snap.portion_to(*node, navgraph)))
+            .collect())
+    };
+
+    let direct_distance = if origin.edge == destination.edge {
+        navgraph.graph[origin.edge].length * (origin.fraction - destination.fraction).abs()
     } else {
-        return Err(ErrorResponse {
-            status: Status::NotFound, 
-            message: "DIRECTIONS ARE NOT POSSIBLE WITH GIVEN CONFIGURATION".to_owned()
-        })
+        f64::INFINITY
+    };
+
+    match hierarchy.shortest_path(&seeds(origin)?, &seeds(destination)?) {
+        Some((distance, nodes)) if distance < direct_distance => Some(nodes),
+        _ if direct_distance.is_finite() => Some(vec![]),
+        _ => None,
     }
 }
 
-#[post("/test_mapbox", data = "<request>")]
-pub async fn test_mapbox(request: Json<UserRouteQuery>) -> Result<Value, ErrorResponse> {
-    dotenv::dotenv().ok(); 
+/*
+ *  penalty based alternatives: the edges of every route found so far get more expensive
+ *  so the next search is pushed onto different sidewalks. Candidates that are too long or
+ *  that mostly overlap a route that was already picked are thrown away.
+ */
+fn compute_alternatives(
+    primary: &RoutePath,
+    origin: &Snap,
+    destination: &Snap,
+    count: usize,
+    weights: &RouteWeights,
+    navgraph: &NavGraph,
+) -> Vec<RoutePath> {
+    let mut alternatives: Vec<RoutePath> = vec![];
+    if count == 0 || primary.segments.is_empty() {
+        return alternatives;
+    }
 
-    let request = request.into_inner(); 
+    let mut penalties: HashMap<EdgeIndex, f64> = HashMap::new();
+    penalize_edges(&mut penalties, &primary.segments);
 
-    let origin_x = request.origin[X_POSITION_INDICATOR];
-    let origin_y = request.origin[Y_POSITION_INDICATOR]; 
+    for _ in 0..count * ALTERNATIVE_ATTEMPT_FACTOR {
+        if alternatives.len() == count {
+            break;
+        }
 
-    let destination_x = request.destination[X_POSITION_INDICATOR];
-    let destination_y = request.destination[Y_POSITION_INDICATOR];
+        let nodes = match find_path(origin, destination, weights, &penalties, navgraph) {
+            Some(nodes) => nodes,
+            None => break,
+        };
+        let candidate = summarize_path(nodes, origin, destination, weights, navgraph);
+        penalize_edges(&mut penalties, &candidate.segments);
 
-    let mapbox_public_token = match std::env::var("MAPBOX_ACCESS_TOKEN") {
-        Ok(token) => token,
-        Err(err) => return Err(ErrorResponse {
-            status: Status::InternalServerError, 
-            message: err.to_string()
-        })
-    }; 
+        if candidate.distance > primary.distance * MAX_DETOUR_RATIO {
+            continue;
+        }
 
-    let api_query =
-        format!("https://api.mapbox.com/directions/v5/mapbox/walking/{}%2C{}%3B{}%2C{}?alternatives=true&continue_straight=true&geometries=geojson&language=en&overview=full&steps=true&access_token={}", origin_x, origin_y, destination_x, destination_y, mapbox_public_token); 
-
-    let client = Client::new();
-    let res = client.get(&api_query).send().await.unwrap();
-
-    if res.status().is_success() {
-        let directions: DirectionsResponse = res.json().await.unwrap();
-
-        if let Some(route) = directions.routes.first() {
-
-            let coordinates: Vec<(f64, f64)> = route.geometry.coordinates.iter().map(|coord| (coord[0], coord[1])).collect();  
-
-            return Ok(json!({
-                "type": "FeatureCollection", 
-                "features": [
-                    {
-                        "type": "Feature", 
-                        "geometry": {
-                            "type": "LineString", 
-                            "coordinates": coordinates, 
-                        }, 
-                        "properties": {}
-                    }
-                ]
-            }))
-        } else {
-            return Err(ErrorResponse {
-                status: Status::NotFound, 
-                message: "NO ROUTES EXIST".to_owned()
-            })
+        let is_distinct = std::iter::once(primary)
+            .chain(alternatives.iter())
+            .all(|route| shared_fraction(&candidate, route) <= MAX_SHARED_FRACTION);
+
+        if is_distinct {
+            alternatives.push(candidate);
         }
+    }
+
+    alternatives
+}
+
+fn penalize_edges(penalties: &mut HashMap<EdgeIndex, f64>, segments: &Vec<RouteSegment>) {
+    for segment in segments {
+        *penalties.entry(segment.edge).or_insert(1.0) *= ALTERNATIVE_PENALTY;
+    }
+}
+
+/* fraction of the candidate's distance that is also walked by the other route */
+fn shared_fraction(candidate: &RoutePath, other: &RoutePath) -> f64 {
+    if candidate.distance == 0.0 {
+        return 1.0;
+    }
+
+    let other_edges: HashSet<EdgeIndex> = other.segments.iter().map(|segment| segment.edge).collect();
+    let shared_distance: f64 = candidate.segments
+        .iter()
+        .filter(|segment| other_edges.contains(&segment.edge))
+        .map(|segment| segment.length)
+        .sum();
+
+    shared_distance / candidate.distance
+}
+
+/* the route starts and ends with the parts of the snapped edges between the projected points and the path */
+fn summarize_path(nodes: Vec<NodeIndex>, origin: &Snap, destination: &Snap, weights: &RouteWeights, navgraph: &NavGraph) -> RoutePath {
+    let mut points: Vec<Point> = vec![origin.point];
+    let mut segments: Vec<RouteSegment> = vec![];
+
+    match (nodes.first(), nodes.last()) {
+        (Some(first), Some(last)) => {
+            segments.push(RouteSegment {
+                edge: origin.edge,
+                length: navgraph.graph[origin.edge].length * origin.portion_to(*first, navgraph),
+            });
+            segments.extend(path_edges(&nodes, weights, navgraph).into_iter().map(|edge| RouteSegment {
+                edge,
+                length: navgraph.graph[edge].length,
+            }));
+            segments.push(RouteSegment {
+                edge: destination.edge,
+                length: navgraph.graph[destination.edge].length * destination.portion_to(*last, navgraph),
+            });
+            points.extend(nodes.iter().map(|node| navgraph.graph[*node]));
+        }
+        _ => segments.push(RouteSegment {
+            edge: origin.edge,
+            length: navgraph.graph[origin.edge].length * (origin.fraction - destination.fraction).abs(),
+        }),
+    }
+    points.push(destination.point);
+
+    let elevations: Vec<Option<f64>> = std::iter::once(snap_elevation(origin, navgraph))
+        .chain(nodes.iter().map(|node| node_elevation(*node, navgraph)))
+        .chain(std::iter::once(snap_elevation(destination, navgraph)))
+        .collect();
+    let lengths: Vec<f64> = segments.iter().map(|segment| segment.length).collect();
+    let climb = climb(&elevations, &lengths);
+
+    let mut distance = 0.0;
+    let mut lit_distance = 0.0;
+    let mut weighted_safety = 0.0;
+    for segment in segments.iter() {
+        let edge = &navgraph.graph[segment.edge];
+        distance += segment.length;
+        weighted_safety += segment.length * weights.safety_factor(segment.edge, edge);
+        if is_well_lit(edge) {
+            lit_distance += segment.length;
+        }
+    }
+
+    let (lit_percentage, safety_score) = if distance > 0.0 {
+        (100.0 * lit_distance / distance, 100.0 * weighted_safety / distance)
     } else {
+        (0.0, 0.0)
+    };
+
+    let instructions = build_maneuvers(&points, &segments, navgraph);
+    let coordinates: Vec<(f64, f64)> = points.iter().map(|point| (point.x(), point.y())).collect();
+
+    RoutePath {
+        legs: vec![RouteLeg {
+            route: coordinates.clone(),
+            distance_meters: distance,
+            eta_seconds: climb.duration,
+            lit_percentage,
+            safety_score,
+        }],
+        coordinates,
+        segments,
+        distance,
+        duration: climb.duration,
+        lit_percentage,
+        safety_score,
+        instructions,
+        hazards: vec![],
+        breakdown: vec![],
+        ascent: climb.ascent,
+        descent: climb.descent,
+        elevation_profile: climb.profile,
+    }
+}
+
+fn empty_route() -> RoutePath {
+    RoutePath {
+        coordinates: vec![],
+        segments: vec![],
+        distance: 0.0,
+        duration: 0.0,
+        lit_percentage: 0.0,
+        safety_score: 0.0,
+        instructions: vec![],
+        hazards: vec![],
+        legs: vec![],
+        breakdown: vec![],
+        ascent: 0.0,
+        descent: 0.0,
+        elevation_profile: vec![],
+    }
+}
+
+/* picks the cheapest edge between consecutive nodes of a path */
+fn path_edges(nodes: &[NodeIndex], weights: &RouteWeights, navgraph: &NavGraph) -> Vec<EdgeIndex> {
+    nodes.windows(2)
+        .filter_map(|pair| {
+            navgraph.graph
+                .edges_connecting(pair[0], pair[1])
+                .min_by(|a, b| weights.walk_cost(a.id(), pair[1], navgraph).total_cmp(&weights.walk_cost(b.id(), pair[1], navgraph)))
+                .map(|edge| edge.id())
+        })
+        .collect()
+}
+
+#[post("/test_routes", data = "<request>")]
+pub async fn test_routes(
+    request: Json<UserRouteQuery>,
+    graph_state: &State<GraphState>,
+    baseline: &State<BaselineProvider>,
+    route_cache: &State<RouteCache>,
+    mut db: Connection<Db>,
+) -> Result<Value, RouteError> {
+    let request = request.into_inner(); 
+    let (region, snapshot) = origin_region(&request, graph_state)?; 
+    let path = compute_route(&request, &region.config.key, &snapshot, baseline, route_cache, &mut db).await?; 
+
+    Ok(route_features(&path))
+}
+
+/* baseline route of the configured routing provider as a GeoJSON feature collection */
+#[post("/test_mapbox", data = "<request>")]
+pub async fn test_mapbox(request: Json<UserRouteQuery>, baseline: &State<BaselineProvider>) -> Result<Value, ErrorResponse> {
+    let request = request.into_inner(); 
+    if request.origin.len() != CORRECT_COORDINATE_AMOUNT || request.destination.len() != CORRECT_COORDINATE_AMOUNT {
         return Err(ErrorResponse {
-            status: Status::NotFound, 
-            message: "DIRECTIONS ARE NOT POSSIBLE WITH GIVEN CONFIGURATION".to_owned()
+            status: Status::BadRequest, 
+            message: "origin and destination must only contain longitude and latitude coordinates".to_owned(), 
         })
     }
-}
\ No newline at end of file
+
+    let provider = baseline.0.as_ref().ok_or(ErrorResponse {
+        status: Status::ServiceUnavailable, 
+        message: "no routing provider is configured".to_owned(), 
+    })?; 
+
+    let stops = vec![
+        (request.origin[X_POSITION_INDICATOR], request.origin[Y_POSITION_INDICATOR]), 
+        (request.destination[X_POSITION_INDICATOR], request.destination[Y_POSITION_INDICATOR]), 
+    ]; 
+    let coordinates = provider.walking_route(&stops).await?; 
+
+    Ok(json!({
+        "type": "FeatureCollection", 
+        "features": [
+            {
+                "type": "Feature", 
+                "geometry": {
+                    "type": "LineString", 
+                    "coordinates": coordinates, 
+                }, 
+                "properties": {
+                    "provider": provider.name(), 
+                }
+            }
+        ]
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_graph::{distance_weights, navgraph, path_distance, snap, stop_distance, EDGES, GRID_EDGES};
+
+    #[test]
+    fn matches_brute_force_between_stops_on_every_pair_of_edges() {
+        let navgraph = navgraph();
+        let weights = distance_weights();
+        for (from_source, from_target, _) in &EDGES[..GRID_EDGES] {
+            for (to_source, to_target, _) in &EDGES[..GRID_EDGES] {
+                let origin = snap(&navgraph, *from_source, *from_target, 0.3);
+                let destination = snap(&navgraph, *to_source, *to_target, 0.6);
+                let expected = stop_distance(&navgraph, &origin, &destination);
+
+                let nodes = find_path(&origin, &destination, &weights, &HashMap::new(), &navgraph).unwrap();
+                let distance = path_distance(&navgraph, &origin, &destination, &nodes);
+                assert!(
+                    (distance - expected).abs() < 1e-6,
+                    "{}-{} -> {}-{}: {} instead of {}",
+                    from_source,
+                    from_target,
+                    to_source,
+                    to_target,
+                    distance,
+                    expected
+                );
+                let path = summarize_path(nodes, &origin, &destination, &weights, &navgraph);
+                assert!((path.distance - expected).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn walks_directly_between_stops_on_the_same_edge() {
+        let navgraph = navgraph();
+        let length = navgraph.graph[snap(&navgraph, 0, 1, 0.0).edge].length;
+        for (from, to) in [(0.2, 0.7), (0.7, 0.2)] {
+            let origin = snap(&navgraph, 0, 1, from);
+            let destination = snap(&navgraph, 0, 1, to);
+
+            let nodes = find_path(&origin, &destination, &distance_weights(), &HashMap::new(), &navgraph).unwrap();
+            assert!(nodes.is_empty());
+            let path = summarize_path(nodes, &origin, &destination, &distance_weights(), &navgraph);
+            assert!((path.distance - 0.5 * length).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn leaves_a_long_edge_when_going_around_is_shorter() {
+        let navgraph = navgraph();
+        let origin = snap(&navgraph, 1, 4, 0.05);
+        let destination = snap(&navgraph, 1, 4, 0.95);
+
+        let nodes = find_path(&origin, &destination, &distance_weights(), &HashMap::new(), &navgraph).unwrap();
+        assert_eq!(nodes, [1, 0, 3, 4].map(NodeIndex::new));
+        let distance = path_distance(&navgraph, &origin, &destination, &nodes);
+        assert!((distance - stop_distance(&navgraph, &origin, &destination)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn has_no_path_to_a_disconnected_stop() {
+        let navgraph = navgraph();
+        let origin = snap(&navgraph, 0, 1, 0.5);
+        let destination = snap(&navgraph, 9, 10, 0.5);
+
+        assert!(find_path(&origin, &destination, &distance_weights(), &HashMap::new(), &navgraph).is_none());
+        assert!(find_path(&destination, &origin, &distance_weights(), &HashMap::new(), &navgraph).is_none());
+    }
+}
@@ -0,0 +1,37 @@
+/*
+ * Internal Imports
+ */
+use shared::response_models::RouteError;
+
+/*
+ * External Imports
+ */
+use geo::Point;
+use rocket::http::Status;
+
+/*
+ * constants
+ */
+pub const CORRECT_COORDINATE_AMOUNT: usize = 2;
+pub const X_POSITION_INDICATOR: usize = 0;
+pub const Y_POSITION_INDICATOR: usize = 1;
+
+/* bad request pointing at the field of the request body it is about, if any */
+pub fn invalid_request(code: &str, message: &str, field: Option<&str>) -> RouteError {
+    RouteError {
+        status: Status::BadRequest,
+        code: code.to_owned(),
+        message: message.to_owned(),
+        field: field.map(|field| field.to_owned()),
+    }
+}
+
+/* longitude and latitude pair of the request body as a point */
+pub fn request_point(coordinates: &[f64], field: &str) -> Result<Point, RouteError> {
+    if coordinates.len() != CORRECT_COORDINATE_AMOUNT {
+        let message = format!("{} must only contain longitude and latitude coordinates", field);
+        return Err(invalid_request("invalid_coordinates", &message, Some(field)));
+    }
+
+    Ok(Point::new(coordinates[X_POSITION_INDICATOR], coordinates[Y_POSITION_INDICATOR]))
+}
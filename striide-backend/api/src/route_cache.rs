@@ -0,0 +1,339 @@
+/*
+ * Internal Imports
+ */
+use models::user::{Role, UserSession};
+use shared::response_models::ErrorResponse;
+use crate::hazards::Hazards;
+use crate::pathfinder::RoutePath;
+use crate::route_weights::RouteWeights;
+use crate::snapping::Snap;
+use crate::NavGraph;
+
+/*
+ * External Imports
+ */
+use rocket::fairing::AdHoc;
+use rocket::http::Status;
+use rocket::serde::json::Value;
+use rocket::State;
+use serde_json::json;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/*
+ * constants
+ */
+/* used when route_cache_size is not set in Rocket.toml or ROCKET_ROUTE_CACHE_SIZE - 0 turns the cache off */
+const DEFAULT_CACHE_SIZE: usize = 1000;
+/* used when route_cache_ttl_seconds is not set - bounds how stale opening hours and expired reports can get */
+const DEFAULT_CACHE_TTL_SECONDS: u64 = 600;
+/* stops snapped within this many meters of each other along the same edge share cached routes */
+const POSITION_RESOLUTION_METERS: f64 = 1.0;
+
+/*
+ *  RouteCacheKey : everything the routes of a query depend on
+ *  region        : key of the region whose graph the stops were snapped onto - edge indices of
+ *                  different graphs line up and JSON graphs all share the same version
+ *  graph_version : build time of the graph the stops were snapped onto
+ *  stops         : snapped edge and position along it (in POSITION_RESOLUTION_METERS) of every stop
+ *  weights       : hash of the route weights and the hazard penalties - it changes with the routing
+ *                  profile, the businesses open at the departure time and the active reports
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RouteCacheKey {
+    region: String,
+    graph_version: String,
+    stops: Vec<(usize, i64)>,
+    optimize_waypoints: bool,
+    alternatives: usize,
+    weights: u64,
+}
+
+impl RouteCacheKey {
+    pub fn new(
+        region: &str,
+        stops: &[Snap],
+        optimize_waypoints: bool,
+        alternatives: usize,
+        weights: &RouteWeights,
+        hazards: &Hazards,
+        navgraph: &NavGraph,
+    ) -> RouteCacheKey {
+        RouteCacheKey {
+            region: region.to_owned(),
+            graph_version: navgraph.version(),
+            stops: stops
+                .iter()
+                .map(|stop| {
+                    let position = stop.fraction * navgraph.graph[stop.edge].length / POSITION_RESOLUTION_METERS;
+                    (stop.edge.index(), position.round() as i64)
+                })
+                .collect(),
+            optimize_waypoints,
+            alternatives,
+            weights: weights_fingerprint(weights, hazards),
+        }
+    }
+}
+
+/*
+ *  CachedRoutes   : result of the pathfinder for one key
+ *  waypoint_order : visiting order of the waypoints
+ *  routes         : main route followed by its alternatives
+ */
+#[derive(Clone)]
+pub struct CachedRoutes {
+    pub waypoint_order: Vec<usize>,
+    pub routes: Vec<RoutePath>,
+}
+
+struct CacheEntry {
+    value: CachedRoutes,
+    inserted_at: Instant,
+}
+
+/* order holds the keys oldest first - a key whose entry was replaced since is skipped when evicting */
+#[derive(Default)]
+struct CacheEntries {
+    routes: HashMap<RouteCacheKey, CacheEntry>,
+    order: VecDeque<(RouteCacheKey, Instant)>,
+}
+
+/*
+ *  RouteCache : routes computed recently, managed by rocket
+ *  purpose    : commuters ask for the same route every day. Entries expire after the ttl and the
+ *               oldest ones are evicted once the cache is full. The whole cache is cleared when
+ *               the graphs are reloaded or a report is published.
+ */
+pub struct RouteCache {
+    capacity: usize,
+    ttl: Duration,
+    entries: Mutex<CacheEntries>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl RouteCache {
+    pub fn new(capacity: usize, ttl: Duration) -> RouteCache {
+        RouteCache {
+            capacity,
+            ttl,
+            entries: Mutex::new(CacheEntries::default()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    pub fn get(&self, key: &RouteCacheKey) -> Option<CachedRoutes> {
+        let mut entries = self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        let cached = match entries.routes.get(key) {
+            Some(entry) if entry.inserted_at.elapsed() <= self.ttl => Some(entry.value.clone()),
+            Some(_) => {
+                entries.routes.remove(key);
+                None
+            }
+            None => None,
+        };
+
+        let counter = if cached.is_some() { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        cached
+    }
+
+    pub fn insert(&self, key: RouteCacheKey, value: CachedRoutes) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut entries = self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let now = Instant::now();
+        /* replacing a route does not grow the cache, so nothing has to make room for it */
+        let replacing = entries.routes.contains_key(&key);
+
+        while let Some((oldest, inserted_at)) = entries.order.front().cloned() {
+            let is_current = entries.routes.get(&oldest).is_some_and(|entry| entry.inserted_at == inserted_at);
+            if is_current && now - inserted_at <= self.ttl && (replacing || entries.routes.len() < self.capacity) {
+                break;
+            }
+            if is_current {
+                entries.routes.remove(&oldest);
+            }
+            entries.order.pop_front();
+        }
+
+        entries.order.push_back((key.clone(), now));
+        entries.routes.insert(key, CacheEntry { value, inserted_at: now });
+    }
+
+    /* the counters are kept so the hit rate can be followed across reloads */
+    pub fn clear(&self) {
+        let mut entries = self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        entries.routes.clear();
+        entries.order.clear();
+    }
+
+    fn stats(&self) -> Value {
+        let entries = self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).routes.len();
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+
+        json!({
+            "entries": entries,
+            "capacity": self.capacity,
+            "ttl_seconds": self.ttl.as_secs(),
+            "hits": hits,
+            "misses": misses,
+            "hit_rate": if hits + misses > 0 { hits as f64 / (hits + misses) as f64 } else { 0.0 },
+        })
+    }
+}
+
+/* hash maps are hashed in edge order so equal weights always give the same fingerprint */
+fn weights_fingerprint(weights: &RouteWeights, hazards: &Hazards) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    weights.lights.to_bits().hash(&mut hasher);
+    weights.businesses.to_bits().hash(&mut hasher);
+    weights.accessibility.hash(&mut hasher);
+    weights.slopes.hash(&mut hasher);
+
+    if let Some(open_businesses) = &weights.open_businesses {
+        let mut open: Vec<(usize, usize)> = open_businesses.iter().map(|(edge, count)| (edge.index(), *count)).collect();
+        open.sort_unstable();
+        open.hash(&mut hasher);
+    }
+
+    let mut penalties: Vec<(usize, u64)> = hazards.penalties().iter().map(|(edge, penalty)| (edge.index(), penalty.to_bits())).collect();
+    penalties.sort_unstable();
+    penalties.hash(&mut hasher);
+
+    hasher.finish()
+}
+
+/* manages the route cache sized by route_cache_size and route_cache_ttl_seconds */
+pub fn stage() -> AdHoc {
+    AdHoc::on_ignite("Route Cache", |rocket| async {
+        let figment = rocket.figment();
+        let capacity = figment.extract_inner::<usize>("route_cache_size").unwrap_or(DEFAULT_CACHE_SIZE);
+        let ttl = figment.extract_inner::<u64>("route_cache_ttl_seconds").unwrap_or(DEFAULT_CACHE_TTL_SECONDS);
+
+        rocket.manage(RouteCache::new(capacity, Duration::from_secs(ttl)))
+    })
+}
+
+/* size, hit and miss counters of the route cache (admin only) */
+#[get("/admin/route_cache")]
+pub fn route_cache_stats(session: UserSession, route_cache: &State<RouteCache>) -> Result<Value, ErrorResponse> {
+    if !matches!(session.role, Role::Admin) {
+        return Err(ErrorResponse {
+            status: Status::Forbidden,
+            message: "only admins can see the route cache statistics".to_owned(),
+        });
+    }
+
+    Ok(route_cache.stats())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(region: &str, edge: usize) -> RouteCacheKey {
+        RouteCacheKey {
+            region: region.to_owned(),
+            graph_version: "unversioned".to_owned(),
+            stops: vec![(edge, 0), (edge + 1, 0)],
+            optimize_waypoints: false,
+            alternatives: 0,
+            weights: 0,
+        }
+    }
+
+    /* the waypoint order tells the cached values apart */
+    fn routes(marker: usize) -> CachedRoutes {
+        CachedRoutes { waypoint_order: vec![marker], routes: vec![] }
+    }
+
+    fn cached(cache: &RouteCache, key: &RouteCacheKey) -> Option<usize> {
+        cache.get(key).map(|routes| routes.waypoint_order[0])
+    }
+
+    #[test]
+    fn returns_routes_until_they_expire() {
+        let cache = RouteCache::new(10, Duration::from_millis(50));
+        cache.insert(key("toronto", 1), routes(1));
+        assert_eq!(cached(&cache, &key("toronto", 1)), Some(1));
+
+        std::thread::sleep(Duration::from_millis(80));
+        assert_eq!(cached(&cache, &key("toronto", 1)), None);
+        assert_eq!(cache.stats()["entries"], 0);
+    }
+
+    #[test]
+    fn evicts_the_oldest_routes_once_full() {
+        let cache = RouteCache::new(2, Duration::from_secs(60));
+        for edge in 1..=3 {
+            cache.insert(key("toronto", edge), routes(edge));
+        }
+
+        assert_eq!(cached(&cache, &key("toronto", 1)), None);
+        assert_eq!(cached(&cache, &key("toronto", 2)), Some(2));
+        assert_eq!(cached(&cache, &key("toronto", 3)), Some(3));
+    }
+
+    #[test]
+    fn replaced_routes_count_as_new() {
+        let cache = RouteCache::new(2, Duration::from_secs(60));
+        cache.insert(key("toronto", 1), routes(1));
+        cache.insert(key("toronto", 2), routes(2));
+        cache.insert(key("toronto", 1), routes(10));
+        cache.insert(key("toronto", 3), routes(3));
+
+        assert_eq!(cached(&cache, &key("toronto", 1)), Some(10));
+        assert_eq!(cached(&cache, &key("toronto", 2)), None);
+        assert_eq!(cached(&cache, &key("toronto", 3)), Some(3));
+    }
+
+    #[test]
+    fn replacing_routes_in_a_full_cache_keeps_the_others() {
+        let cache = RouteCache::new(2, Duration::from_secs(60));
+        cache.insert(key("toronto", 1), routes(1));
+        cache.insert(key("toronto", 2), routes(2));
+        cache.insert(key("toronto", 2), routes(20));
+
+        assert_eq!(cached(&cache, &key("toronto", 1)), Some(1));
+        assert_eq!(cached(&cache, &key("toronto", 2)), Some(20));
+    }
+
+    #[test]
+    fn keeps_regions_apart() {
+        let cache = RouteCache::new(10, Duration::from_secs(60));
+        cache.insert(key("toronto", 1), routes(1));
+        assert_eq!(cached(&cache, &key("ottawa", 1)), None);
+    }
+
+    #[test]
+    fn zero_capacity_turns_the_cache_off() {
+        let cache = RouteCache::new(0, Duration::from_secs(60));
+        cache.insert(key("toronto", 1), routes(1));
+        assert_eq!(cached(&cache, &key("toronto", 1)), None);
+    }
+
+    #[test]
+    fn clearing_keeps_the_counters() {
+        let cache = RouteCache::new(10, Duration::from_secs(60));
+        cache.insert(key("toronto", 1), routes(1));
+        cached(&cache, &key("toronto", 1));
+        cache.clear();
+        cached(&cache, &key("toronto", 1));
+
+        let stats = cache.stats();
+        assert_eq!(stats["entries"], 0);
+        assert_eq!(stats["hits"], 1);
+        assert_eq!(stats["misses"], 1);
+    }
+}
@@ -0,0 +1,270 @@
+/*
+ * Internal Imports
+ */
+use shared::response_models::{ComputedPath, Response, ResponseBody, RouteError};
+
+/*
+ * External Imports
+ */
+use rocket::http::{ContentType, Status};
+use rocket::request::Request;
+use rocket::response::{self, Responder};
+use rocket::serde::json::Value;
+use serde_json::json;
+use std::io::Cursor;
+
+/*
+ * constants
+ */
+/* encoded polylines keep 6 decimal places like OSRM and Valhalla */
+const POLYLINE_PRECISION: f64 = 1e6;
+/* every character of an encoded polyline holds 5 bits of a value */
+const POLYLINE_CHUNK_BITS: u32 = 5;
+const POLYLINE_CHUNK_MASK: i64 = 0x1f;
+const POLYLINE_CONTINUATION: i64 = 0x20;
+const POLYLINE_OFFSET: i64 = 63;
+/* elevation profiles are encoded to the decimeter, which is finer than any elevation model */
+const PROFILE_PRECISION: f64 = 10.0;
+
+/*
+ *  RouteFormat : format parameter of the route queries
+ *  Json        : the default response with [lng, lat] coordinate lists
+ *  GeoJson     : one LineString feature per route
+ *  Polyline6   : the default response with every route line encoded as a precision 6 polyline, the
+ *                segment geometries replaced by the range of the route line they cover and the
+ *                elevation profiles encoded as polylines of [meters, elevation] in decimeters
+ *  Gpx         : one track per route for watches and other navigation apps
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RouteFormat {
+    Json,
+    GeoJson,
+    Polyline6,
+    Gpx,
+}
+
+impl RouteFormat {
+    pub fn parse(format: Option<&str>) -> Result<RouteFormat, RouteError> {
+        match format {
+            None | Some("json") => Ok(RouteFormat::Json),
+            Some("geojson") => Ok(RouteFormat::GeoJson),
+            Some("polyline6") => Ok(RouteFormat::Polyline6),
+            Some("gpx") => Ok(RouteFormat::Gpx),
+            Some(other) => Err(RouteError {
+                status: Status::BadRequest,
+                code: "invalid_format".to_owned(),
+                message: format!("unknown format {} - use json, geojson, polyline6 or gpx", other),
+                field: Some("format".to_owned()),
+            }),
+        }
+    }
+}
+
+/* computed route in the requested format with the matching content type */
+pub enum ExportedRoute {
+    Json(Response),
+    Document(ContentType, String),
+}
+
+#[rocket::async_trait]
+impl<'r> Responder<'r, 'static> for ExportedRoute {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        match self {
+            ExportedRoute::Json(response) => response.respond_to(request),
+            ExportedRoute::Document(content_type, body) => response::Response::build()
+                .header(content_type)
+                .status(Status::Ok)
+                .sized_body(body.len(), Cursor::new(body))
+                .ok(),
+        }
+    }
+}
+
+pub fn export_route(path: ComputedPath, format: RouteFormat) -> ExportedRoute {
+    match format {
+        RouteFormat::Json => ExportedRoute::Json(Response {
+            status: Status::Ok,
+            body: ResponseBody::ComputedPath(path),
+        }),
+        RouteFormat::GeoJson => ExportedRoute::Document(
+            ContentType::new("application", "geo+json"),
+            route_features(&path).to_string(),
+        ),
+        RouteFormat::Polyline6 => ExportedRoute::Document(ContentType::JSON, encoded_path(&path).to_string()),
+        RouteFormat::Gpx => ExportedRoute::Document(ContentType::new("application", "gpx+xml"), route_gpx(&path)),
+    }
+}
+
+/* the main route followed by its alternatives as a GeoJSON FeatureCollection */
+pub fn route_features(path: &ComputedPath) -> Value {
+    let main_route = json!({
+        "type": "Feature",
+        "geometry": {
+            "type": "LineString",
+            "coordinates": path.striide_route,
+        },
+        "properties": {
+            "alternative": 0,
+            "distance_meters": path.distance_meters,
+            "eta_seconds": path.eta_seconds,
+            "lit_percentage": path.lit_percentage,
+            "safety_score": path.safety_score,
+            "origin_snap_meters": path.origin_snap_meters,
+            "destination_snap_meters": path.destination_snap_meters,
+            "waypoint_order": path.waypoint_order,
+            "legs": path.legs,
+            "segments": path.segments,
+            "instructions": path.instructions,
+            "hazards": path.hazards,
+            "comparison": path.comparison,
+        }
+    });
+
+    let alternatives = path.alternatives.iter().enumerate().map(|(index, alternative)| {
+        json!({
+            "type": "Feature",
+            "geometry": {
+                "type": "LineString",
+                "coordinates": alternative.route,
+            },
+            "properties": {
+                "alternative": index + 1,
+                "distance_meters": alternative.distance_meters,
+                "eta_seconds": alternative.eta_seconds,
+                "lit_percentage": alternative.lit_percentage,
+                "safety_score": alternative.safety_score,
+                "segments": alternative.segments,
+                "instructions": alternative.instructions,
+                "hazards": alternative.hazards,
+            }
+        })
+    });
+
+    json!({
+        "type": "FeatureCollection",
+        "features": std::iter::once(main_route).chain(alternatives).collect::<Vec<Value>>(),
+    })
+}
+
+/* the default response with the route lines replaced by encoded polylines */
+fn encoded_path(path: &ComputedPath) -> Value {
+    let mut encoded = json!(path);
+    encoded["striide_route"] = json!(encode_polyline(&path.striide_route));
+    encoded["mapbox_route"] = json!(encode_polyline(&path.mapbox_route));
+    encoded["elevation_profile"] = json!(encode_profile(&path.elevation_profile));
+    index_segments(&mut encoded["segments"]);
+
+    for (index, leg) in path.legs.iter().enumerate() {
+        encoded["legs"][index]["route"] = json!(encode_polyline(&leg.route));
+    }
+    for (index, alternative) in path.alternatives.iter().enumerate() {
+        let encoded_alternative = &mut encoded["alternatives"][index];
+        encoded_alternative["route"] = json!(encode_polyline(&alternative.route));
+        encoded_alternative["elevation_profile"] = json!(encode_profile(&alternative.elevation_profile));
+        index_segments(&mut encoded_alternative["segments"]);
+    }
+
+    encoded
+}
+
+/*
+ *  index_segments: replaces the geometry of every segment feature by the coordinates it spans on the route line
+ *  purpose       : segment n runs from coordinate n to coordinate n + 1 of its route, so the line can be
+ *                  decoded once and sliced instead of sending every coordinate twice
+ */
+fn index_segments(segments: &mut Value) {
+    if let Some(features) = segments["features"].as_array_mut() {
+        for (index, feature) in features.iter_mut().enumerate() {
+            feature["geometry"] = Value::Null;
+            feature["route_range"] = json!([index, index + 1]);
+        }
+    }
+}
+
+/*
+ *  encode_polyline: encoded polyline algorithm format with 6 decimal places
+ *  purpose        : coordinates are [lng, lat] in the api, the format stores latitude first
+ */
+pub fn encode_polyline(coordinates: &[(f64, f64)]) -> String {
+    encode_pairs(coordinates.iter().map(|(lng, lat)| (*lat, *lng)), POLYLINE_PRECISION)
+}
+
+/* [meters from the start, elevation] pairs in the same format, to the decimeter */
+pub fn encode_profile(profile: &[(f64, f64)]) -> String {
+    encode_pairs(profile.iter().copied(), PROFILE_PRECISION)
+}
+
+fn encode_pairs(pairs: impl Iterator<Item = (f64, f64)>, precision: f64) -> String {
+    let mut encoded = String::new();
+    let (mut previous_first, mut previous_second) = (0, 0);
+
+    for (first, second) in pairs {
+        let first = (first * precision).round() as i64;
+        let second = (second * precision).round() as i64;
+        encode_value(first - previous_first, &mut encoded);
+        encode_value(second - previous_second, &mut encoded);
+        previous_first = first;
+        previous_second = second;
+    }
+
+    encoded
+}
+
+fn encode_value(value: i64, encoded: &mut String) {
+    let mut value = if value < 0 { !(value << 1) } else { value << 1 };
+
+    while value >= POLYLINE_CONTINUATION {
+        encoded.push(char::from(((POLYLINE_CONTINUATION | (value & POLYLINE_CHUNK_MASK)) + POLYLINE_OFFSET) as u8));
+        value >>= POLYLINE_CHUNK_BITS;
+    }
+    encoded.push(char::from((value + POLYLINE_OFFSET) as u8));
+}
+
+/* GPX 1.1 document with one track for the main route and one for every alternative */
+fn route_gpx(path: &ComputedPath) -> String {
+    let mut gpx = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<gpx version=\"1.1\" creator=\"striide\" xmlns=\"http://www.topografix.com/GPX/1/1\">\n",
+    );
+
+    gpx.push_str(&gpx_track("Striide route", path.distance_meters, &path.striide_route));
+    for (index, alternative) in path.alternatives.iter().enumerate() {
+        gpx.push_str(&gpx_track(&format!("Alternative {}", index + 1), alternative.distance_meters, &alternative.route));
+    }
+
+    gpx.push_str("</gpx>\n");
+    gpx
+}
+
+fn gpx_track(name: &str, distance: f64, coordinates: &[(f64, f64)]) -> String {
+    let points: String = coordinates
+        .iter()
+        .map(|(lng, lat)| format!("      <trkpt lat=\"{:.6}\" lon=\"{:.6}\"/>\n", lat, lng))
+        .collect();
+
+    format!(
+        "  <trk>\n    <name>{}</name>\n    <desc>{:.0} meters</desc>\n    <trkseg>\n{}    </trkseg>\n  </trk>\n",
+        name, distance, points
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /* the example of the polyline algorithm documentation at precision 6 */
+    #[test]
+    fn encodes_polyline6() {
+        let coordinates = vec![(-120.2, 38.5), (-120.95, 40.7), (-126.453, 43.252)];
+        assert_eq!(encode_polyline(&coordinates), "_izlhA~rlgdF_{geC~ywl@_kwzCn`{nI");
+    }
+
+    #[test]
+    fn encodes_empty_line() {
+        assert_eq!(encode_polyline(&[]), "");
+    }
+
+    #[test]
+    fn encodes_profile_in_decimeters() {
+        assert_eq!(encode_profile(&[(0.0, 12.3), (2.5, 11.8)]), "?uFq@H");
+    }
+}
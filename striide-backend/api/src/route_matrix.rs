@@ -0,0 +1,402 @@
+/*
+ * Internal Imports
+ */
+use infrastructure::database::Db;
+use models::user::RouteMatrixQuery;
+use shared::response_models::{Response, ResponseBody, RouteError, RouteMatrix};
+use crate::elevation::{grade_toward, walking_seconds};
+use crate::pathfinder::{build_weights, load_hazards};
+use crate::request_validation::{invalid_request, request_point};
+use crate::route_weights::RouteWeights;
+use crate::service_area::{ensure_in_service_area, locate_region};
+use crate::snapping::{snap_to_edge, Snap};
+use crate::{GraphState, NavGraph};
+
+/*
+ * External Imports
+ */
+use geo::Point;
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use petgraph::visit::EdgeRef;
+use rocket::{http::Status, serde::json::Json, State};
+use rocket_db_pools::Connection;
+use ordered_float::OrderedFloat;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+/*
+ * constants
+ */
+/* most origins or destinations in one request */
+const MAX_MATRIX_SIDE: usize = 25;
+/* most origin/destination pairs in one request - one search runs per origin, so this bounds the work */
+const MAX_MATRIX_CELLS: usize = 400;
+
+/*
+ *  MatrixState: entry of the open set of the search from one origin, wrapped in Reverse so the heap pops the cheapest node first
+ *  cost       : safety weighted cost from the origin to the node
+ */
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct MatrixState {
+    cost: OrderedFloat<f64>,
+    node: NodeIndex,
+}
+
+impl MatrixState {
+    fn queued(cost: f64, node: NodeIndex) -> Reverse<MatrixState> {
+        Reverse(MatrixState { cost: OrderedFloat(cost), node })
+    }
+}
+
+/*
+ *  MatrixEntry : cheapest route from one origin to one destination
+ *  duration    : walking time in seconds, slower uphill and on steep downhills
+ *  via         : node the route leaves the graph at onto the destination's edge, None when the
+ *                origin and the destination are on the same edge
+ */
+#[derive(Clone, Copy)]
+pub struct MatrixEntry {
+    pub cost: f64,
+    pub distance: f64,
+    pub duration: f64,
+    pub via: Option<NodeIndex>,
+}
+
+/*
+ *  route_matrix: walking distances and safety weighted costs from every origin to every destination
+ *  purpose     : opening hours and hazard reports are loaded once for the whole matrix and every
+ *                origin runs a single dijkstra that stops once all destinations are settled.
+ *                Destinations that cannot be reached are null instead of failing the request.
+ */
+#[post("/route_matrix", data = "<request>")]
+pub async fn route_matrix(
+    request: Json<RouteMatrixQuery>,
+    graph_state: &State<GraphState>,
+    mut db: Connection<Db>,
+) -> Result<Response, RouteError> {
+    let request = request.into_inner();
+    if request.origins.is_empty() || request.destinations.is_empty() {
+        return Err(invalid_request("empty_matrix", "origins and destinations must each contain at least one point", None));
+    }
+    if request.origins.len() > MAX_MATRIX_SIDE || request.destinations.len() > MAX_MATRIX_SIDE {
+        return Err(invalid_request(
+            "matrix_too_large",
+            &format!("origins and destinations can each contain at most {} points", MAX_MATRIX_SIDE),
+            None,
+        ));
+    }
+    if request.origins.len() * request.destinations.len() > MAX_MATRIX_CELLS {
+        return Err(invalid_request(
+            "matrix_too_large",
+            &format!("a matrix can contain at most {} origin/destination pairs", MAX_MATRIX_CELLS),
+            None,
+        ));
+    }
+
+    let points: Vec<(String, Point)> = request
+        .origins
+        .iter()
+        .enumerate()
+        .map(|(index, coordinates)| (format!("origins[{}]", index), coordinates))
+        .chain(request.destinations.iter().enumerate().map(|(index, coordinates)| (format!("destinations[{}]", index), coordinates)))
+        .map(|(field, coordinates)| Ok((field.clone(), request_point(coordinates, &field)?)))
+        .collect::<Result<Vec<(String, Point)>, RouteError>>()?;
+
+    let (_, snapshot) = locate_region(points[0].1, &points[0].0, graph_state)?;
+    let snaps = points
+        .iter()
+        .map(|(field, point)| {
+            ensure_in_service_area(*point, field, &snapshot)?;
+            snap_to_edge(*point, field, &snapshot)
+        })
+        .collect::<Result<Vec<Snap>, RouteError>>()?;
+
+    let weights = build_weights(&request.preference, request.departure_time, &mut db, &snapshot).await?;
+    let hazards = load_hazards(&mut db, &snapshot).await;
+    let weights = weights.with_hazards(&hazards);
+    let geometries = request.geometries.unwrap_or(false);
+    let destinations = snaps[request.origins.len()..].to_vec();
+    let origins = snaps[..request.origins.len()].to_vec();
+
+    /* the searches are cpu bound, so they run off the async workers */
+    let matrix = rocket::tokio::task::spawn_blocking(move || compute_matrix(&origins, &destinations, &weights, geometries, &snapshot))
+        .await
+        .map_err(|err| RouteError {
+            status: Status::InternalServerError,
+            code: "matrix_failed".to_owned(),
+            message: format!("route matrix did not finish: {}", err),
+            field: None,
+        })?;
+
+    Ok(Response {
+        status: Status::Ok,
+        body: ResponseBody::RouteMatrix(matrix),
+    })
+}
+
+fn compute_matrix(origins: &[Snap], destinations: &[Snap], weights: &RouteWeights, geometries: bool, navgraph: &NavGraph) -> RouteMatrix {
+    let mut matrix = RouteMatrix {
+        distances_meters: vec![],
+        durations_seconds: vec![],
+        costs: vec![],
+        geometries: if geometries { Some(vec![]) } else { None },
+    };
+
+    for origin in origins {
+        let (entries, parents) = one_to_many(origin, destinations, weights, f64::INFINITY, destinations.len(), navgraph);
+
+        matrix.distances_meters.push(entries.iter().map(|entry| entry.map(|entry| entry.distance)).collect());
+        matrix.durations_seconds.push(
+            entries
+                .iter()
+                .map(|entry| entry.map(|entry| entry.duration))
+                .collect(),
+        );
+        matrix.costs.push(entries.iter().map(|entry| entry.map(|entry| entry.cost)).collect());
+        if let Some(rows) = matrix.geometries.as_mut() {
+            rows.push(
+                entries
+                    .iter()
+                    .zip(destinations.iter())
+                    .map(|(entry, destination)| entry.map(|entry| route_line(origin, destination, entry.via, &parents, navgraph)))
+                    .collect(),
+            );
+        }
+    }
+
+    matrix
+}
+
+/*
+ *  one_to_many: cheapest route from the origin to the destinations and the search tree they are read from
+ *  purpose    : destinations in the middle of an edge are reached through either node of their edge,
+ *               so the search only stops once the wanted number of destinations is known to be
+ *               cheaper than everything left, or once max_cost is used up. Entries of destinations
+ *               the search did not settle can be more expensive than max_cost or missing.
+ */
+pub fn one_to_many(
+    origin: &Snap,
+    destinations: &[Snap],
+    weights: &RouteWeights,
+    max_cost: f64,
+    wanted: usize,
+    navgraph: &NavGraph,
+) -> (Vec<Option<MatrixEntry>>, HashMap<NodeIndex, NodeIndex>) {
+    let walk_cost = |edge: EdgeIndex, toward: NodeIndex| weights.walk_cost(edge, toward, navgraph);
+    let seconds = |edge: EdgeIndex, toward: NodeIndex, portion: f64| {
+        walking_seconds(navgraph.graph[edge].length * portion, grade_toward(edge, toward, navgraph))
+    };
+    let mut costs: HashMap<NodeIndex, f64> = HashMap::new();
+    let mut distances: HashMap<NodeIndex, f64> = HashMap::new();
+    let mut durations: HashMap<NodeIndex, f64> = HashMap::new();
+    let mut parents: HashMap<NodeIndex, NodeIndex> = HashMap::new();
+    let mut open: BinaryHeap<Reverse<MatrixState>> = BinaryHeap::new();
+
+    let mut best: Vec<Option<MatrixEntry>> = destinations
+        .iter()
+        .map(|destination| {
+            if destination.edge != origin.edge {
+                return None;
+            }
+            let (source, target) = navgraph.graph.edge_endpoints(origin.edge)?;
+            let toward = if destination.fraction >= origin.fraction { target } else { source };
+            let share = (origin.fraction - destination.fraction).abs();
+            Some(MatrixEntry {
+                cost: walk_cost(origin.edge, toward) * share,
+                distance: navgraph.graph[origin.edge].length * share,
+                duration: seconds(origin.edge, toward, share),
+                via: None,
+            })
+        })
+        .collect();
+
+    /* cheapest known cost of every destination, popped once the search has gone past it */
+    let mut reached: BinaryHeap<Reverse<(OrderedFloat<f64>, usize)>> = best
+        .iter()
+        .enumerate()
+        .filter_map(|(index, entry)| entry.map(|entry| Reverse((OrderedFloat(entry.cost), index))))
+        .collect();
+    let mut settled = 0;
+
+    /* destinations reachable from each node of their edge */
+    let mut exits: HashMap<NodeIndex, Vec<usize>> = HashMap::new();
+    for (index, destination) in destinations.iter().enumerate() {
+        if let Some((source, target)) = navgraph.graph.edge_endpoints(destination.edge) {
+            exits.entry(source).or_insert(vec![]).push(index);
+            exits.entry(target).or_insert(vec![]).push(index);
+        }
+    }
+
+    if let Some((source, target)) = navgraph.graph.edge_endpoints(origin.edge) {
+        for node in [source, target] {
+            let portion = origin.portion_to(node, navgraph);
+            let cost = walk_cost(origin.edge, node) * portion;
+            if cost < *costs.get(&node).unwrap_or(&f64::INFINITY) {
+                costs.insert(node, cost);
+                distances.insert(node, navgraph.graph[origin.edge].length * portion);
+                durations.insert(node, seconds(origin.edge, node, portion));
+                open.push(MatrixState::queued(cost, node));
+            }
+        }
+    }
+
+    while let Some(Reverse(MatrixState { cost: OrderedFloat(cost), node })) = open.pop() {
+        if cost > *costs.get(&node).unwrap_or(&f64::INFINITY) {
+            continue;
+        }
+        /* every later candidate costs at least this much, so destinations below it are final */
+        while let Some(&Reverse((OrderedFloat(reached_cost), index))) = reached.peek() {
+            if reached_cost > cost {
+                break;
+            }
+            reached.pop();
+            if best[index].is_some_and(|entry| entry.cost == reached_cost) {
+                settled += 1;
+            }
+        }
+        if cost > max_cost || settled >= wanted {
+            break;
+        }
+
+        let (distance, duration) = (distances[&node], durations[&node]);
+        for index in exits.get(&node).map(|indices| indices.as_slice()).unwrap_or(&[]) {
+            let destination = &destinations[*index];
+            let portion = destination.portion_to(node, navgraph);
+            let toward = match navgraph.graph.edge_endpoints(destination.edge) {
+                Some((source, target)) if source == node => target,
+                Some((source, _)) => source,
+                None => continue,
+            };
+            let candidate = MatrixEntry {
+                cost: cost + walk_cost(destination.edge, toward) * portion,
+                distance: distance + navgraph.graph[destination.edge].length * portion,
+                duration: duration + seconds(destination.edge, toward, portion),
+                via: Some(node),
+            };
+            if best[*index].is_none_or(|entry| candidate.cost < entry.cost) {
+                best[*index] = Some(candidate);
+                reached.push(Reverse((OrderedFloat(candidate.cost), *index)));
+            }
+        }
+
+        for edge in navgraph.graph.edges(node) {
+            let next = if edge.source() == node { edge.target() } else { edge.source() };
+            let next_cost = cost + walk_cost(edge.id(), next);
+            if next_cost < *costs.get(&next).unwrap_or(&f64::INFINITY) {
+                costs.insert(next, next_cost);
+                distances.insert(next, distance + edge.weight().length);
+                durations.insert(next, duration + seconds(edge.id(), next, 1.0));
+                parents.insert(next, node);
+                open.push(MatrixState::queued(next_cost, next));
+            }
+        }
+    }
+
+    (best, parents)
+}
+
+/* [lng, lat] coordinates from the origin through the search tree to the destination */
+pub fn route_line(
+    origin: &Snap,
+    destination: &Snap,
+    via: Option<NodeIndex>,
+    parents: &HashMap<NodeIndex, NodeIndex>,
+    navgraph: &NavGraph,
+) -> Vec<(f64, f64)> {
+    let mut nodes = vec![];
+    let mut current = via;
+    while let Some(node) = current {
+        nodes.push(node);
+        current = parents.get(&node).copied();
+    }
+    nodes.reverse();
+
+    std::iter::once(origin.point)
+        .chain(nodes.into_iter().map(|node| navgraph.graph[node]))
+        .chain(std::iter::once(destination.point))
+        .map(|point| (point.x(), point.y()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pathfinder::find_path;
+    use crate::test_graph::{distance_weights, navgraph, path_distance, snap, stop_distance};
+    use crate::shared::types::HashablePoint;
+
+    /* nodes the route line passes through between the origin and the destination */
+    fn line_nodes(line: &[(f64, f64)], navgraph: &NavGraph) -> Vec<NodeIndex> {
+        line[1..line.len() - 1]
+            .iter()
+            .map(|(x, y)| navgraph.point_to_index_map[&HashablePoint::new(*x, *y)])
+            .collect()
+    }
+
+    #[test]
+    fn matches_find_path_from_the_middle_of_an_edge() {
+        let navgraph = navgraph();
+        let weights = distance_weights();
+        let origin = snap(&navgraph, 0, 1, 0.3);
+        let destinations = vec![
+            snap(&navgraph, 0, 1, 0.8),
+            snap(&navgraph, 1, 0, 0.9),
+            snap(&navgraph, 4, 5, 0.5),
+            snap(&navgraph, 6, 7, 0.25),
+            snap(&navgraph, 5, 8, 0.9),
+            snap(&navgraph, 1, 4, 0.6),
+        ];
+
+        let (entries, parents) = one_to_many(&origin, &destinations, &weights, f64::INFINITY, destinations.len(), &navgraph);
+        for (entry, destination) in entries.iter().zip(destinations.iter()) {
+            let entry = entry.expect("every destination is on the grid");
+            let nodes = find_path(&origin, destination, &weights, &HashMap::new(), &navgraph).unwrap();
+            let expected = path_distance(&navgraph, &origin, destination, &nodes);
+            assert!((entry.distance - expected).abs() < 1e-6, "{} instead of {}", entry.distance, expected);
+            assert!((entry.distance - stop_distance(&navgraph, &origin, destination)).abs() < 1e-6);
+            assert!((entry.cost - entry.distance).abs() < 1e-6);
+
+            let line = route_line(&origin, destination, entry.via, &parents, &navgraph);
+            assert_eq!(line.first(), Some(&(origin.point.x(), origin.point.y())));
+            assert_eq!(line.last(), Some(&(destination.point.x(), destination.point.y())));
+            let walked = path_distance(&navgraph, &origin, destination, &line_nodes(&line, &navgraph));
+            assert!((walked - entry.distance).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn leaves_unreachable_destinations_empty() {
+        let navgraph = navgraph();
+        let origin = snap(&navgraph, 0, 1, 0.5);
+        let destinations = vec![snap(&navgraph, 9, 10, 0.5), snap(&navgraph, 7, 8, 0.5)];
+
+        let (entries, _) = one_to_many(&origin, &destinations, &distance_weights(), f64::INFINITY, destinations.len(), &navgraph);
+        assert!(entries[0].is_none());
+        assert!(entries[1].is_some());
+    }
+
+    #[test]
+    fn stops_once_the_wanted_destinations_are_settled() {
+        let navgraph = navgraph();
+        let origin = snap(&navgraph, 0, 1, 0.5);
+        let destinations = vec![snap(&navgraph, 0, 1, 0.9), snap(&navgraph, 6, 7, 0.5)];
+
+        let (entries, _) = one_to_many(&origin, &destinations, &distance_weights(), f64::INFINITY, 1, &navgraph);
+        assert!(entries[0].is_some());
+        assert!(entries[1].is_none());
+
+        let (entries, _) = one_to_many(&origin, &destinations, &distance_weights(), f64::INFINITY, 2, &navgraph);
+        assert!(entries[1].is_some());
+    }
+
+    #[test]
+    fn stops_once_max_cost_is_used_up() {
+        let navgraph = navgraph();
+        let origin = snap(&navgraph, 0, 1, 0.5);
+        let destinations = vec![snap(&navgraph, 0, 1, 0.9), snap(&navgraph, 6, 7, 0.5)];
+
+        let (entries, _) = one_to_many(&origin, &destinations, &distance_weights(), 100.0, destinations.len(), &navgraph);
+        assert!(entries[0].is_some());
+        assert!(entries[1].is_none());
+    }
+}
@@ -0,0 +1,229 @@
+/*
+ * Internal Imports
+ */
+use models::user::{AccessibilityProfile, RoutePreference};
+use shared::response_models::ErrorResponse;
+use crate::elevation::{grade_toward, slope_factor};
+use crate::hazards::Hazards;
+use crate::snapping::nearby_edges;
+use crate::{NavEdge, NavGraph};
+
+/*
+ * External Imports
+ */
+use geo::Point;
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use rocket::http::Status;
+use std::collections::HashMap;
+
+/*
+ * constants
+ */
+const DEFAULT_SAFETY_LEVEL: f64 = 0.5;
+/* weight of the darkness penalty baked into the graph when the user only cares about safety */
+const MAX_LIGHT_WEIGHT: f64 = 3.0;
+/* extra meters per meter walked along a sidewalk without businesses when the user only cares about safety */
+const MAX_BUSINESS_WEIGHT: f64 = 1.0;
+/* sidewalks with at least this many buildings per 100 meters are considered busy */
+const BUSY_BUILDING_DENSITY: f64 = 5.0;
+/* sidewalks with at least this many lights per 100 meters are fully lit */
+const WELL_LIT_DENSITY: f64 = 4.0;
+/* share of lights and businesses in the safety score of an edge */
+const LIGHT_SAFETY_SHARE: f64 = 0.7;
+const BUSINESS_SAFETY_SHARE: f64 = 0.3;
+/* businesses within this many meters of a sidewalk make it lively while they are open */
+const OPEN_BUSINESS_RADIUS: f64 = 40.0;
+/* edges checked around every open business before the radius is applied */
+const OPEN_BUSINESS_EDGE_CANDIDATES: usize = 16;
+/* sidewalks with at least this many open businesses nearby are considered fully lively */
+const LIVELY_OPEN_BUSINESSES: f64 = 2.0;
+/* once opening hours are known, buildings only count for this share of how busy a sidewalk is */
+const CLOSED_BUILDING_SHARE: f64 = 0.3;
+/* narrowest clear sidewalk width a wheelchair fits through - 36 inches */
+const MIN_ACCESSIBLE_WIDTH: f64 = 0.91;
+/* surfaces that are hard to roll over - anything else, including unknown surfaces, counts as paved */
+const ROUGH_SURFACES: [&str; 9] = ["gravel", "fine_gravel", "unpaved", "dirt", "ground", "grass", "sand", "cobblestone", "sett"];
+/* cost multipliers of edges that are usable but unpleasant for the profile */
+const WHEELCHAIR_ROUGH_SURFACE_PENALTY: f64 = 3.0;
+const STROLLER_ROUGH_SURFACE_PENALTY: f64 = 1.5;
+const STROLLER_NARROW_SIDEWALK_PENALTY: f64 = 1.5;
+const STROLLER_MISSING_CURB_RAMP_PENALTY: f64 = 2.0;
+
+/*
+ *  RouteWeights: how much a route cares about things other than distance
+ *  purpose     : every edge costs its length plus weighted penalties for darkness and for
+ *                stretches without businesses nearby, scaled by how much longer the slope makes
+ *                walking it. All penalties are non-negative, so an edge never costs less than its
+ *                length times the lowest slope factor.
+ *  open_businesses: number of open businesses near each edge at the departure time, None when
+ *                   opening hours are unavailable and only the building density can be used
+ *  hazards        : cost multiplier of edges close to active user reports
+ *  accessibility  : mobility profile - edges it cannot use are excluded and the ones it can use with
+ *                   difficulty cost more. Edges without accessibility data are treated as compliant.
+ *  slopes         : false when edges cost the same in both directions whatever their grade, e.g. to
+ *                   rank places by walking distance
+ */
+#[derive(Debug, Clone)]
+pub struct RouteWeights {
+    pub lights: f64,
+    pub businesses: f64,
+    pub open_businesses: Option<HashMap<EdgeIndex, usize>>,
+    pub hazards: HashMap<EdgeIndex, f64>,
+    pub accessibility: Option<AccessibilityProfile>,
+    pub slopes: bool,
+}
+
+impl RouteWeights {
+    pub fn from_preference(preference: &Option<RoutePreference>) -> Result<RouteWeights, ErrorResponse> {
+        let safety_level = match preference {
+            Some(preference) => preference.safety_level(),
+            None => DEFAULT_SAFETY_LEVEL,
+        };
+
+        if !(0.0..=1.0).contains(&safety_level) {
+            return Err(ErrorResponse {
+                status: Status::BadRequest,
+                message: "preference must be a number between 0 and 1 or one of fastest, balanced, safest".to_owned(),
+            });
+        }
+
+        Ok(RouteWeights {
+            lights: safety_level * MAX_LIGHT_WEIGHT,
+            businesses: safety_level * MAX_BUSINESS_WEIGHT,
+            open_businesses: None,
+            hazards: HashMap::new(),
+            accessibility: None,
+            slopes: true,
+        })
+    }
+
+    /* counts the businesses open at the departure time around every edge they are close to */
+    pub fn with_open_businesses(mut self, locations: &Vec<(f64, f64)>, navgraph: &NavGraph) -> RouteWeights {
+        let mut open_businesses: HashMap<EdgeIndex, usize> = HashMap::new();
+
+        for (x, y) in locations {
+            for (edge, _, distance) in nearby_edges(Point::new(*x, *y), OPEN_BUSINESS_EDGE_CANDIDATES, navgraph) {
+                if distance <= OPEN_BUSINESS_RADIUS {
+                    *open_businesses.entry(edge).or_insert(0) += 1;
+                }
+            }
+        }
+
+        self.open_businesses = Some(open_businesses);
+        self
+    }
+
+    pub fn with_hazards(mut self, hazards: &Hazards) -> RouteWeights {
+        self.hazards = hazards.penalties().clone();
+        self
+    }
+
+    pub fn with_accessibility(mut self, accessibility: Option<AccessibilityProfile>) -> RouteWeights {
+        self.accessibility = accessibility;
+        self
+    }
+
+    pub fn without_slopes(mut self) -> RouteWeights {
+        self.slopes = false;
+        self
+    }
+
+    /* true when every edge costs exactly its length, which is what the contraction hierarchy is built for */
+    pub fn is_distance_only(&self) -> bool {
+        self.lights == 0.0 && self.businesses == 0.0 && self.hazards.is_empty() && self.accessibility.is_none()
+    }
+
+    /* excluded edges cost infinity, so the searches never walk along them */
+    pub fn edge_cost(&self, index: EdgeIndex, edge: &NavEdge) -> f64 {
+        if self.is_excluded(edge) {
+            return f64::INFINITY;
+        }
+
+        let cost = edge.length + self.lights * edge.safety + self.businesses * edge.length * (1.0 - self.activity_factor(index, edge));
+        cost * self.hazards.get(&index).unwrap_or(&1.0) * self.accessibility_penalty(edge)
+    }
+
+    /* cost of walking along the edge toward one of its nodes - uphill and steep downhill walks take longer */
+    pub fn walk_cost(&self, index: EdgeIndex, toward: NodeIndex, navgraph: &NavGraph) -> f64 {
+        let cost = self.edge_cost(index, &navgraph.graph[index]);
+        if !self.slopes {
+            return cost;
+        }
+        cost * slope_factor(grade_toward(index, toward, navgraph))
+    }
+
+    /* true when the accessibility profile cannot use the edge at all */
+    pub fn is_excluded(&self, edge: &NavEdge) -> bool {
+        match self.accessibility {
+            Some(AccessibilityProfile::Wheelchair) => edge.stairs || edge.curb_ramps == Some(false) || is_narrow(edge),
+            Some(AccessibilityProfile::Stroller) => edge.stairs,
+            None => false,
+        }
+    }
+
+    /* 0.0 - 1.0 safety of an edge at the departure time - hazard reports nearby divide it by their penalty */
+    pub fn safety_factor(&self, index: EdgeIndex, edge: &NavEdge) -> f64 {
+        let safety = LIGHT_SAFETY_SHARE * lit_factor(edge) + BUSINESS_SAFETY_SHARE * self.activity_factor(index, edge);
+        safety / self.hazards.get(&index).unwrap_or(&1.0)
+    }
+
+    pub fn open_businesses_near(&self, index: EdgeIndex) -> Option<usize> {
+        self.open_businesses
+            .as_ref()
+            .map(|open_businesses| *open_businesses.get(&index).unwrap_or(&0))
+    }
+
+    /* cost multiplier (at least 1.0) of an edge the accessibility profile can use with difficulty */
+    fn accessibility_penalty(&self, edge: &NavEdge) -> f64 {
+        match self.accessibility {
+            Some(AccessibilityProfile::Wheelchair) if is_rough(edge) => WHEELCHAIR_ROUGH_SURFACE_PENALTY,
+            Some(AccessibilityProfile::Stroller) => {
+                let mut penalty = 1.0;
+                if is_rough(edge) {
+                    penalty *= STROLLER_ROUGH_SURFACE_PENALTY;
+                }
+                if is_narrow(edge) {
+                    penalty *= STROLLER_NARROW_SIDEWALK_PENALTY;
+                }
+                if edge.curb_ramps == Some(false) {
+                    penalty *= STROLLER_MISSING_CURB_RAMP_PENALTY;
+                }
+                penalty
+            }
+            _ => 1.0,
+        }
+    }
+
+    /* 0.0 - 1.0 how lively an edge is at the departure time */
+    fn activity_factor(&self, index: EdgeIndex, edge: &NavEdge) -> f64 {
+        match &self.open_businesses {
+            Some(open_businesses) => {
+                let open = *open_businesses.get(&index).unwrap_or(&0) as f64;
+                CLOSED_BUILDING_SHARE * busy_factor(edge) + (1.0 - CLOSED_BUILDING_SHARE) * (open / LIVELY_OPEN_BUSINESSES).min(1.0)
+            }
+            None => busy_factor(edge),
+        }
+    }
+}
+
+/* 0.0 for a sidewalk without businesses, 1.0 for a busy one */
+pub fn busy_factor(edge: &NavEdge) -> f64 {
+    (edge.building_density / BUSY_BUILDING_DENSITY).min(1.0)
+}
+
+/* 0.0 for a dark sidewalk, 1.0 for a well lit one */
+pub fn lit_factor(edge: &NavEdge) -> f64 {
+    (edge.light_density / WELL_LIT_DENSITY).min(1.0)
+}
+
+pub fn is_well_lit(edge: &NavEdge) -> bool {
+    edge.light_density >= WELL_LIT_DENSITY
+}
+
+fn is_narrow(edge: &NavEdge) -> bool {
+    edge.sidewalk_width.is_some_and(|width| width < MIN_ACCESSIBLE_WIDTH)
+}
+
+fn is_rough(edge: &NavEdge) -> bool {
+    edge.surface.as_deref().is_some_and(|surface| ROUGH_SURFACES.contains(&surface))
+}
@@ -0,0 +1,213 @@
+/*
+ * Internal Imports
+ */
+use shared::response_models::{ErrorResponse, RouteComparison};
+use crate::route_weights::is_well_lit;
+use crate::snapping::nearby_edges;
+use crate::NavGraph;
+
+/*
+ * External Imports
+ */
+use geo::{HaversineDistance, Point};
+use reqwest::Client;
+use rocket::fairing::AdHoc;
+use rocket::http::Status;
+use rocket::serde::Deserialize;
+
+/*
+ * constants
+ */
+const MAPBOX_DIRECTIONS_URL: &str = "https://api.mapbox.com/directions/v5/mapbox/walking";
+/* baseline segments further than this many meters from every sidewalk are left out of its lit percentage */
+const MAX_MATCH_DISTANCE_METERS: f64 = 25.0;
+/* edges checked around every baseline segment before the distance limit is applied */
+const MATCH_EDGE_CANDIDATES: usize = 4;
+
+/*
+ *  RoutingProvider : external router whose walking route is compared with the striide route
+ *  purpose         : the comparison shows how much longer the safer route is and how much more of
+ *                    it is lit. Providers are selected with routing_provider in Rocket.toml or
+ *                    ROCKET_ROUTING_PROVIDER.
+ */
+#[rocket::async_trait]
+pub trait RoutingProvider: Send + Sync {
+    fn name(&self) -> &str;
+
+    /* walking route through every stop in order as [lng, lat] coordinates */
+    async fn walking_route(&self, stops: &[(f64, f64)]) -> Result<Vec<(f64, f64)>, ErrorResponse>;
+}
+
+/* provider managed by rocket - None when no provider is configured and comparisons are skipped */
+pub struct BaselineProvider(pub Option<Box<dyn RoutingProvider>>);
+
+/*
+ *  MapboxProvider : walking directions of the Mapbox directions API
+ *  access_token   : mapbox_access_token in Rocket.toml or the MAPBOX_ACCESS_TOKEN environment variable
+ */
+pub struct MapboxProvider {
+    client: Client,
+    access_token: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(crate = "rocket::serde")]
+struct DirectionsResponse {
+    routes: Vec<Route>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(crate = "rocket::serde")]
+struct Route {
+    geometry: Geometry,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(crate = "rocket::serde")]
+struct Geometry {
+    coordinates: Vec<Vec<f64>>,
+}
+
+impl MapboxProvider {
+    pub fn new(access_token: String) -> MapboxProvider {
+        MapboxProvider {
+            client: Client::new(),
+            access_token,
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl RoutingProvider for MapboxProvider {
+    fn name(&self) -> &str {
+        "mapbox"
+    }
+
+    async fn walking_route(&self, stops: &[(f64, f64)]) -> Result<Vec<(f64, f64)>, ErrorResponse> {
+        let coordinates: Vec<String> = stops.iter().map(|(x, y)| format!("{}%2C{}", x, y)).collect();
+        let api_query = format!(
+            "{}/{}?continue_straight=true&geometries=geojson&overview=full&access_token={}",
+            MAPBOX_DIRECTIONS_URL,
+            coordinates.join("%3B"),
+            self.access_token
+        );
+
+        let res = self.client.get(&api_query).send().await.map_err(|err| ErrorResponse {
+            status: Status::BadGateway,
+            message: format!("could not reach mapbox: {}", err),
+        })?;
+
+        if !res.status().is_success() {
+            return Err(ErrorResponse {
+                status: Status::BadGateway,
+                message: format!("mapbox directions failed with status {}", res.status()),
+            });
+        }
+
+        let directions: DirectionsResponse = res.json().await.map_err(|err| ErrorResponse {
+            status: Status::BadGateway,
+            message: format!("could not read mapbox directions: {}", err),
+        })?;
+
+        match directions.routes.first() {
+            Some(route) => Ok(route.geometry.coordinates.iter().map(|coord| (coord[0], coord[1])).collect()),
+            None => Err(ErrorResponse {
+                status: Status::NotFound,
+                message: "mapbox has no walking route between the stops".to_owned(),
+            }),
+        }
+    }
+}
+
+/* straight lines between the stops - lets tests and local development compare routes without an api token */
+pub struct StubProvider;
+
+#[rocket::async_trait]
+impl RoutingProvider for StubProvider {
+    fn name(&self) -> &str {
+        "stub"
+    }
+
+    async fn walking_route(&self, stops: &[(f64, f64)]) -> Result<Vec<(f64, f64)>, ErrorResponse> {
+        Ok(stops.to_vec())
+    }
+}
+
+/*
+ *  stage: manages the configured provider
+ *  purpose: routing_provider picks "mapbox" or "stub". Without it mapbox is used whenever an
+ *           access token is available, otherwise comparisons are turned off.
+ */
+pub fn stage() -> AdHoc {
+    AdHoc::on_ignite("Routing Provider", |rocket| async {
+        let figment = rocket.figment();
+        let access_token = figment
+            .extract_inner::<String>("mapbox_access_token")
+            .ok()
+            .or_else(|| std::env::var("MAPBOX_ACCESS_TOKEN").ok());
+
+        let provider: Option<Box<dyn RoutingProvider>> = match (figment.extract_inner::<String>("routing_provider").ok().as_deref(), access_token) {
+            (Some("stub"), _) => Some(Box::new(StubProvider)),
+            (Some("mapbox") | None, Some(access_token)) => Some(Box::new(MapboxProvider::new(access_token))),
+            (Some("mapbox"), None) => {
+                println!("routing_provider is mapbox but no mapbox access token is configured - comparisons are turned off");
+                None
+            }
+            (Some(other), _) => {
+                println!("unknown routing_provider {} - comparisons are turned off", other);
+                None
+            }
+            (None, None) => None,
+        };
+
+        rocket.manage(BaselineProvider(provider))
+    })
+}
+
+/*
+ *  compare_routes: how the striide route differs from the provider's baseline route
+ *  purpose       : the baseline is matched onto the closest sidewalks to tell how much of it is
+ *                  lit. Parts of it that are not close to any sidewalk are left out of its lit
+ *                  percentage.
+ */
+pub fn compare_routes(
+    provider: &str,
+    baseline: &[(f64, f64)],
+    distance: f64,
+    lit_percentage: f64,
+    navgraph: &NavGraph,
+) -> RouteComparison {
+    let mut baseline_distance = 0.0;
+    let mut matched_distance = 0.0;
+    let mut lit_distance = 0.0;
+
+    for pair in baseline.windows(2) {
+        let start = Point::new(pair[0].0, pair[0].1);
+        let end = Point::new(pair[1].0, pair[1].1);
+        let length = start.haversine_distance(&end);
+        baseline_distance += length;
+
+        let middle = Point::new((start.x() + end.x()) / 2.0, (start.y() + end.y()) / 2.0);
+        let closest = nearby_edges(middle, MATCH_EDGE_CANDIDATES, navgraph)
+            .into_iter()
+            .min_by(|a, b| a.2.total_cmp(&b.2))
+            .filter(|(_, _, distance)| *distance <= MAX_MATCH_DISTANCE_METERS);
+
+        if let Some((edge, _, _)) = closest {
+            matched_distance += length;
+            if is_well_lit(&navgraph.graph[edge]) {
+                lit_distance += length;
+            }
+        }
+    }
+
+    let baseline_lit_percentage = if matched_distance > 0.0 { 100.0 * lit_distance / matched_distance } else { 0.0 };
+
+    RouteComparison {
+        provider: provider.to_owned(),
+        baseline_distance_meters: baseline_distance,
+        extra_distance_meters: distance - baseline_distance,
+        baseline_lit_percentage,
+        lit_percentage_difference: lit_percentage - baseline_lit_percentage,
+    }
+}
@@ -0,0 +1,105 @@
+/*
+ * Internal Imports
+ */
+use application::business::get_open_business_places;
+use infrastructure::database::Db;
+use models::business::OpenBusiness;
+use models::user::{RoutePreference, RouteProfile, SafePlaceQuery};
+use shared::response_models::{Response, ResponseBody, RouteError, SafePlace, SafePlaces};
+use crate::route_matrix::{one_to_many, route_line};
+use crate::request_validation::{invalid_request, request_point};
+use crate::route_weights::RouteWeights;
+use crate::service_area::locate_region;
+use crate::snapping::{snap_to_edge, Snap};
+use crate::{GraphState, NavGraph};
+
+/*
+ * External Imports
+ */
+use chrono::Utc;
+use geo::{HaversineDistance, Intersects, Point};
+use rocket::{http::Status, serde::json::Json, State};
+use rocket_db_pools::Connection;
+
+/*
+ * constants
+ */
+const DEFAULT_PLACE_COUNT: usize = 3;
+const MAX_PLACE_COUNT: usize = 10;
+/* places further than this on foot are not worth suggesting - about 25 minutes of walking */
+const MAX_WALKING_METERS: f64 = 2000.0;
+
+/*
+ *  nearest_safe_place: the open businesses closest to the user on foot, each with the route to it
+ *  purpose           : someone who feels unsafe needs somewhere open and staffed they can reach
+ *                      quickly. Businesses are ranked by the walking distance along the sidewalks,
+ *                      not the straight line, which can be far shorter across rivers and highways.
+ */
+#[post("/nearest_safe_place", data = "<request>")]
+pub async fn nearest_safe_place(
+    request: Json<SafePlaceQuery>,
+    graph_state: &State<GraphState>,
+    mut db: Connection<Db>,
+) -> Result<Response, RouteError> {
+    let request = request.into_inner();
+    let origin = request_point(&request.origin, "origin")?;
+
+    let count = request.count.unwrap_or(DEFAULT_PLACE_COUNT);
+    if count == 0 || count > MAX_PLACE_COUNT {
+        return Err(invalid_request("invalid_count", &format!("count must be between 1 and {}", MAX_PLACE_COUNT), Some("count")));
+    }
+
+    let (_, snapshot) = locate_region(origin, "origin", graph_state)?;
+    let navgraph: &NavGraph = &snapshot;
+    let origin_snap = snap_to_edge(origin, "origin", navgraph)?;
+
+    let (businesses, snaps) = reachable_candidates(get_open_business_places(&mut db, Utc::now()).await?, origin, navgraph);
+    /* fastest without slopes costs exactly the walking distance, so the search is bounded and ranked in meters */
+    let weights = RouteWeights::from_preference(&Some(RoutePreference::Profile(RouteProfile::Fastest)))?.without_slopes();
+    let (entries, parents) = one_to_many(&origin_snap, &snaps, &weights, MAX_WALKING_METERS, count, navgraph);
+
+    let mut places: Vec<SafePlace> = businesses
+        .into_iter()
+        .zip(snaps.iter())
+        .zip(entries)
+        .filter_map(|((business, snap), entry)| {
+            let entry = entry.filter(|entry| entry.distance <= MAX_WALKING_METERS)?;
+            Some(SafePlace {
+                id: business.id,
+                name: business.name,
+                address: business.address,
+                location: (business.longitude, business.latitude),
+                walking_distance_meters: entry.distance,
+                eta_seconds: entry.duration,
+                closes_in_minutes: business.closes_in_minutes,
+                route: route_line(&origin_snap, snap, entry.via, &parents, navgraph),
+            })
+        })
+        .collect();
+    places.sort_by(|a, b| a.walking_distance_meters.total_cmp(&b.walking_distance_meters));
+    places.truncate(count);
+
+    Ok(Response {
+        status: Status::Ok,
+        body: ResponseBody::SafePlaces(SafePlaces { places }),
+    })
+}
+
+/*
+ *  reachable_candidates: open businesses that could be within walking range, snapped onto their sidewalk
+ *  purpose             : the walking distance is never shorter than the straight line, so businesses
+ *                        further than MAX_WALKING_METERS in a straight line are dropped before the search
+ */
+fn reachable_candidates(businesses: Vec<OpenBusiness>, origin: Point, navgraph: &NavGraph) -> (Vec<OpenBusiness>, Vec<Snap>) {
+    businesses
+        .into_iter()
+        .filter_map(|business| {
+            let location = Point::new(business.longitude, business.latitude);
+            if origin.haversine_distance(&location) > MAX_WALKING_METERS || !navgraph.service_area.intersects(&location) {
+                return None;
+            }
+            let snap = snap_to_edge(location, "business", navgraph).ok()?;
+            Some((business, snap))
+        })
+        .unzip()
+}
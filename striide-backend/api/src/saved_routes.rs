@@ -0,0 +1,139 @@
+/*
+ * Internal Imports
+ */
+use application::saved_routes::{
+    create_saved_routes_table, get_shared_route, get_user_saved_routes, insert_saved_route, revoke_saved_route,
+};
+use application::utils::create_token;
+use infrastructure::database::Db;
+use models::saved_route::NewSavedRoute;
+use models::user::{UserRouteQuery, UserSession};
+use shared::response_models::{ErrorResponse, Response, ResponseBody, RouteError, SavedRoutes};
+use crate::pathfinder::{compute_route, origin_region};
+use crate::route_cache::RouteCache;
+use crate::routing_provider::BaselineProvider;
+use crate::GraphState;
+
+/*
+ * External Imports
+ */
+use chrono::{Duration, Utc};
+use rocket::http::Status;
+use rocket::serde::json::{to_string, Json};
+use rocket::serde::Deserialize;
+use rocket::State;
+use rocket_db_pools::Connection;
+
+/*
+ * constants
+ */
+/* alphanumeric characters in a share token - long enough that links cannot be guessed */
+const SHARE_TOKEN_LENGTH: usize = 32;
+/* share links work for a week unless the request asks for something else */
+const DEFAULT_EXPIRY_HOURS: i64 = 24 * 7;
+const MAX_EXPIRY_HOURS: i64 = 24 * 90;
+
+/*
+ *  SaveRouteRequest : route query to compute and save
+ *  expires_in_hours : lifetime of the share link, DEFAULT_EXPIRY_HOURS when left out
+ */
+#[derive(Deserialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct SaveRouteRequest {
+    route: UserRouteQuery,
+    expires_in_hours: Option<i64>,
+}
+
+fn json_error(err: serde_json::Error) -> ErrorResponse {
+    ErrorResponse {
+        status: Status::InternalServerError,
+        message: format!("Failed to serialize saved route: {}", err),
+    }
+}
+
+/*
+ *  save_route: computes the route and saves it for the user with a share link
+ *  purpose   : the route is stored as it was computed, together with the graph version, so a
+ *              shared link shows the same route even after the graph is regenerated
+ */
+#[post("/routes", data = "<request>")]
+pub async fn save_route(
+    session: UserSession,
+    request: Json<SaveRouteRequest>,
+    graph_state: &State<GraphState>,
+    baseline: &State<BaselineProvider>,
+    route_cache: &State<RouteCache>,
+    mut db: Connection<Db>,
+) -> Result<Response, RouteError> {
+    let SaveRouteRequest { route: query, expires_in_hours } = request.into_inner();
+    let expires_in_hours = expires_in_hours.unwrap_or(DEFAULT_EXPIRY_HOURS);
+    if expires_in_hours <= 0 || expires_in_hours > MAX_EXPIRY_HOURS {
+        return Err(RouteError {
+            status: Status::BadRequest,
+            code: "invalid_expiry".to_owned(),
+            message: format!("share links can expire after 1 to {} hours", MAX_EXPIRY_HOURS),
+            field: Some("expires_in_hours".to_owned()),
+        });
+    }
+
+    let (region, snapshot) = origin_region(&query, graph_state)?;
+    let path = compute_route(&query, &region.config.key, &snapshot, baseline, route_cache, &mut db).await?;
+
+    create_saved_routes_table(&mut db).await?;
+    let saved_route = insert_saved_route(
+        &mut db,
+        NewSavedRoute {
+            userid: session.id,
+            share_token: create_token(SHARE_TOKEN_LENGTH),
+            origin: query.origin.clone(),
+            destination: query.destination.clone(),
+            parameters: to_string(&query).map_err(json_error)?,
+            route: to_string(&path).map_err(json_error)?,
+            distance_meters: path.distance_meters,
+            graph_version: snapshot.version(),
+            expires_at: Utc::now() + Duration::hours(expires_in_hours),
+        },
+    )
+    .await?;
+
+    Ok(Response {
+        status: Status::Created,
+        body: ResponseBody::SavedRoute(saved_route),
+    })
+}
+
+/* routes saved by the user, newest first */
+#[get("/routes")]
+pub async fn saved_routes(session: UserSession, mut db: Connection<Db>) -> Result<Response, ErrorResponse> {
+    create_saved_routes_table(&mut db).await?;
+    let routes = get_user_saved_routes(&mut db, session.id).await?;
+
+    Ok(Response {
+        status: Status::Ok,
+        body: ResponseBody::SavedRoutes(SavedRoutes { routes }),
+    })
+}
+
+/* saved route behind a share link - no session needed until the link expires or is revoked */
+#[get("/routes/shared/<token>")]
+pub async fn shared_route(token: String, mut db: Connection<Db>) -> Result<Response, ErrorResponse> {
+    create_saved_routes_table(&mut db).await?;
+    let route = get_shared_route(&mut db, token).await?;
+
+    Ok(Response {
+        status: Status::Ok,
+        body: ResponseBody::SavedRoute(route),
+    })
+}
+
+/* turns the share link of one of the user's routes off, the route stays in their list */
+#[post("/routes/<token>/revoke")]
+pub async fn revoke_route(token: String, session: UserSession, mut db: Connection<Db>) -> Result<Response, ErrorResponse> {
+    create_saved_routes_table(&mut db).await?;
+    revoke_saved_route(&mut db, token, session.id).await?;
+
+    Ok(Response {
+        status: Status::Ok,
+        body: ResponseBody::Message("share link revoked".to_owned()),
+    })
+}
@@ -0,0 +1,78 @@
+/*
+ * Internal Imports
+ */
+use shared::response_models::{ErrorResponse, RouteError};
+use crate::graph_state::Region;
+use crate::{GraphState, NavGraph};
+
+/*
+ * External Imports
+ */
+use geo::{Intersects, Point};
+use rocket::{http::Status, serde::json::Value, State};
+use serde_json::json;
+use std::sync::Arc;
+
+/*
+ *  ensure_in_service_area: rejects route requests for points the navigation graph does not cover
+ *  purpose               : outside of the service area the closest sidewalk can be far away or
+ *                          belong to a different neighbourhood, so the route would be misleading
+ */
+pub fn ensure_in_service_area(point: Point, field: &str, navgraph: &NavGraph) -> Result<(), RouteError> {
+    if navgraph.service_area.intersects(&point) {
+        return Ok(());
+    }
+
+    Err(outside_service_area(field))
+}
+
+/* first region whose service area contains the point with the graph snapshot it was found in - requests are routed within it */
+pub fn locate_region<'a>(point: Point, field: &str, graph_state: &'a GraphState) -> Result<(&'a Region, Arc<NavGraph>), RouteError> {
+    graph_state
+        .regions()
+        .iter()
+        .map(|region| (region, region.current()))
+        .find(|(_, navgraph)| navgraph.service_area.intersects(&point))
+        .ok_or_else(|| outside_service_area(field))
+}
+
+fn outside_service_area(field: &str) -> RouteError {
+    RouteError {
+        status: Status::UnprocessableEntity,
+        code: "outside_service_area".to_owned(),
+        message: format!("{} is outside of the area currently covered by striide", field),
+        field: Some(field.to_owned()),
+    }
+}
+
+/* service area polygon of a region (the first configured one by default) as a GeoJSON feature so the frontend can draw the coverage */
+#[get("/service_area?<region>")]
+pub fn service_area(region: Option<String>, graph_state: &State<GraphState>) -> Result<Value, ErrorResponse> {
+    let region = match &region {
+        Some(key) => graph_state.region(key),
+        None => graph_state.regions().first(),
+    }
+    .ok_or(ErrorResponse {
+        status: Status::NotFound,
+        message: "unknown region - see /regions for the supported regions".to_owned(),
+    })?;
+
+    let snapshot = region.current();
+    let navgraph: &NavGraph = &snapshot;
+    let ring: Vec<(f64, f64)> = navgraph.service_area
+        .exterior()
+        .points()
+        .map(|point| (point.x(), point.y()))
+        .collect();
+
+    Ok(json!({
+        "type": "Feature",
+        "geometry": {
+            "type": "Polygon",
+            "coordinates": [ring],
+        },
+        "properties": {
+            "region": region.config.key,
+        },
+    }))
+}
@@ -0,0 +1,153 @@
+/*
+ * Internal Imports
+ */
+use shared::response_models::RouteError;
+use crate::route_weights::RouteWeights;
+use crate::{NavEdge, NavGraph};
+
+/*
+ * External Imports
+ */
+use geo::{HaversineDistance, Point};
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use rocket::http::Status;
+
+/*
+ * constants
+ */
+/* used when MAX_SNAP_DISTANCE_METERS is not set */
+const DEFAULT_MAX_SNAP_DISTANCE: f64 = 150.0;
+/* the edge tree works in degrees, so the closest few edges are compared in meters before picking one */
+const SNAP_CANDIDATES: usize = 8;
+/*
+ *  edges are searched in degrees, which shrink in meters along the longitude away from the equator -
+ *  the search for a usable edge gives up once candidates are this many max snap distances away
+ */
+const SEARCH_DISTANCE_FACTOR: f64 = 2.0;
+
+/*
+ *  Snap     : a query point projected onto the closest sidewalk edge of the graph
+ *  edge     : edge the point was projected onto
+ *  point    : projected point on the edge
+ *  fraction : position of the projected point along the edge, 0 at the edge's source node and 1 at its target
+ *  distance : meters between the query point and the projected point
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct Snap {
+    pub edge: EdgeIndex,
+    pub point: Point,
+    pub fraction: f64,
+    pub distance: f64,
+}
+
+impl Snap {
+    /* share of the snapped edge lying between the projected point and one of the edge's nodes */
+    pub fn portion_to(&self, node: NodeIndex, navgraph: &NavGraph) -> f64 {
+        match navgraph.graph.edge_endpoints(self.edge) {
+            Some((source, _)) if source == node => self.fraction,
+            _ => 1.0 - self.fraction,
+        }
+    }
+}
+
+/*
+ *  snap_to_edge: projects a query point onto the closest sidewalk edge
+ *  purpose     : lets a route start or end in the middle of a block instead of at the closest
+ *                graph vertex, which may be behind the user. Points further away from any
+ *                sidewalk than the max snap distance are rejected.
+ */
+pub fn snap_to_edge(query: Point, field: &str, navgraph: &NavGraph) -> Result<Snap, RouteError> {
+    snap_to_usable_edge(query, field, navgraph, |_| true)
+}
+
+/*
+ *  snap_to_accessible_edge: projects a query point onto the closest sidewalk the accessibility profile of the weights can use
+ *  purpose                : the closest sidewalk can be a flight of steps with an accessible one a few meters
+ *                           away. Points are only rejected when no usable sidewalk is within the max snap distance.
+ */
+pub fn snap_to_accessible_edge(query: Point, field: &str, weights: &RouteWeights, navgraph: &NavGraph) -> Result<Snap, RouteError> {
+    snap_to_usable_edge(query, field, navgraph, |edge| !weights.is_excluded(edge))
+}
+
+fn snap_to_usable_edge(query: Point, field: &str, navgraph: &NavGraph, is_usable: impl Fn(&NavEdge) -> bool) -> Result<Snap, RouteError> {
+    let max_distance = max_snap_distance();
+    let mut nearest_distance: Option<f64> = None;
+    let mut candidates: Vec<(EdgeIndex, Point, f64)> = vec![];
+
+    for candidate in navgraph.edge_tree.nearest_neighbor_iter(&[query.x(), query.y()]) {
+        let [x, y] = candidate.geom().nearest_point(&[query.x(), query.y()]);
+        let projected = Point::new(x, y);
+        let distance = query.haversine_distance(&projected);
+        nearest_distance = Some(nearest_distance.map_or(distance, |nearest| nearest.min(distance)));
+
+        if distance > max_distance * SEARCH_DISTANCE_FACTOR {
+            break;
+        }
+        if is_usable(&navgraph.graph[candidate.data]) {
+            candidates.push((candidate.data, projected, distance));
+            if candidates.len() == SNAP_CANDIDATES {
+                break;
+            }
+        }
+    }
+
+    let (edge, point, distance) = match (candidates.into_iter().min_by(|a, b| a.2.total_cmp(&b.2)), nearest_distance) {
+        (Some(closest), _) if closest.2 <= max_distance => closest,
+        (_, None) => return Err(RouteError {
+            status: Status::InternalServerError,
+            code: "empty_graph".to_owned(),
+            message: "navigation graph does not contain any sidewalks".to_owned(),
+            field: None,
+        }),
+        (_, Some(nearest)) if nearest <= max_distance => return Err(RouteError {
+            status: Status::UnprocessableEntity,
+            code: "inaccessible_stop".to_owned(),
+            message: format!(
+                "no sidewalk within {:.0} meters of {} is accessible with the requested accessibility profile",
+                max_distance, field
+            ),
+            field: Some(field.to_owned()),
+        }),
+        (_, Some(nearest)) => return Err(RouteError {
+            status: Status::UnprocessableEntity,
+            code: "too_far_from_sidewalk".to_owned(),
+            message: format!(
+                "{} is {:.0} meters away from the nearest sidewalk - it must be within {:.0} meters",
+                field, nearest, max_distance
+            ),
+            field: Some(field.to_owned()),
+        }),
+    };
+
+    let (source, target) = navgraph.graph.edge_endpoints(edge).unwrap();
+    let to_source = point.haversine_distance(&navgraph.graph[source]);
+    let to_target = point.haversine_distance(&navgraph.graph[target]);
+    let fraction = if to_source + to_target > 0.0 { to_source / (to_source + to_target) } else { 0.0 };
+
+    Ok(Snap {
+        edge,
+        point,
+        fraction,
+        distance,
+    })
+}
+
+/* closest edges to a point (by the edge tree's degree distance) with the projected point and its distance in meters */
+pub fn nearby_edges(query: Point, count: usize, navgraph: &NavGraph) -> Vec<(EdgeIndex, Point, f64)> {
+    navgraph.edge_tree
+        .nearest_neighbor_iter(&[query.x(), query.y()])
+        .take(count)
+        .map(|candidate| {
+            let [x, y] = candidate.geom().nearest_point(&[query.x(), query.y()]);
+            let projected = Point::new(x, y);
+            (candidate.data, projected, query.haversine_distance(&projected))
+        })
+        .collect()
+}
+
+fn max_snap_distance() -> f64 {
+    std::env::var("MAX_SNAP_DISTANCE_METERS")
+        .ok()
+        .and_then(|distance| distance.parse::<f64>().ok())
+        .unwrap_or(DEFAULT_MAX_SNAP_DISTANCE)
+}
@@ -0,0 +1,151 @@
+/*
+ * Internal Imports
+ */
+use crate::route_weights::RouteWeights;
+use crate::snapping::Snap;
+use crate::{build_nav_graph, NavEdge, NavGraph};
+
+/*
+ * External Imports
+ */
+use geo::{HaversineDistance, Point};
+use petgraph::graph::NodeIndex;
+use std::collections::HashMap;
+
+/*
+ * constants
+ */
+/*
+ *  3 x 3 grid of sidewalks 0.001 degrees apart with a diagonal (nodes 0 - 8, node 0 in the south west corner),
+ *  a separate sidewalk (nodes 9 - 10) and a node without any sidewalk (node 11)
+ */
+pub const NODES: [(f64, f64); 12] = [
+    (-79.400, 43.650), (-79.399, 43.650), (-79.398, 43.650),
+    (-79.400, 43.651), (-79.399, 43.651), (-79.398, 43.651),
+    (-79.400, 43.652), (-79.399, 43.652), (-79.398, 43.652),
+    (-79.390, 43.650), (-79.389, 43.650),
+    (-79.380, 43.660),
+];
+/* source, target and how much longer than the straight line the sidewalk is - 1 - 4 winds a long way around */
+pub const EDGES: [(usize, usize, f64); 14] = [
+    (0, 1, 1.0), (1, 2, 1.2), (3, 4, 1.0), (4, 5, 1.1), (6, 7, 1.3), (7, 8, 1.0),
+    (0, 3, 1.0), (3, 6, 1.1), (1, 4, 5.0), (4, 7, 1.0), (2, 5, 1.0), (5, 8, 1.2),
+    (0, 4, 1.5),
+    (9, 10, 1.0),
+];
+/* edges of the grid, the ones every grid stop can reach */
+pub const GRID_EDGES: usize = 13;
+
+pub fn navgraph() -> NavGraph {
+    let nodes: Vec<Point> = NODES.iter().map(|(x, y)| Point::new(*x, *y)).collect();
+    let edges = EDGES
+        .iter()
+        .map(|(source, target, detour)| (*source, *target, sidewalk(nodes[*source].haversine_distance(&nodes[*target]) * detour)))
+        .collect();
+    build_nav_graph(None, nodes, edges, None, vec![None; NODES.len()]).unwrap()
+}
+
+/* every edge costs its length */
+pub fn distance_weights() -> RouteWeights {
+    RouteWeights {
+        lights: 0.0,
+        businesses: 0.0,
+        open_businesses: None,
+        hazards: HashMap::new(),
+        accessibility: None,
+        slopes: true,
+    }
+}
+
+/* stop on the edge between two nodes, the fraction of the way from source to target */
+pub fn snap(navgraph: &NavGraph, source: usize, target: usize, fraction: f64) -> Snap {
+    let (source, target) = (NodeIndex::new(source), NodeIndex::new(target));
+    let edge = navgraph.graph.find_edge(source, target).unwrap();
+    let fraction = if navgraph.graph.edge_endpoints(edge) == Some((source, target)) { fraction } else { 1.0 - fraction };
+    let (from, to) = navgraph.graph.edge_endpoints(edge).unwrap();
+    let (from, to) = (navgraph.graph[from], navgraph.graph[to]);
+
+    Snap {
+        edge,
+        point: Point::new(from.x() + (to.x() - from.x()) * fraction, from.y() + (to.y() - from.y()) * fraction),
+        fraction,
+        distance: 0.0,
+    }
+}
+
+/* shortest distance between two stops by brute force, infinity when they are not connected */
+pub fn stop_distance(navgraph: &NavGraph, origin: &Snap, destination: &Snap) -> f64 {
+    let ends = |snap: &Snap| {
+        let (source, target) = navgraph.graph.edge_endpoints(snap.edge).unwrap();
+        [source, target].map(|node| (node, navgraph.graph[snap.edge].length * snap.portion_to(node, navgraph)))
+    };
+    let distances = dijkstra(navgraph, &ends(origin));
+    let direct = if origin.edge == destination.edge {
+        navgraph.graph[origin.edge].length * (origin.fraction - destination.fraction).abs()
+    } else {
+        f64::INFINITY
+    };
+
+    ends(destination)
+        .iter()
+        .map(|(node, portion)| distances[node.index()] + portion)
+        .fold(direct, f64::min)
+}
+
+/* length of a route from the origin through the nodes to the destination */
+pub fn path_distance(navgraph: &NavGraph, origin: &Snap, destination: &Snap, nodes: &[NodeIndex]) -> f64 {
+    let (first, last) = match (nodes.first(), nodes.last()) {
+        (Some(first), Some(last)) => (*first, *last),
+        _ => return navgraph.graph[origin.edge].length * (origin.fraction - destination.fraction).abs(),
+    };
+    let between: f64 = nodes
+        .windows(2)
+        .map(|pair| navgraph.graph[navgraph.graph.find_edge(pair[0], pair[1]).unwrap()].length)
+        .sum();
+
+    navgraph.graph[origin.edge].length * origin.portion_to(first, navgraph)
+        + between
+        + navgraph.graph[destination.edge].length * destination.portion_to(last, navgraph)
+}
+
+/* distance to every node from the seeds, by scanning for the closest unsettled node */
+fn dijkstra(navgraph: &NavGraph, seeds: &[(NodeIndex, f64)]) -> Vec<f64> {
+    let mut distances = vec![f64::INFINITY; navgraph.graph.node_count()];
+    let mut settled = vec![false; navgraph.graph.node_count()];
+    for (node, distance) in seeds {
+        distances[node.index()] = distances[node.index()].min(*distance);
+    }
+
+    while let Some(node) = (0..distances.len())
+        .filter(|node| !settled[*node] && distances[*node].is_finite())
+        .min_by(|a, b| distances[*a].total_cmp(&distances[*b]))
+    {
+        settled[node] = true;
+        for neighbor in navgraph.graph.neighbors(NodeIndex::new(node)) {
+            let edge = navgraph.graph.find_edge(NodeIndex::new(node), neighbor).unwrap();
+            let distance = distances[node] + navgraph.graph[edge].length;
+            if distance < distances[neighbor.index()] {
+                distances[neighbor.index()] = distance;
+            }
+        }
+    }
+
+    distances
+}
+
+fn sidewalk(length: f64) -> NavEdge {
+    NavEdge {
+        length,
+        safety: 0.0,
+        light_density: 0.0,
+        building_density: 0.0,
+        street_name: None,
+        light_count: 0,
+        building_count: 0,
+        sidewalk_width: None,
+        curb_ramps: None,
+        stairs: false,
+        surface: None,
+        grade: None,
+    }
+}
@@ -0,0 +1,436 @@
+/*
+ * Internal Imports
+ */
+use application::trips::{
+    create_trip_tables, get_stalled_trips, get_trip_events, get_user_trip, insert_trip, insert_trip_events, update_trip,
+};
+use infrastructure::database::Db;
+use models::trip::{NewTrip, Trip, TripEvent, TripEventKind, TripLocation, TripStatus};
+use models::user::{UserRouteQuery, UserSession};
+use shared::response_models::{ErrorResponse, Response, ResponseBody, RouteError, TripEventResponse, TripResponse};
+use crate::pathfinder::{compute_route, origin_region};
+use crate::request_validation::request_point;
+use crate::route_cache::RouteCache;
+use crate::routing_provider::BaselineProvider;
+use crate::snapping::nearby_edges;
+use crate::{GraphState, NavGraph};
+
+/*
+ * External Imports
+ */
+use chrono::{DateTime, Duration, Utc};
+use geo::{Closest, ClosestPoint, HaversineDistance, Line, Point};
+use rocket::fairing::AdHoc;
+use rocket::http::Status;
+use rocket::serde::json::{to_string, Json};
+use rocket::State;
+use rocket_db_pools::{sqlx::PgConnection, Connection, Database};
+
+/*
+ * constants
+ */
+/* locations further than this many meters from the route flag the trip as off route */
+const OFF_ROUTE_THRESHOLD_METERS: f64 = 40.0;
+/* locations closer than this to the last movement are treated as gps noise, not movement */
+const MOVEMENT_THRESHOLD_METERS: f64 = 15.0;
+/* used when TRIP_STALL_MINUTES is not set */
+const DEFAULT_STALL_MINUTES: i64 = 5;
+/* the trip ends on its own once the user is this close to the destination */
+const ARRIVAL_RADIUS_METERS: f64 = 30.0;
+/* sidewalks around a location checked for being part of the route */
+const TRIP_EDGE_CANDIDATES: usize = 8;
+/* edges checked around the middle of every route segment to find the edge it was computed on */
+const ROUTE_EDGE_CANDIDATES: usize = 4;
+/* route segments further than this from every edge were not computed on the current graph */
+const MAX_ROUTE_MATCH_METERS: f64 = 1.0;
+/* how often trips whose phone stopped reporting are checked for stalling */
+const STALL_CHECK_INTERVAL_SECONDS: u64 = 60;
+
+/*
+ *  start_trip: computes the route and starts tracking the user along it
+ *  purpose   : the core of walk me home - the user reports their location while walking and
+ *              the trip flags when they leave the route or stop moving
+ */
+#[post("/trips", data = "<request>")]
+pub async fn start_trip(
+    session: UserSession,
+    request: Json<UserRouteQuery>,
+    graph_state: &State<GraphState>,
+    baseline: &State<BaselineProvider>,
+    route_cache: &State<RouteCache>,
+    mut db: Connection<Db>,
+) -> Result<Response, RouteError> {
+    let query = request.into_inner();
+    let (region, snapshot) = origin_region(&query, graph_state)?;
+    let path = compute_route(&query, &region.config.key, &snapshot, baseline, route_cache, &mut db).await?;
+
+    create_trip_tables(&mut db).await?;
+    let trip = insert_trip(
+        &mut db,
+        NewTrip {
+            userid: session.id,
+            region: region.config.key.clone(),
+            graph_version: snapshot.version(),
+            route: to_string(&path.striide_route).map_err(|err| ErrorResponse {
+                status: Status::InternalServerError,
+                message: format!("Failed to serialize trip route: {}", err),
+            })?,
+            route_edges: to_string(&route_edges(&path.striide_route, &snapshot)).map_err(|err| ErrorResponse {
+                status: Status::InternalServerError,
+                message: format!("Failed to serialize trip route edges: {}", err),
+            })?,
+            origin: query.origin.clone(),
+            destination: query.destination.clone(),
+        },
+    )
+    .await?;
+
+    let started = vec![TripEvent {
+        kind: TripEventKind::Started,
+        location: Some(query.origin.clone()),
+        distance_from_route: None,
+        created_at: trip.started_at,
+    }];
+    insert_trip_events(&mut db, trip.id, &started).await?;
+
+    Ok(Response {
+        status: Status::Created,
+        body: ResponseBody::TripResponse(trip_response(trip, started)),
+    })
+}
+
+/* location report of the user walking the trip - returns the trip with every event so far */
+#[post("/trips/<id>/location", data = "<request>")]
+pub async fn report_location(
+    id: i32,
+    session: UserSession,
+    request: Json<TripLocation>,
+    graph_state: &State<GraphState>,
+    mut db: Connection<Db>,
+) -> Result<Response, RouteError> {
+    let location = request_point(&request.into_inner().location, "location")?;
+
+    create_trip_tables(&mut db).await?;
+    let mut trip = get_user_trip(&mut db, id, session.id).await?;
+    if trip.status != TripStatus::Active {
+        return Err(RouteError {
+            status: Status::Conflict,
+            code: "trip_finished".to_owned(),
+            message: format!("trip {} is no longer active", trip.id),
+            field: None,
+        });
+    }
+
+    let region = graph_state.region(&trip.region).ok_or(RouteError {
+        status: Status::Conflict,
+        code: "region_unavailable".to_owned(),
+        message: format!("region {} of the trip is no longer served", trip.region),
+        field: None,
+    })?;
+    let snapshot = region.current();
+
+    let events = track_location(&mut trip, location, Utc::now(), &snapshot);
+    update_trip(&mut db, &trip).await?;
+    insert_trip_events(&mut db, trip.id, &events).await?;
+
+    Ok(Response {
+        status: Status::Ok,
+        body: ResponseBody::TripResponse(load_trip_response(&mut db, trip).await?),
+    })
+}
+
+/* trip with every event so far - also flags a user whose phone stopped reporting as stalled, between two stall checks */
+#[get("/trips/<id>")]
+pub async fn get_trip(id: i32, session: UserSession, mut db: Connection<Db>) -> Result<Response, ErrorResponse> {
+    create_trip_tables(&mut db).await?;
+    let mut trip = get_user_trip(&mut db, id, session.id).await?;
+
+    if let Some(stalled) = check_stalled(&mut trip, Utc::now()) {
+        update_trip(&mut db, &trip).await?;
+        insert_trip_events(&mut db, trip.id, &vec![stalled]).await?;
+    }
+
+    Ok(Response {
+        status: Status::Ok,
+        body: ResponseBody::TripResponse(load_trip_response(&mut db, trip).await?),
+    })
+}
+
+/* the user ended the trip before reaching the destination */
+#[post("/trips/<id>/end")]
+pub async fn end_trip(id: i32, session: UserSession, mut db: Connection<Db>) -> Result<Response, ErrorResponse> {
+    create_trip_tables(&mut db).await?;
+    let mut trip = get_user_trip(&mut db, id, session.id).await?;
+
+    if trip.status == TripStatus::Active {
+        let now = Utc::now();
+        trip.status = TripStatus::Ended;
+        trip.ended_at = Some(now);
+        update_trip(&mut db, &trip).await?;
+        insert_trip_events(
+            &mut db,
+            trip.id,
+            &vec![TripEvent {
+                kind: TripEventKind::Ended,
+                location: trip.last_location.clone(),
+                distance_from_route: None,
+                created_at: now,
+            }],
+        )
+        .await?;
+    }
+
+    Ok(Response {
+        status: Status::Ok,
+        body: ResponseBody::TripResponse(load_trip_response(&mut db, trip).await?),
+    })
+}
+
+/*
+ *  track_location: moves the trip to a reported location and returns what changed
+ *  purpose       : events are only recorded when the state changes, so a user walking off route
+ *                  gets one OffRoute event and one BackOnRoute event, not one per location
+ */
+fn track_location(trip: &mut Trip, location: Point, now: DateTime<Utc>, navgraph: &NavGraph) -> Vec<TripEvent> {
+    let coordinates = vec![location.x(), location.y()];
+    let (distance, segment) = route_position(location, trip, navgraph);
+    let event = |kind: TripEventKind| TripEvent {
+        kind,
+        location: Some(coordinates.clone()),
+        distance_from_route: Some(distance),
+        created_at: now,
+    };
+    let mut events = vec![];
+
+    let off_route = distance > OFF_ROUTE_THRESHOLD_METERS;
+    if off_route != trip.off_route {
+        events.push(event(if off_route { TripEventKind::OffRoute } else { TripEventKind::BackOnRoute }));
+    }
+    if !off_route {
+        trip.route_progress = segment;
+    }
+    trip.off_route = off_route;
+    trip.distance_from_route = Some(distance);
+
+    let moved_from = Point::new(trip.moved_location[0], trip.moved_location[1]);
+    if location.haversine_distance(&moved_from) > MOVEMENT_THRESHOLD_METERS {
+        trip.moved_location = coordinates.clone();
+        trip.moved_at = now;
+        if trip.stalled {
+            trip.stalled = false;
+            events.push(event(TripEventKind::Moving));
+        }
+    } else if let Some(mut stalled) = check_stalled(trip, now) {
+        stalled.location = Some(coordinates.clone());
+        stalled.distance_from_route = Some(distance);
+        events.push(stalled);
+    }
+
+    let destination = Point::new(trip.destination[0], trip.destination[1]);
+    if location.haversine_distance(&destination) <= ARRIVAL_RADIUS_METERS {
+        trip.status = TripStatus::Arrived;
+        trip.ended_at = Some(now);
+        events.push(event(TripEventKind::Arrived));
+    }
+
+    trip.last_location = Some(coordinates.clone());
+    trip.last_seen_at = Some(now);
+    events
+}
+
+/* flags an active trip whose user has not moved for the stall duration, once */
+fn check_stalled(trip: &mut Trip, now: DateTime<Utc>) -> Option<TripEvent> {
+    if trip.status != TripStatus::Active || trip.stalled || now - trip.moved_at < stall_duration() {
+        return None;
+    }
+
+    trip.stalled = true;
+    Some(TripEvent {
+        kind: TripEventKind::Stalled,
+        location: trip.last_location.clone(),
+        distance_from_route: trip.distance_from_route,
+        created_at: now,
+    })
+}
+
+/*
+ *  route_position: meters between a location and the rest of the route, and the route segment closest to it
+ *  purpose       : only the route from the segment the user last walked along is measured, so walking back
+ *                  along a part of the route already walked is off route. The sidewalks closest to the
+ *                  location are looked up in the edge tree and the closest one the rest of the route was
+ *                  matched to gives the distance. When none of them is, or the graph changed since the
+ *                  route was matched, the distance to the route line itself is used.
+ */
+fn route_position(location: Point, trip: &Trip, navgraph: &NavGraph) -> (f64, usize) {
+    if navgraph.version() != trip.graph_version {
+        return closest_segment(location, &trip.route, trip.route_progress);
+    }
+
+    let remaining = trip.route_progress..trip.route_edges.len();
+    let on_route = nearby_edges(location, TRIP_EDGE_CANDIDATES, navgraph)
+        .into_iter()
+        .filter_map(|(edge, _, distance)| {
+            remaining
+                .clone()
+                .find(|segment| trip.route_edges[*segment] == Some(edge.index()))
+                .map(|segment| (distance, segment))
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0));
+
+    on_route.unwrap_or_else(|| closest_segment(location, &trip.route, trip.route_progress))
+}
+
+/* closest route segment to a location from the given segment on, with its distance in meters */
+fn closest_segment(location: Point, route: &[(f64, f64)], from: usize) -> (f64, usize) {
+    let distance_to = |closest: Closest<f64>| match closest {
+        Closest::Intersection(closest) | Closest::SinglePoint(closest) => location.haversine_distance(&closest),
+        Closest::Indeterminate => f64::INFINITY,
+    };
+
+    route
+        .windows(2)
+        .enumerate()
+        .skip(from)
+        .map(|(segment, pair)| (distance_to(Line::new(pair[0], pair[1]).closest_point(&location)), segment))
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .unwrap_or_else(|| {
+            let distance = route.last().map(|last| location.haversine_distance(&Point::from(*last)));
+            (distance.unwrap_or(f64::INFINITY), from)
+        })
+}
+
+/* graph edge of every route segment, found from the middle of the segment - None when it is not on the graph */
+fn route_edges(route: &[(f64, f64)], navgraph: &NavGraph) -> Vec<Option<usize>> {
+    route
+        .windows(2)
+        .map(|pair| {
+            let middle = Point::new((pair[0].0 + pair[1].0) / 2.0, (pair[0].1 + pair[1].1) / 2.0);
+            nearby_edges(middle, ROUTE_EDGE_CANDIDATES, navgraph)
+                .into_iter()
+                .min_by(|a, b| a.2.total_cmp(&b.2))
+                .filter(|(_, _, distance)| *distance <= MAX_ROUTE_MATCH_METERS)
+                .map(|(edge, _, _)| edge.index())
+        })
+        .collect()
+}
+
+/*
+ *  stage: checks active trips for stalling every STALL_CHECK_INTERVAL_SECONDS
+ *  purpose: a phone that stopped reporting never sends the location report that would flag its
+ *           trip, so the trips are also checked in the background
+ */
+pub fn stage() -> AdHoc {
+    AdHoc::on_liftoff("Trip Stall Check", |rocket| {
+        Box::pin(async move {
+            let pool = match Db::fetch(rocket) {
+                Some(db) => (**db).clone(),
+                None => {
+                    println!("trip stall check is disabled: no database is attached");
+                    return;
+                }
+            };
+
+            rocket::tokio::spawn(async move {
+                let mut interval = rocket::tokio::time::interval(std::time::Duration::from_secs(STALL_CHECK_INTERVAL_SECONDS));
+                loop {
+                    interval.tick().await;
+                    match pool.acquire().await {
+                        Ok(mut conn) => {
+                            if let Err(err) = flag_stalled_trips(&mut conn).await {
+                                println!("trip stall check failed: {}", err.message);
+                            }
+                        }
+                        Err(err) => println!("trip stall check could not connect to the database: {}", err),
+                    }
+                }
+            });
+        })
+    })
+}
+
+/* flags every active trip whose user has not moved for the stall duration */
+async fn flag_stalled_trips(conn: &mut PgConnection) -> Result<(), ErrorResponse> {
+    let now = Utc::now();
+    create_trip_tables(conn).await?;
+    for mut trip in get_stalled_trips(conn, now - stall_duration()).await? {
+        if let Some(stalled) = check_stalled(&mut trip, now) {
+            update_trip(conn, &trip).await?;
+            insert_trip_events(conn, trip.id, &vec![stalled]).await?;
+        }
+    }
+
+    Ok(())
+}
+
+fn stall_duration() -> Duration {
+    let minutes = std::env::var("TRIP_STALL_MINUTES")
+        .ok()
+        .and_then(|minutes| minutes.parse::<i64>().ok())
+        .unwrap_or(DEFAULT_STALL_MINUTES);
+    Duration::minutes(minutes)
+}
+
+async fn load_trip_response(conn: &mut PgConnection, trip: Trip) -> Result<TripResponse, ErrorResponse> {
+    let events = get_trip_events(conn, trip.id).await?;
+    Ok(trip_response(trip, events))
+}
+
+fn trip_response(trip: Trip, events: Vec<TripEvent>) -> TripResponse {
+    TripResponse {
+        id: trip.id,
+        region: trip.region,
+        graph_version: trip.graph_version,
+        status: trip.status.to_string(),
+        route: trip.route,
+        destination: trip.destination,
+        off_route: trip.off_route,
+        stalled: trip.stalled,
+        distance_from_route_meters: trip.distance_from_route,
+        last_location: trip.last_location,
+        last_seen_at: trip.last_seen_at.map(|seen| seen.to_rfc3339()),
+        started_at: trip.started_at.to_rfc3339(),
+        ended_at: trip.ended_at.map(|ended| ended.to_rfc3339()),
+        events: events
+            .into_iter()
+            .map(|event| TripEventResponse {
+                kind: event.kind.to_string(),
+                location: event.location,
+                distance_from_route_meters: event.distance_from_route,
+                created_at: event.created_at.to_rfc3339(),
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /* an out and back route along a street: the walker is at its far end */
+    fn out_and_back() -> Vec<(f64, f64)> {
+        vec![(-79.400, 43.650), (-79.399, 43.650), (-79.398, 43.650), (-79.399, 43.650), (-79.400, 43.650)]
+    }
+
+    #[test]
+    fn measures_to_the_closest_segment() {
+        let (distance, segment) = closest_segment(Point::new(-79.3985, 43.6501), &out_and_back(), 0);
+        assert!(distance < 12.0);
+        assert_eq!(segment, 1);
+    }
+
+    #[test]
+    fn ignores_the_route_already_walked() {
+        let location = Point::new(-79.3995, 43.6501);
+        let (_, walked) = closest_segment(location, &out_and_back(), 0);
+        let (_, remaining) = closest_segment(location, &out_and_back(), 2);
+        assert_eq!(walked, 0);
+        assert_eq!(remaining, 3);
+    }
+
+    #[test]
+    fn measures_to_a_single_point_route() {
+        let (distance, segment) = closest_segment(Point::new(-79.400, 43.651), &[(-79.400, 43.650)], 0);
+        assert!((distance - 111.2).abs() < 1.0);
+        assert_eq!(segment, 0);
+    }
+}
@@ -1,6 +1,6 @@
 use application::upload_reports::{delete_draft, get_all_published_reports, get_report_dislikes, get_report_draft, get_report_ids, get_report_info, get_report_likes, get_user_liked_report, insert_report, set_draft_to_publish, user_like_report, Id, ReportLike};
 use infrastructure::database::Db;
-use rocket::{http::Status, serde::json::Json};
+use rocket::{http::Status, serde::json::Json, State};
 // use rocket_db_pools::Connection;
 // use infrastructure::database::Db;
 use models::user::UserSession;
@@ -8,6 +8,7 @@ use shared::response_models::{
     BasicReportInfo, ClientResponse, ErrorResponse, ReportBody, ReportRequest, Reports, Response, ResponseBody, UserReport, UserReportLikes
 };
 use rocket_db_pools::Connection;
+use crate::route_cache::RouteCache;
 
 #[post("/upload_report", data = "<request>")]
 pub async fn upload_report(
@@ -30,11 +31,11 @@ pub async fn fetch_reports(
     mut db: Connection<Db>,
     user_session: UserSession,
 ) -> Result<Response, ErrorResponse> {
-    let reports = get_all_published_reports(user_session, &mut **db).await?;
+    let reports = get_all_published_reports(user_session, &mut db).await?;
 
     Ok(Response {
         status: Status::Ok,
-        body: ResponseBody::Reports(Reports { reports: reports }),
+        body: ResponseBody::Reports(Reports { reports }),
     })
 }
 
@@ -43,7 +44,7 @@ pub async fn fetch_report_draft(
     request: Json<ReportRequest>,
     mut db: Connection<Db>,
 ) -> Result<Response, ErrorResponse> {
-    let report_body = get_report_draft(request.into_inner(), &mut **db).await?;
+    let report_body = get_report_draft(request.into_inner(), &mut db).await?;
 
     Ok(Response {
         status: Status::Ok,
@@ -65,7 +66,7 @@ pub async fn fetch_report_draft(
 #[post("/discard_draft", data="<request>")]
 pub async fn discard_draft(request: Json<ReportRequest>, mut db: Connection<Db>) -> Result<Response, ErrorResponse>{
 
-    let rows_affected = delete_draft(request.into_inner(), &mut **db).await?;  
+    let rows_affected = delete_draft(request.into_inner(), &mut db).await?;  
 
     Ok(Response {
         status: Status::Ok, 
@@ -76,10 +77,14 @@ pub async fn discard_draft(request: Json<ReportRequest>, mut db: Connection<Db>)
     })
 }
 
+/* a published report penalizes the sidewalks around it, so routes cached before it are dropped */
 #[post("/publish_draft", data="<request>")]
-pub async fn publish_draft(request: Json<ReportRequest>, mut db: Connection<Db>) -> Result<Response, ErrorResponse>{
+pub async fn publish_draft(request: Json<ReportRequest>, route_cache: &State<RouteCache>, mut db: Connection<Db>) -> Result<Response, ErrorResponse>{
 
-    let rows_affected = set_draft_to_publish(request.into_inner(), &mut **db).await?;  
+    let rows_affected = set_draft_to_publish(request.into_inner(), &mut db).await?;  
+    if rows_affected > 0 {
+        route_cache.clear(); 
+    }
 
     Ok(Response {
         status: Status::Ok, 
@@ -92,7 +97,7 @@ pub async fn publish_draft(request: Json<ReportRequest>, mut db: Connection<Db>)
 
 #[get("/report_ids")]
 pub async fn report_ids(mut db: Connection<Db>) -> Result<Response, ErrorResponse> {
-    let report_ids = get_report_ids(&mut *db).await?;
+    let report_ids = get_report_ids(&mut db).await?;
 
     Ok(Response {
         status: Status::Ok,
@@ -114,21 +119,21 @@ pub async fn get_report(request: Json<Id>) -> Result<Response, ErrorResponse> {
 #[post("/report_likes", data="<request>")]
 pub async fn report_likes(request: Json<Id>, session: UserSession, mut db: Connection<Db>) -> Result<Response, ErrorResponse> {
     let id = request.into_inner().id;
-    let report_likes = get_report_likes(id.clone(), &mut *db).await.map_err(|_| {
+    let report_likes = get_report_likes(id.clone(), &mut db).await.map_err(|_| {
         ErrorResponse {
             status: Status::InternalServerError,
             message: "Failed to get report likes".to_owned(),
         }
     })?;
 
-    let report_dislikes = get_report_dislikes(id.clone(), &mut *db).await.map_err(|_| {
+    let report_dislikes = get_report_dislikes(id.clone(), &mut db).await.map_err(|_| {
         ErrorResponse {
             status: Status::InternalServerError,
             message: "Failed to get report dislikes".to_owned(),
         }
     })?;
 
-    let user_liked = get_user_liked_report(id, session.id, &mut *db).await.map_err(|_| {
+    let user_liked = get_user_liked_report(id, session.id, &mut db).await.map_err(|_| {
         ErrorResponse {
             status: Status::InternalServerError,
             message: "Failed to get user liked report".to_owned(),
@@ -148,7 +153,7 @@ pub async fn report_likes(request: Json<Id>, session: UserSession, mut db: Conne
 #[post("/like_report", data="<request>")]
 pub async fn like_report(request: Json<ReportLike>, session: UserSession, mut db: Connection<Db>) -> Result<Response, ErrorResponse> {
     let req = request.into_inner();
-    user_like_report(req.id, session.id, req.liked, &mut *db).await?;
+    user_like_report(req.id, session.id, req.liked, &mut db).await?;
     Ok(Response {
         status: Status::Ok,
         body: ResponseBody::ClientResponse(ClientResponse {
@@ -19,7 +19,7 @@ pub async fn user_exists(conn: &mut PgConnection, email: String) -> Result<bool,
         .bind(email)
         .fetch_optional(conn)
         .await
-        .and_then(|row| Ok(row.is_some()))
+        .map(|row| row.is_some())
         .map_err(|_| ErrorResponse {
             status: Status::InternalServerError,
             message: "Failed to check if user exists".to_owned(),
@@ -89,7 +89,7 @@ pub async fn insert_user_ip(
         .bind(user_id)
         .execute(conn)
         .await
-        .and_then(|_| Ok(()))
+        .map(|_| ())
         .map_err(|_| ErrorResponse {
             status: Status::InternalServerError,
             message: "Failed to create user ip".to_owned(),
@@ -110,9 +110,7 @@ pub async fn insert_session(
         .bind(user_id)
         .execute(conn)
         .await
-        .and_then(|_| {
-            Ok(())
-        })
+        .map(|_| ())
         .map_err(|_| {
             ErrorResponse {
                 status: Status::InternalServerError,
@@ -150,7 +148,7 @@ pub async fn remove_session(conn: &mut PgConnection, user_id: String) -> Result<
         .bind(user_id)
         .execute(conn)
         .await
-        .and_then(|_| Ok(()))
+        .map(|_| ())
         .map_err(|_| ErrorResponse {
             status: Status::InternalServerError,
             message: "Failed to delete session".to_owned(),
@@ -168,7 +166,7 @@ pub async fn valid_refresh_token(
         .and_then(|row| {
             let expires: DateTime<Utc> = row.try_get("refresh_expires")?;
             let is_active = expires > Utc::now();
-            return Ok(is_active);
+            Ok(is_active)
         })
         .map_err(|_| {
             ErrorResponse {
@@ -218,7 +216,7 @@ pub async fn new_access_token(
     .bind(refresh_token)
     .execute(conn)
     .await
-    .and_then(|_| Ok(new_token))
+    .map(|_| new_token)
     .map_err(|_| ErrorResponse {
         status: Status::InternalServerError,
         message: "Failed to update access token".to_owned(),
@@ -236,7 +234,7 @@ pub async fn new_csrf_token(
         .bind(access_token)
         .execute(conn)
         .await
-        .and_then(|_| Ok(new_token))
+        .map(|_| new_token)
         .map_err(|_| ErrorResponse {
             status: Status::InternalServerError,
             message: "Failed to update csrf token".to_owned(),
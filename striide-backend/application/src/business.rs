@@ -7,14 +7,20 @@ use rocket_db_pools::sqlx::{PgConnection, Row};
 /*
  * Internal imports
  */
-use chrono::{Datelike, Timelike, Utc, Weekday};
+use chrono::{DateTime, Datelike, Timelike, Utc, Weekday};
 use chrono_tz::US::Eastern;
+use models::business::OpenBusiness;
 use shared::response_models::ErrorResponse;
 
-pub async fn get_open_businesses(conn: &mut PgConnection) -> Result<Vec<i64>, ErrorResponse> {
-    let now_est = Utc::now().with_timezone(&Eastern);
-    let minutes = (now_est.num_seconds_from_midnight() / 60) as i32;
-    let weekday = match now_est.weekday() {
+/*
+ *  opening hours are stored per weekday (0 = monday) in minutes since midnight eastern time.
+ *  businesses open past midnight have a close time above 1440 on the day they opened, which is
+ *  why the previous day is also checked with the minutes shifted by a full day.
+ */
+fn week_time(at: DateTime<Utc>) -> (i32, i32) {
+    let local = at.with_timezone(&Eastern);
+    let minutes = (local.num_seconds_from_midnight() / 60) as i32;
+    let weekday = match local.weekday() {
         Weekday::Mon => 0,
         Weekday::Tue => 1,
         Weekday::Wed => 2,
@@ -24,6 +30,18 @@ pub async fn get_open_businesses(conn: &mut PgConnection) -> Result<Vec<i64>, Er
         Weekday::Sun => 6,
     };
 
+    (weekday, minutes)
+}
+
+/* (weekday, minutes) of the time itself and of the day before with the minutes shifted by a full day */
+fn open_hours_times(at: DateTime<Utc>) -> [(i32, i32); 2] {
+    let (weekday, minutes) = week_time(at);
+    [(weekday, minutes), ((weekday - 1).rem_euclid(7), minutes + 1440)]
+}
+
+pub async fn get_open_businesses(conn: &mut PgConnection) -> Result<Vec<i64>, ErrorResponse> {
+    let [(weekday, minutes), (previous_day, shifted_minutes)] = open_hours_times(Utc::now());
+
     let result = sqlx::query(
         "
         SELECT mapbox_id 
@@ -34,17 +52,17 @@ pub async fn get_open_businesses(conn: &mut PgConnection) -> Result<Vec<i64>, Er
     )
     .bind(weekday)
     .bind(minutes)
-    .bind((weekday - 1) % 7)
-    .bind(minutes + 1440)
+    .bind(previous_day)
+    .bind(shifted_minutes)
     .fetch_all(conn)
     .await
-    .and_then(|rows| {
+    .map(|rows| {
         let building_ids: Vec<i64> = rows
             .into_iter()
             .map(|row| row.try_get("mapbox_id"))
             .filter_map(Result::ok)
             .collect();
-        Ok(building_ids)
+        building_ids
     });
 
     match result {
@@ -55,3 +73,134 @@ pub async fn get_open_businesses(conn: &mut PgConnection) -> Result<Vec<i64>, Er
         }),
     }
 }
+
+/* coordinates (longitude, latitude) of every business open at the given time */
+pub async fn get_open_business_locations(conn: &mut PgConnection, at: DateTime<Utc>) -> Result<Vec<(f64, f64)>, ErrorResponse> {
+    let [(weekday, minutes), (previous_day, shifted_minutes)] = open_hours_times(at);
+
+    let result = sqlx::query(
+        "
+        SELECT DISTINCT business_info.longitude, business_info.latitude 
+        FROM business_hours 
+        INNER JOIN business_info ON business_hours.business = business_info.xata_id 
+        WHERE (day = $1 AND open <= $2 AND close >= $2) OR (day = $3 AND open <= $4 AND close >= $4)
+    ",
+    )
+    .bind(weekday)
+    .bind(minutes)
+    .bind(previous_day)
+    .bind(shifted_minutes)
+    .fetch_all(conn)
+    .await
+    .map(|rows| {
+        let locations: Vec<(f64, f64)> = rows
+            .into_iter()
+            .map(|row| Ok((row.try_get("longitude")?, row.try_get("latitude")?)))
+            .filter_map(|location: Result<(f64, f64), sqlx::Error>| location.ok())
+            .collect();
+        locations
+    });
+
+    match result {
+        Ok(locations) => Ok(locations),
+        Err(_) => Err(ErrorResponse {
+            status: Status::InternalServerError,
+            message: "Failed to retrieve open business locations".to_owned(),
+        }),
+    }
+}
+
+/* businesses open at the given time with where they are and how long they stay open */
+pub async fn get_open_business_places(conn: &mut PgConnection, at: DateTime<Utc>) -> Result<Vec<OpenBusiness>, ErrorResponse> {
+    let [(weekday, minutes), (previous_day, shifted_minutes)] = open_hours_times(at);
+
+    let result = sqlx::query(
+        "
+        SELECT business_info.xata_id, business_info.name, business_info.address, business_info.longitude, business_info.latitude, 
+               MAX(CASE WHEN day = $1 THEN close - $2 ELSE close - $4 END)::INTEGER AS closes_in_minutes 
+        FROM business_hours 
+        INNER JOIN business_info ON business_hours.business = business_info.xata_id 
+        WHERE (day = $1 AND open <= $2 AND close >= $2) OR (day = $3 AND open <= $4 AND close >= $4) 
+        GROUP BY business_info.xata_id, business_info.name, business_info.address, business_info.longitude, business_info.latitude
+    ",
+    )
+    .bind(weekday)
+    .bind(minutes)
+    .bind(previous_day)
+    .bind(shifted_minutes)
+    .fetch_all(conn)
+    .await
+    .map(|rows| {
+        let businesses: Vec<OpenBusiness> = rows
+            .into_iter()
+            .map(|row| {
+                Ok(OpenBusiness {
+                    id: row.try_get("xata_id")?,
+                    name: row.try_get("name")?,
+                    address: row.try_get("address")?,
+                    longitude: row.try_get("longitude")?,
+                    latitude: row.try_get("latitude")?,
+                    closes_in_minutes: row.try_get("closes_in_minutes")?,
+                })
+            })
+            .filter_map(|business: Result<OpenBusiness, sqlx::Error>| business.ok())
+            .collect();
+        businesses
+    });
+
+    match result {
+        Ok(businesses) => Ok(businesses),
+        Err(_) => Err(ErrorResponse {
+            status: Status::InternalServerError,
+            message: "Failed to retrieve open businesses".to_owned(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    /* the WHERE clause of the open business queries for one row of business_hours */
+    fn is_open(day: i32, open: i32, close: i32, at: DateTime<Utc>) -> bool {
+        open_hours_times(at)
+            .iter()
+            .any(|(weekday, minutes)| day == *weekday && open <= *minutes && close >= *minutes)
+    }
+
+    #[test]
+    fn week_time_is_in_eastern_time() {
+        /* 00:30 on monday january 1st 2024, standard time */
+        assert_eq!(week_time(Utc.with_ymd_and_hms(2024, 1, 1, 5, 30, 0).unwrap()), (0, 30));
+        /* 00:30 on monday july 1st 2024, daylight saving time */
+        assert_eq!(week_time(Utc.with_ymd_and_hms(2024, 7, 1, 4, 30, 0).unwrap()), (0, 30));
+        /* 23:59 on sunday june 30th 2024 */
+        assert_eq!(week_time(Utc.with_ymd_and_hms(2024, 7, 1, 3, 59, 0).unwrap()), (6, 1439));
+    }
+
+    #[test]
+    fn previous_day_wraps_from_monday_to_sunday() {
+        let monday = Utc.with_ymd_and_hms(2024, 7, 1, 4, 30, 0).unwrap();
+        assert_eq!(open_hours_times(monday), [(0, 30), (6, 1470)]);
+    }
+
+    #[test]
+    fn hours_past_midnight_count_on_the_next_day() {
+        /* open sunday from 22:00 until 02:00 on monday */
+        let (day, open, close) = (6, 22 * 60, 26 * 60);
+        assert!(is_open(day, open, close, Utc.with_ymd_and_hms(2024, 7, 1, 2, 0, 0).unwrap()));
+        assert!(is_open(day, open, close, Utc.with_ymd_and_hms(2024, 7, 1, 4, 30, 0).unwrap()));
+        assert!(!is_open(day, open, close, Utc.with_ymd_and_hms(2024, 7, 1, 6, 30, 0).unwrap()));
+        assert!(!is_open(day, open, close, Utc.with_ymd_and_hms(2024, 6, 30, 16, 0, 0).unwrap()));
+    }
+
+    #[test]
+    fn daytime_hours_only_count_on_their_day() {
+        /* open monday from 10:00 until 20:00 */
+        let (day, open, close) = (0, 10 * 60, 20 * 60);
+        assert!(is_open(day, open, close, Utc.with_ymd_and_hms(2024, 7, 1, 16, 0, 0).unwrap()));
+        assert!(!is_open(day, open, close, Utc.with_ymd_and_hms(2024, 7, 2, 16, 0, 0).unwrap()));
+        assert!(!is_open(day, open, close, Utc.with_ymd_and_hms(2024, 7, 1, 4, 30, 0).unwrap()));
+    }
+}
@@ -3,3 +3,5 @@ pub mod business;
 pub mod utils;
 pub mod upload_reports;
 pub mod onboarding;
+pub mod saved_routes;
+pub mod trips;
@@ -1,15 +1,12 @@
-use chrono::{DateTime, Utc};
 use rocket::http::Status;
-use rocket_db_pools::sqlx::{PgConnection, Row};
+use rocket_db_pools::sqlx::PgConnection;
 
 /*
  * Internal imports
  */
-use models::session::Session;
 use models::onboarding::UserInfo;
 use shared::response_models::ErrorResponse;
 
-use crate::utils;
 
 
 pub async fn insert_user(conn: &mut PgConnection, email: String, user_info: UserInfo) -> Result<String, ErrorResponse> {
@@ -0,0 +1,151 @@
+/*
+ * External imports
+ */
+use chrono::{DateTime, Utc};
+use rocket::http::Status;
+use rocket::serde::json::{from_str, Value};
+use rocket_db_pools::sqlx::{postgres::PgRow, PgConnection, Row};
+
+/*
+ * Internal imports
+ */
+use models::saved_route::NewSavedRoute;
+use shared::response_models::{ErrorResponse, SavedRoute};
+
+const SAVED_ROUTE_COLUMNS: &str =
+    "share_token, origin, destination, parameters, route, distance_meters, graph_version, created_at, expires_at, revoked";
+
+pub async fn create_saved_routes_table(conn: &mut PgConnection) -> Result<(), ErrorResponse> {
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS saved_routes (
+            id SERIAL PRIMARY KEY,
+            share_token VARCHAR(64) UNIQUE NOT NULL,
+            userid TEXT NOT NULL,
+            origin DOUBLE PRECISION[] NOT NULL,
+            destination DOUBLE PRECISION[] NOT NULL,
+            parameters TEXT NOT NULL,
+            route TEXT NOT NULL,
+            distance_meters DOUBLE PRECISION NOT NULL,
+            graph_version VARCHAR(64) NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            expires_at TIMESTAMPTZ NOT NULL,
+            revoked BOOLEAN NOT NULL DEFAULT FALSE
+        )"
+    )
+    .execute(conn)
+    .await
+    .map(|_| ())
+    .map_err(|e| ErrorResponse {
+        status: Status::InternalServerError,
+        message: format!("Failed to create saved_routes table: {}", e),
+    })
+}
+
+/* stored JSON columns are returned as JSON, not as strings */
+fn saved_route_from_row(row: PgRow) -> Result<SavedRoute, sqlx::Error> {
+    let parameters: String = row.try_get("parameters")?;
+    let route: String = row.try_get("route")?;
+    let created_at: DateTime<Utc> = row.try_get("created_at")?;
+    let expires_at: DateTime<Utc> = row.try_get("expires_at")?;
+
+    Ok(SavedRoute {
+        share_token: row.try_get("share_token")?,
+        origin: row.try_get("origin")?,
+        destination: row.try_get("destination")?,
+        parameters: from_str::<Value>(&parameters).map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+        route: from_str::<Value>(&route).map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+        distance_meters: row.try_get("distance_meters")?,
+        graph_version: row.try_get("graph_version")?,
+        created_at: created_at.to_rfc3339(),
+        expires_at: expires_at.to_rfc3339(),
+        revoked: row.try_get("revoked")?,
+    })
+}
+
+pub async fn insert_saved_route(conn: &mut PgConnection, saved_route: NewSavedRoute) -> Result<SavedRoute, ErrorResponse> {
+    sqlx::query(&format!(
+        "INSERT INTO saved_routes (share_token, userid, origin, destination, parameters, route, distance_meters, graph_version, expires_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+         RETURNING {}",
+        SAVED_ROUTE_COLUMNS
+    ))
+    .bind(saved_route.share_token)
+    .bind(saved_route.userid)
+    .bind(saved_route.origin)
+    .bind(saved_route.destination)
+    .bind(saved_route.parameters)
+    .bind(saved_route.route)
+    .bind(saved_route.distance_meters)
+    .bind(saved_route.graph_version)
+    .bind(saved_route.expires_at)
+    .fetch_one(conn)
+    .await
+    .and_then(saved_route_from_row)
+    .map_err(|e| ErrorResponse {
+        status: Status::InternalServerError,
+        message: format!("Failed to save route: {}", e),
+    })
+}
+
+/* every route the user saved, newest first - expired and revoked ones included so the owner can see them */
+pub async fn get_user_saved_routes(conn: &mut PgConnection, userid: String) -> Result<Vec<SavedRoute>, ErrorResponse> {
+    sqlx::query(&format!(
+        "SELECT {} FROM saved_routes WHERE userid = $1 ORDER BY created_at DESC",
+        SAVED_ROUTE_COLUMNS
+    ))
+    .bind(userid)
+    .fetch_all(conn)
+    .await
+    .and_then(|rows| rows.into_iter().map(saved_route_from_row).collect())
+    .map_err(|e| ErrorResponse {
+        status: Status::InternalServerError,
+        message: format!("Failed to get saved routes: {}", e),
+    })
+}
+
+/* route behind a share link - expired and revoked links are reported as not found */
+pub async fn get_shared_route(conn: &mut PgConnection, share_token: String) -> Result<SavedRoute, ErrorResponse> {
+    sqlx::query(&format!(
+        "SELECT {} FROM saved_routes WHERE share_token = $1 AND NOT revoked AND expires_at > NOW()",
+        SAVED_ROUTE_COLUMNS
+    ))
+    .bind(share_token)
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| ErrorResponse {
+        status: Status::InternalServerError,
+        message: format!("Failed to get shared route: {}", e),
+    })?
+    .ok_or(ErrorResponse {
+        status: Status::NotFound,
+        message: "this share link does not exist, has expired or was revoked".to_owned(),
+    })
+    .and_then(|row| {
+        saved_route_from_row(row).map_err(|e| ErrorResponse {
+            status: Status::InternalServerError,
+            message: format!("Failed to read shared route: {}", e),
+        })
+    })
+}
+
+/* turns the share link off - only the owner of the route can revoke it */
+pub async fn revoke_saved_route(conn: &mut PgConnection, share_token: String, userid: String) -> Result<(), ErrorResponse> {
+    let result = sqlx::query("UPDATE saved_routes SET revoked = TRUE WHERE share_token = $1 AND userid = $2")
+        .bind(share_token)
+        .bind(userid)
+        .execute(conn)
+        .await
+        .map_err(|e| ErrorResponse {
+            status: Status::InternalServerError,
+            message: format!("Failed to revoke saved route: {}", e),
+        })?;
+
+    if result.rows_affected() == 0 {
+        return Err(ErrorResponse {
+            status: Status::NotFound,
+            message: "you have no saved route with this share token".to_owned(),
+        });
+    }
+
+    Ok(())
+}
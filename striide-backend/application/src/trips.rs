@@ -0,0 +1,230 @@
+/*
+ * External imports
+ */
+use chrono::{DateTime, Utc};
+use rocket::http::Status;
+use rocket::serde::json::from_str;
+use rocket_db_pools::sqlx::{postgres::PgRow, PgConnection, Row};
+
+/*
+ * Internal imports
+ */
+use models::trip::{NewTrip, Trip, TripEvent, TripEventKind, TripStatus};
+use shared::response_models::ErrorResponse;
+
+const TRIP_COLUMNS: &str = "id, userid, region, graph_version, route, route_edges, route_progress, destination, status, off_route, \
+     stalled, distance_from_route, last_location, last_seen_at, moved_location, moved_at, started_at, ended_at";
+
+pub async fn create_trip_tables(conn: &mut PgConnection) -> Result<(), ErrorResponse> {
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS trips (
+            id SERIAL PRIMARY KEY,
+            userid TEXT NOT NULL,
+            region VARCHAR(64) NOT NULL,
+            graph_version VARCHAR(64) NOT NULL,
+            route TEXT NOT NULL,
+            route_edges TEXT NOT NULL DEFAULT '[]',
+            route_progress INTEGER NOT NULL DEFAULT 0,
+            destination DOUBLE PRECISION[] NOT NULL,
+            status VARCHAR(16) NOT NULL,
+            off_route BOOLEAN NOT NULL DEFAULT FALSE,
+            stalled BOOLEAN NOT NULL DEFAULT FALSE,
+            distance_from_route DOUBLE PRECISION,
+            last_location DOUBLE PRECISION[],
+            last_seen_at TIMESTAMPTZ,
+            moved_location DOUBLE PRECISION[] NOT NULL,
+            moved_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            ended_at TIMESTAMPTZ
+        )"
+    )
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| ErrorResponse {
+        status: Status::InternalServerError,
+        message: format!("Failed to create trips table: {}", e),
+    })?;
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS trip_events (
+            id SERIAL PRIMARY KEY,
+            trip_id INTEGER NOT NULL REFERENCES trips(id),
+            kind VARCHAR(32) NOT NULL,
+            location DOUBLE PRECISION[],
+            distance_from_route DOUBLE PRECISION,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )"
+    )
+    .execute(conn)
+    .await
+    .map(|_| ())
+    .map_err(|e| ErrorResponse {
+        status: Status::InternalServerError,
+        message: format!("Failed to create trip_events table: {}", e),
+    })
+}
+
+fn trip_from_row(row: PgRow) -> Result<Trip, sqlx::Error> {
+    let route: String = row.try_get("route")?;
+    let route_edges: String = row.try_get("route_edges")?;
+    let route_progress: i32 = row.try_get("route_progress")?;
+    let status: String = row.try_get("status")?;
+
+    Ok(Trip {
+        id: row.try_get("id")?,
+        userid: row.try_get("userid")?,
+        region: row.try_get("region")?,
+        graph_version: row.try_get("graph_version")?,
+        route: from_str::<Vec<(f64, f64)>>(&route).map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+        route_edges: from_str::<Vec<Option<usize>>>(&route_edges).map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+        route_progress: route_progress.max(0) as usize,
+        destination: row.try_get("destination")?,
+        status: TripStatus::from_string(status),
+        off_route: row.try_get("off_route")?,
+        stalled: row.try_get("stalled")?,
+        distance_from_route: row.try_get("distance_from_route")?,
+        last_location: row.try_get("last_location")?,
+        last_seen_at: row.try_get("last_seen_at")?,
+        moved_location: row.try_get("moved_location")?,
+        moved_at: row.try_get("moved_at")?,
+        started_at: row.try_get("started_at")?,
+        ended_at: row.try_get("ended_at")?,
+    })
+}
+
+/* new trips start active with the stall timer running from the origin */
+pub async fn insert_trip(conn: &mut PgConnection, trip: NewTrip) -> Result<Trip, ErrorResponse> {
+    sqlx::query(&format!(
+        "INSERT INTO trips (userid, region, graph_version, route, route_edges, destination, status, moved_location)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+         RETURNING {}",
+        TRIP_COLUMNS
+    ))
+    .bind(trip.userid)
+    .bind(trip.region)
+    .bind(trip.graph_version)
+    .bind(trip.route)
+    .bind(trip.route_edges)
+    .bind(trip.destination)
+    .bind(TripStatus::Active.to_string())
+    .bind(trip.origin)
+    .fetch_one(conn)
+    .await
+    .and_then(trip_from_row)
+    .map_err(|e| ErrorResponse {
+        status: Status::InternalServerError,
+        message: format!("Failed to start trip: {}", e),
+    })
+}
+
+/* trip of the user - trips of other users are reported as not found */
+pub async fn get_user_trip(conn: &mut PgConnection, id: i32, userid: String) -> Result<Trip, ErrorResponse> {
+    sqlx::query(&format!("SELECT {} FROM trips WHERE id = $1 AND userid = $2", TRIP_COLUMNS))
+        .bind(id)
+        .bind(userid)
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| ErrorResponse {
+            status: Status::InternalServerError,
+            message: format!("Failed to get trip: {}", e),
+        })?
+        .ok_or(ErrorResponse {
+            status: Status::NotFound,
+            message: "you have no trip with this id".to_owned(),
+        })
+        .and_then(|row| {
+            trip_from_row(row).map_err(|e| ErrorResponse {
+                status: Status::InternalServerError,
+                message: format!("Failed to read trip: {}", e),
+            })
+        })
+}
+
+/* active trips not flagged as stalled whose user last moved before the given time */
+pub async fn get_stalled_trips(conn: &mut PgConnection, moved_before: DateTime<Utc>) -> Result<Vec<Trip>, ErrorResponse> {
+    sqlx::query(&format!(
+        "SELECT {} FROM trips WHERE status = $1 AND NOT stalled AND moved_at < $2",
+        TRIP_COLUMNS
+    ))
+    .bind(TripStatus::Active.to_string())
+    .bind(moved_before)
+    .fetch_all(conn)
+    .await
+    .and_then(|rows| rows.into_iter().map(trip_from_row).collect())
+    .map_err(|e| ErrorResponse {
+        status: Status::InternalServerError,
+        message: format!("Failed to get stalled trips: {}", e),
+    })
+}
+
+/* writes the tracking state of the trip back */
+pub async fn update_trip(conn: &mut PgConnection, trip: &Trip) -> Result<(), ErrorResponse> {
+    sqlx::query(
+        "UPDATE trips SET status = $1, route_progress = $2, off_route = $3, stalled = $4, distance_from_route = $5,
+         last_location = $6, last_seen_at = $7, moved_location = $8, moved_at = $9, ended_at = $10
+         WHERE id = $11"
+    )
+    .bind(trip.status.to_string())
+    .bind(trip.route_progress as i32)
+    .bind(trip.off_route)
+    .bind(trip.stalled)
+    .bind(trip.distance_from_route)
+    .bind(trip.last_location.clone())
+    .bind(trip.last_seen_at)
+    .bind(trip.moved_location.clone())
+    .bind(trip.moved_at)
+    .bind(trip.ended_at)
+    .bind(trip.id)
+    .execute(conn)
+    .await
+    .map(|_| ())
+    .map_err(|e| ErrorResponse {
+        status: Status::InternalServerError,
+        message: format!("Failed to update trip: {}", e),
+    })
+}
+
+pub async fn insert_trip_events(conn: &mut PgConnection, trip_id: i32, events: &Vec<TripEvent>) -> Result<(), ErrorResponse> {
+    for event in events {
+        sqlx::query("INSERT INTO trip_events (trip_id, kind, location, distance_from_route, created_at) VALUES ($1, $2, $3, $4, $5)")
+            .bind(trip_id)
+            .bind(event.kind.to_string())
+            .bind(event.location.clone())
+            .bind(event.distance_from_route)
+            .bind(event.created_at)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| ErrorResponse {
+                status: Status::InternalServerError,
+                message: format!("Failed to save trip event: {}", e),
+            })?;
+    }
+
+    Ok(())
+}
+
+/* events of the trip, oldest first */
+pub async fn get_trip_events(conn: &mut PgConnection, trip_id: i32) -> Result<Vec<TripEvent>, ErrorResponse> {
+    sqlx::query("SELECT kind, location, distance_from_route, created_at FROM trip_events WHERE trip_id = $1 ORDER BY created_at, id")
+        .bind(trip_id)
+        .fetch_all(conn)
+        .await
+        .and_then(|rows| {
+            rows.into_iter()
+                .map(|row| {
+                    let kind: String = row.try_get("kind")?;
+                    let created_at: DateTime<Utc> = row.try_get("created_at")?;
+                    Ok(TripEvent {
+                        kind: TripEventKind::from_string(kind),
+                        location: row.try_get("location")?,
+                        distance_from_route: row.try_get("distance_from_route")?,
+                        created_at,
+                    })
+                })
+                .collect()
+        })
+        .map_err(|e| ErrorResponse {
+            status: Status::InternalServerError,
+            message: format!("Failed to get trip events: {}", e),
+        })
+}
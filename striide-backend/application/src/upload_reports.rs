@@ -3,8 +3,9 @@ use models::user::UserSession;
 use rocket::http::Status;
 use rocket::serde::{Deserialize, Serialize};
 // use rocket_db_pools::sqlx::PgConnection;
+use models::report::HazardReport;
 use shared::response_models::{BasicReport, ErrorResponse, MediaBody, ReportString, UserReport};
-use sqlx::{PgConnection, Row};
+use sqlx::{postgres::PgRow, PgConnection, Row};
 
 use shared::response_models::{InsertReportBody, ReportBody, ReportRequest};
 
@@ -29,11 +30,11 @@ pub async fn insert_report(
     }
 
     let json_body = InsertReportBody {
-        address: address,
+        address,
         userid: user_session.id,
         lng: location[0],
         lat: location[1],
-        duration: duration,
+        duration,
         media: media
             .into_iter()
             .map(|m| {
@@ -44,8 +45,8 @@ pub async fn insert_report(
                 })
             })
             .collect(),
-        description: description,
-        is_published: is_published,
+        description,
+        is_published,
     };
 
     let client = reqwest::Client::new();
@@ -84,7 +85,7 @@ pub async fn get_all_published_reports(
         .bind(false)
         .fetch_all(conn)
         .await
-        .and_then(|rows| {
+        .map(|rows| {
             let data_rows: Vec<ReportBody> = rows
                 .iter()
                 .map(|row| {
@@ -109,10 +110,10 @@ pub async fn get_all_published_reports(
             let user_reports: Vec<ReportBody> = data_rows
                 .iter()
                 .filter(|row| row.userid == user_session.id)
-                .map(|row| row.clone())
+                .cloned()
                 .collect();
 
-            Ok(user_reports)
+            user_reports
         })
         .map_err(|err| ErrorResponse {
             status: Status::InternalServerError,
@@ -146,11 +147,11 @@ pub async fn get_report_draft(
         .bind(reportID)
         .fetch_one(conn)
         .await
-        .and_then(|row| {
+        .map(|row| {
             let created_at: DateTime<Utc> = row.try_get("xata_createdat").unwrap();
             let created_at_str = created_at.to_rfc3339();
 
-            Ok(ReportBody {
+            ReportBody {
                 reportID: row.try_get("xata_id").unwrap(),
                 address: row.try_get("address").unwrap(),
                 lng: row.try_get("lng").unwrap(),
@@ -161,7 +162,7 @@ pub async fn get_report_draft(
                 description: row.try_get("description").unwrap(),
                 is_published: row.try_get("is_published").unwrap(),
                 created_at: created_at_str,
-            })
+            }
         })
         .map_err(|err| ErrorResponse {
             status: Status::InternalServerError,
@@ -193,7 +194,7 @@ pub async fn delete_draft(
         .bind(reportID)
         .execute(conn)
         .await
-        .and_then(|rows| Ok(rows.rows_affected()))
+        .map(|rows| rows.rows_affected())
         .map_err(|err| ErrorResponse {
             status: Status::InternalServerError,
             message: err.to_string(),
@@ -225,7 +226,7 @@ pub async fn set_draft_to_publish(
         .bind(reportID)
         .execute(conn)
         .await
-        .and_then(|rows| Ok(rows.rows_affected()))
+        .map(|rows| rows.rows_affected())
         .map_err(|err| ErrorResponse {
             status: Status::InternalServerError,
             message: err.to_string(),
@@ -259,32 +260,29 @@ pub async fn user_like_report(
 ) -> Result<(), ErrorResponse> {
     
     let sql_query = "INSERT INTO report_likes (report, userid, liked) VALUES ($1, $2, $3) ON CONFLICT (report, userid) DO UPDATE SET liked = $3";
-    if user_liked.is_none() {
-        sqlx::query("INSERT INTO report_likes (report, userid, liked) VALUES ($1, $2, NULL) ON CONFLICT (report, userid) DO UPDATE SET liked = NULL")
+    let Some(liked) = user_liked else {
+        return sqlx::query("INSERT INTO report_likes (report, userid, liked) VALUES ($1, $2, NULL) ON CONFLICT (report, userid) DO UPDATE SET liked = NULL")
         .bind(report_id.clone())
         .bind(user_id.clone())
         .execute(conn)
         .await
-        .and_then(|_| Ok(()))
+        .map(|_| ())
         .map_err(|err| ErrorResponse {
             status: Status::InternalServerError,
             message: err.to_string(),
-        })
-    } else {
-        let a = user_liked.unwrap();
-        println!("user_id: {user_id}, report_id: {report_id}, user_liked: {a}");
+        });
+    };
     sqlx::query(sql_query)
         .bind(report_id)
         .bind(user_id)
-        .bind(user_liked.unwrap())
+        .bind(liked)
         .execute(conn)
         .await
-        .and_then(|_| Ok(()))
+        .map(|_| ())
         .map_err(|err| ErrorResponse {
             status: Status::InternalServerError,
             message: err.to_string(),
         })
-    }
 }
 
 pub async fn get_report_likes(
@@ -340,19 +338,7 @@ pub async fn get_user_liked_report(
         .bind(user_id)
         .fetch_optional(conn)
         .await
-        .and_then(|row| {
-            if row.is_none() {
-                return Ok(None);
-            }
-            let res = row.unwrap().try_get("liked");
-            let liked = match res {
-                Ok(liked) => liked,
-                Err(_) => {
-                    return Ok(None)
-                }
-            };
-            Ok(Some(liked))
-        })
+        .map(|row| row.and_then(|row| row.try_get("liked").ok()))
         .map_err(|err| {
             println!("{:?}", err);
             ErrorResponse {
@@ -370,7 +356,7 @@ pub async fn get_report_ids(
     let report_ids = sqlx::query(sql_query)
         .fetch_all(conn)
         .await
-        .and_then(|rows| {
+        .map(|rows| {
             let data_rows: Vec<BasicReport> = rows
                 .iter()
                 .map(|row| {
@@ -382,7 +368,7 @@ pub async fn get_report_ids(
                 })
                 .collect();
 
-            Ok(data_rows)
+            data_rows
         })
         .map_err(|err| ErrorResponse {
             status: Status::InternalServerError,
@@ -402,6 +388,46 @@ pub async fn get_report_ids(
     Ok(result)
 }
 
+/* published reports created after the given time - used by the pathfinder to avoid hazards */
+pub async fn get_recent_published_reports(
+    conn: &mut PgConnection,
+    since: DateTime<Utc>,
+) -> Result<Vec<HazardReport>, ErrorResponse> {
+    let sql_query = "SELECT xata_id, lat, lng, duration, description, xata_createdat FROM reports WHERE is_published = true AND xata_createdat >= $1";
+
+    sqlx::query(sql_query)
+        .bind(since)
+        .fetch_all(conn)
+        .await
+        .map(|rows| {
+            rows.iter()
+                .filter_map(|row| match hazard_report(row) {
+                    Ok(report) => Some(report),
+                    Err(err) => {
+                        println!("skipping malformed report row: {}", err);
+                        None
+                    }
+                })
+                .collect()
+        })
+        .map_err(|err| ErrorResponse {
+            status: Status::InternalServerError,
+            message: err.to_string(),
+        })
+}
+
+/* a report with a missing location or duration cannot be placed on the graph, so it fails instead of panicking */
+fn hazard_report(row: &PgRow) -> Result<HazardReport, sqlx::Error> {
+    Ok(HazardReport {
+        id: row.try_get("xata_id")?,
+        lat: row.try_get("lat")?,
+        lng: row.try_get("lng")?,
+        duration: row.try_get("duration")?,
+        description: row.try_get("description")?,
+        created_at: row.try_get("xata_createdat")?,
+    })
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(crate = "rocket::serde")]
 pub struct ReportRequestBody {
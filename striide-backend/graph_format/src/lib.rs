@@ -0,0 +1,463 @@
+/*
+ * External Imports
+ */
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+
+/*
+ * constants
+ */
+/* first bytes of every binary graph file */
+pub const GRAPH_MAGIC: &[u8; 8] = b"STRIIDEG";
+/* bumped whenever the layout below changes - gen_graph writes it and the api refuses other versions */
+pub const GRAPH_FORMAT_VERSION: u32 = 3;
+/* first bytes and version of the contraction hierarchy written by gen_hierarchy */
+pub const HIERARCHY_MAGIC: &[u8; 8] = b"STRIIDEH";
+pub const HIERARCHY_FORMAT_VERSION: u32 = 1;
+
+/*
+ *  SourceHash : dataset the graph was generated from
+ *  file       : file name passed to gen_graph
+ *  sha256     : hex encoded sha256 of the file contents
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SourceHash {
+    pub file: String,
+    pub sha256: String,
+}
+
+/*
+ *  GraphHeader    : written right after GRAPH_MAGIC so a mismatch is reported before the body is read
+ *  built_at       : unix timestamp (seconds) of the gen_graph run
+ *  bounding_box   : [min_lng, min_lat, max_lng, max_lat] of the nodes
+ *  node_count     : number of nodes in the body
+ *  edge_count     : number of edges in the body
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GraphHeader {
+    pub format_version: u32,
+    pub built_at: u64,
+    pub source_hashes: Vec<SourceHash>,
+    pub bounding_box: [f64; 4],
+    pub node_count: u64,
+    pub edge_count: u64,
+}
+
+/*
+ *  BinaryEdge : edge of the binary graph - source and target are positions in GraphBody::nodes
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BinaryEdge {
+    pub source: u32,
+    pub target: u32,
+    pub length: f64,
+    pub safety: f64,
+    pub light_density: f64,
+    pub building_density: f64,
+    pub street_name: Option<String>,
+    pub light_count: u32,
+    pub building_count: u32,
+    pub sidewalk_width: Option<f64>,
+    pub curb_ramps: Option<bool>,
+    pub stairs: bool,
+    pub surface: Option<String>,
+}
+
+/*
+ *  GraphBody    : everything after the header
+ *  nodes        : [lng, lat] of every node, the node id is its position
+ *  service_area : closed ring of the polygon the graph covers
+ *  elevations   : elevation in meters of every node, None where gen_graph had no elevation model data
+ */
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GraphBody {
+    pub nodes: Vec<[f64; 2]>,
+    pub edges: Vec<BinaryEdge>,
+    pub service_area: Vec<[f64; 2]>,
+    pub elevations: Vec<Option<f64>>,
+}
+
+/*
+ *  HierarchyHeader : ties a contraction hierarchy to the graph it was built from
+ *  graph_built_at  : built_at of the graph header
+ *  shortcut_count  : arcs that do not exist in the graph
+ */
+#[derive(Serialize, Deserialize, Debug)]
+pub struct HierarchyHeader {
+    pub format_version: u32,
+    pub graph_built_at: u64,
+    pub node_count: u64,
+    pub shortcut_count: u64,
+}
+
+/*
+ *  HierarchyArc : arc from a node to a node contracted after it
+ *  weight       : meters between the two nodes
+ *  middle       : node the shortcut was created for, None for an edge of the graph
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct HierarchyArc {
+    pub target: u32,
+    pub weight: f64,
+    pub middle: Option<u32>,
+}
+
+/*
+ *  HierarchyBody : upward arcs of every node
+ *  ranks         : contraction order of every node
+ *  first_arc     : arcs of node n are arcs[first_arc[n]..first_arc[n + 1]]
+ */
+#[derive(Serialize, Deserialize, Debug)]
+pub struct HierarchyBody {
+    pub ranks: Vec<u32>,
+    pub first_arc: Vec<u32>,
+    pub arcs: Vec<HierarchyArc>,
+}
+
+/* true when the file starts with GRAPH_MAGIC - anything else is treated as the gzipped JSON export */
+pub fn is_binary_graph(graph_file_path: &str) -> Result<bool, String> {
+    let mut file = File::open(graph_file_path)
+        .map_err(|err| format!("Error opening graph file {}: {}", graph_file_path, err))?;
+
+    let mut magic = [0u8; 8];
+    match file.read_exact(&mut magic) {
+        Ok(()) => Ok(&magic == GRAPH_MAGIC),
+        Err(_) => Ok(false),
+    }
+}
+
+/*
+ *  read_binary_graph: reads the header and body of a binary graph file
+ *  purpose          : the version is checked before the body is decoded so an old server pointed at a
+ *                     newer graph (or the other way around) fails with a clear message
+ */
+pub fn read_binary_graph(graph_file_path: &str) -> Result<(GraphHeader, GraphBody), String> {
+    let file = File::open(graph_file_path)
+        .map_err(|err| format!("Error opening graph file {}: {}", graph_file_path, err))?;
+    let mut reader = BufReader::new(file);
+
+    let mut magic = [0u8; 8];
+    reader
+        .read_exact(&mut magic)
+        .map_err(|err| format!("could not read graph file header: {}", err))?;
+    if &magic != GRAPH_MAGIC {
+        return Err(format!("{} is not a binary navigation graph", graph_file_path));
+    }
+
+    let header: GraphHeader = bincode::deserialize_from(&mut reader)
+        .map_err(|err| format!("could not decode graph file header: {}", err))?;
+    if header.format_version != GRAPH_FORMAT_VERSION {
+        return Err(format!(
+            "graph file has format version {} but this server reads version {} - regenerate it with gen_graph",
+            header.format_version, GRAPH_FORMAT_VERSION
+        ));
+    }
+
+    let body: GraphBody = bincode::deserialize_from(&mut reader)
+        .map_err(|err| format!("could not decode graph file body: {}", err))?;
+    if body.nodes.len() as u64 != header.node_count || body.edges.len() as u64 != header.edge_count {
+        return Err(format!(
+            "graph header announces {} nodes and {} edges but the file contains {} nodes and {} edges",
+            header.node_count,
+            header.edge_count,
+            body.nodes.len(),
+            body.edges.len()
+        ));
+    }
+    validate_graph_body(&body)?;
+
+    Ok((header, body))
+}
+
+/* a body that decodes can still be corrupt - anything the api would index or search with is checked */
+fn validate_graph_body(body: &GraphBody) -> Result<(), String> {
+    let node_count = body.nodes.len();
+    if body.elevations.len() != node_count {
+        return Err(format!("graph file has {} elevations for {} nodes", body.elevations.len(), node_count));
+    }
+    if let Some(position) = body.nodes.iter().position(|node| !node[0].is_finite() || !node[1].is_finite()) {
+        return Err(format!("graph file is corrupted - node {} has no valid coordinates", position));
+    }
+
+    for (position, edge) in body.edges.iter().enumerate() {
+        if edge.source as usize >= node_count || edge.target as usize >= node_count {
+            return Err(format!(
+                "graph file is corrupted - edge {} references node {} but the graph has {} nodes",
+                position,
+                edge.source.max(edge.target),
+                node_count
+            ));
+        }
+        if !edge.length.is_finite() || edge.length < 0.0 || !edge.safety.is_finite() {
+            return Err(format!("graph file is corrupted - edge {} has an invalid length or safety", position));
+        }
+    }
+
+    Ok(())
+}
+
+/*
+ *  read_hierarchy: reads the contraction hierarchy gen_hierarchy built for the graph with this header
+ *  purpose       : a hierarchy of another graph would return wrong routes, so it is rejected
+ */
+pub fn read_hierarchy(hierarchy_file_path: &str, graph_header: &GraphHeader) -> Result<HierarchyBody, String> {
+    let file = File::open(hierarchy_file_path)
+        .map_err(|err| format!("Error opening hierarchy file {}: {}", hierarchy_file_path, err))?;
+    let mut reader = BufReader::new(file);
+
+    let mut magic = [0u8; 8];
+    reader
+        .read_exact(&mut magic)
+        .map_err(|err| format!("could not read hierarchy file header: {}", err))?;
+    if &magic != HIERARCHY_MAGIC {
+        return Err(format!("{} is not a contraction hierarchy", hierarchy_file_path));
+    }
+
+    let header: HierarchyHeader = bincode::deserialize_from(&mut reader)
+        .map_err(|err| format!("could not decode hierarchy file header: {}", err))?;
+    if header.format_version != HIERARCHY_FORMAT_VERSION {
+        return Err(format!(
+            "hierarchy file has format version {} but this server reads version {} - regenerate it with gen_hierarchy",
+            header.format_version, HIERARCHY_FORMAT_VERSION
+        ));
+    }
+    if header.graph_built_at != graph_header.built_at || header.node_count != graph_header.node_count {
+        return Err("hierarchy file was built for another graph - regenerate it with gen_hierarchy".to_owned());
+    }
+
+    let body: HierarchyBody = bincode::deserialize_from(&mut reader)
+        .map_err(|err| format!("could not decode hierarchy file body: {}", err))?;
+    validate_hierarchy_body(&body, graph_header.node_count as usize)
+        .map_err(|err| format!("hierarchy file is corrupted ({}) - regenerate it with gen_hierarchy", err))?;
+
+    Ok(body)
+}
+
+/*
+ *  validate_hierarchy_body: checks everything the hierarchy query relies on
+ *  purpose                : arcs have to point to nodes of higher rank and shortcuts have to be created for a
+ *                           node of lower rank than both of their ends, otherwise the search or the unpacking
+ *                           of shortcuts would never finish
+ */
+fn validate_hierarchy_body(body: &HierarchyBody, node_count: usize) -> Result<(), String> {
+    if body.ranks.len() != node_count || body.first_arc.len() != node_count + 1 {
+        return Err(format!("expected {} ranks and {} arc offsets", node_count, node_count + 1));
+    }
+
+    let mut ranked = vec![false; node_count];
+    for rank in body.ranks.iter() {
+        match ranked.get_mut(*rank as usize) {
+            Some(seen) if !*seen => *seen = true,
+            _ => return Err(format!("rank {} is out of range or given to more than one node", rank)),
+        }
+    }
+
+    if body.first_arc[0] != 0 || body.first_arc[node_count] as usize != body.arcs.len() {
+        return Err("arc offsets do not cover the arcs".to_owned());
+    }
+    if body.first_arc.windows(2).any(|pair| pair[0] > pair[1]) {
+        return Err("arc offsets are not increasing".to_owned());
+    }
+
+    for node in 0..node_count {
+        let rank = body.ranks[node];
+        for arc in body.arcs[body.first_arc[node] as usize..body.first_arc[node + 1] as usize].iter() {
+            let target_rank = *body.ranks.get(arc.target as usize).ok_or(format!("arc of node {} leaves the graph", node))?;
+            if target_rank <= rank {
+                return Err(format!("arc of node {} does not lead to a node of higher rank", node));
+            }
+            if !arc.weight.is_finite() || arc.weight < 0.0 {
+                return Err(format!("arc of node {} has an invalid weight", node));
+            }
+            if let Some(middle) = arc.middle {
+                let middle_rank = *body.ranks.get(middle as usize).ok_or(format!("shortcut of node {} leaves the graph", node))?;
+                if middle_rank >= rank {
+                    return Err(format!("shortcut of node {} skips a node of higher rank", node));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn write_graph(graph_file_path: &str, header: &GraphHeader, body: &GraphBody) -> Result<(), String> {
+    write_file(graph_file_path, GRAPH_MAGIC, header, body)
+}
+
+pub fn write_hierarchy(hierarchy_file_path: &str, header: &HierarchyHeader, body: &HierarchyBody) -> Result<(), String> {
+    write_file(hierarchy_file_path, HIERARCHY_MAGIC, header, body)
+}
+
+/* file layout: magic bytes, bincode encoded header, bincode encoded body */
+fn write_file<H: Serialize, B: Serialize>(path: &str, magic: &[u8; 8], header: &H, body: &B) -> Result<(), String> {
+    let file = File::create(path).map_err(|err| format!("Error creating {}: {}", path, err))?;
+    let mut writer = BufWriter::new(file);
+
+    writer.write_all(magic).map_err(|err| format!("could not write {}: {}", path, err))?;
+    bincode::serialize_into(&mut writer, header).map_err(|err| format!("could not encode the header of {}: {}", path, err))?;
+    bincode::serialize_into(&mut writer, body).map_err(|err| format!("could not encode the body of {}: {}", path, err))?;
+    writer.flush().map_err(|err| format!("could not write {}: {}", path, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /* file in the temp directory, unique per test and test run */
+    fn temp_path(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("graph_format_{}_{}", std::process::id(), name))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    fn edge(source: u32, target: u32, length: f64) -> BinaryEdge {
+        BinaryEdge {
+            source,
+            target,
+            length,
+            safety: length * 0.5,
+            light_density: 2.0,
+            building_density: 1.5,
+            street_name: Some("Bloor Street West".to_owned()),
+            light_count: 3,
+            building_count: 2,
+            sidewalk_width: Some(1.8),
+            curb_ramps: None,
+            stairs: false,
+            surface: Some("asphalt".to_owned()),
+        }
+    }
+
+    fn graph() -> (GraphHeader, GraphBody) {
+        let body = GraphBody {
+            nodes: vec![[-79.40, 43.66], [-79.39, 43.66], [-79.39, 43.67]],
+            edges: vec![edge(0, 1, 800.0), edge(1, 2, 1100.0)],
+            service_area: vec![[-79.41, 43.65], [-79.38, 43.65], [-79.38, 43.68], [-79.41, 43.65]],
+            elevations: vec![Some(120.5), None, Some(131.0)],
+        };
+        let header = GraphHeader {
+            format_version: GRAPH_FORMAT_VERSION,
+            built_at: 1_700_000_000,
+            source_hashes: vec![SourceHash { file: "sidewalks.geojson".to_owned(), sha256: "ab".repeat(32) }],
+            bounding_box: [-79.40, 43.66, -79.39, 43.67],
+            node_count: 3,
+            edge_count: 2,
+        };
+        (header, body)
+    }
+
+    /* node 1 is contracted first, with a shortcut between 0 and 2 through it */
+    fn hierarchy(graph_header: &GraphHeader) -> (HierarchyHeader, HierarchyBody) {
+        let arc = |target: u32, weight: f64, middle: Option<u32>| HierarchyArc { target, weight, middle };
+        let header = HierarchyHeader {
+            format_version: HIERARCHY_FORMAT_VERSION,
+            graph_built_at: graph_header.built_at,
+            node_count: graph_header.node_count,
+            shortcut_count: 1,
+        };
+        let body = HierarchyBody {
+            ranks: vec![1, 0, 2],
+            first_arc: vec![0, 1, 3, 3],
+            arcs: vec![arc(2, 1900.0, Some(1)), arc(0, 800.0, None), arc(2, 1100.0, None)],
+        };
+        (header, body)
+    }
+
+    #[test]
+    fn graph_round_trip() {
+        let path = temp_path("round_trip.graph");
+        let (header, body) = graph();
+        write_graph(&path, &header, &body).unwrap();
+
+        assert!(is_binary_graph(&path).unwrap());
+        let (read_header, read_body) = read_binary_graph(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(read_header.built_at, header.built_at);
+        assert_eq!(read_header.source_hashes[0].sha256, header.source_hashes[0].sha256);
+        assert_eq!(read_header.bounding_box, header.bounding_box);
+        assert_eq!(read_body.nodes, body.nodes);
+        assert_eq!(read_body.service_area, body.service_area);
+        assert_eq!(read_body.elevations, body.elevations);
+        assert_eq!(format!("{:?}", read_body.edges), format!("{:?}", body.edges));
+    }
+
+    #[test]
+    fn rejects_other_format_versions() {
+        let path = temp_path("old_version.graph");
+        let (mut header, body) = graph();
+        header.format_version = GRAPH_FORMAT_VERSION - 1;
+        write_graph(&path, &header, &body).unwrap();
+
+        let err = read_binary_graph(&path).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert!(err.contains("format version"), "{}", err);
+    }
+
+    #[test]
+    fn rejects_edges_outside_the_graph() {
+        let path = temp_path("dangling_edge.graph");
+        let (header, mut body) = graph();
+        body.edges[1].target = 7;
+        write_graph(&path, &header, &body).unwrap();
+
+        let err = read_binary_graph(&path).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert!(err.contains("edge 1 references node 7"), "{}", err);
+    }
+
+    #[test]
+    fn is_not_binary_without_the_magic_bytes() {
+        let path = temp_path("export.json");
+        std::fs::write(&path, b"{\"nodes\": []}").unwrap();
+        let is_binary = is_binary_graph(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(!is_binary);
+    }
+
+    #[test]
+    fn hierarchy_round_trip() {
+        let path = temp_path("round_trip.hierarchy");
+        let (graph_header, _) = graph();
+        let (header, body) = hierarchy(&graph_header);
+        write_hierarchy(&path, &header, &body).unwrap();
+
+        let read_body = read_hierarchy(&path, &graph_header).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(read_body.ranks, body.ranks);
+        assert_eq!(read_body.first_arc, body.first_arc);
+        assert_eq!(format!("{:?}", read_body.arcs), format!("{:?}", body.arcs));
+    }
+
+    #[test]
+    fn rejects_hierarchies_of_other_graphs() {
+        let path = temp_path("other_graph.hierarchy");
+        let (mut graph_header, _) = graph();
+        let (header, body) = hierarchy(&graph_header);
+        write_hierarchy(&path, &header, &body).unwrap();
+
+        graph_header.built_at += 1;
+        let err = read_hierarchy(&path, &graph_header).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert!(err.contains("another graph"), "{}", err);
+    }
+
+    #[test]
+    fn rejects_corrupted_hierarchies() {
+        let (graph_header, _) = graph();
+        let (_, mut body) = hierarchy(&graph_header);
+        body.first_arc = vec![0, 3, 1, 3];
+        assert!(validate_hierarchy_body(&body, 3).is_err());
+
+        let (_, mut body) = hierarchy(&graph_header);
+        body.arcs[2].target = 1;
+        assert!(validate_hierarchy_body(&body, 3).is_err());
+
+        let (_, mut body) = hierarchy(&graph_header);
+        body.ranks = vec![0, 0, 2];
+        assert!(validate_hierarchy_body(&body, 3).is_err());
+    }
+}
@@ -0,0 +1,13 @@
+/*
+ *  OpenBusiness      : business open at the time it was looked up
+ *  closes_in_minutes : minutes until it closes
+ */
+#[derive(Debug, Clone)]
+pub struct OpenBusiness {
+    pub id: String,
+    pub name: String,
+    pub address: Option<String>,
+    pub longitude: f64,
+    pub latitude: f64,
+    pub closes_in_minutes: i32,
+}
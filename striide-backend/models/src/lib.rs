@@ -1,4 +1,7 @@
 pub mod session;
 pub mod user;
 pub mod onboarding;
-
+pub mod report;
+pub mod saved_route;
+pub mod trip;
+pub mod business;
@@ -1,12 +1,9 @@
+// the enum and variant names are what the onboarding form sends, serde matches them as written
+#![allow(non_camel_case_types)]
+
 use chrono::{DateTime, Utc};
-use rocket::http::Status;
-use rocket::outcome::try_outcome;
-use rocket::request::{FromRequest, Outcome, Request};
 use rocket::serde::{Deserialize, Serialize};
-use rocket_db_pools::sqlx::Row;
 use std::fmt;
-use crate::session::Session;
-use infrastructure::database::Db;
 
 //create a enum for gender
 #[derive(Debug, Clone, Serialize,Deserialize)]
@@ -106,6 +103,7 @@ pub struct UserInfo {
 
 //Implement the UserInfo struct
 impl UserInfo {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(city: String, state: String, occupation: String, gender: Gender, birthdate: DateTime<Utc>, phone_number: String, transport_modes: [Transport_mode; 4], commute_frequency: Commmute_frequency, travel_time: Travel_time, feed_type: Feed_type) -> UserInfo {
         UserInfo {
             city,
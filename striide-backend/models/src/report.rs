@@ -0,0 +1,18 @@
+/*
+ * External Imports
+ */
+use chrono::{DateTime, Utc};
+
+/*
+ *  HazardReport : published user report the pathfinder routes around
+ *  duration     : how long the reporter expects the hazard to last (Short, Medium, Long)
+ */
+#[derive(Debug, Clone)]
+pub struct HazardReport {
+    pub id: String,
+    pub lat: f64,
+    pub lng: f64,
+    pub duration: String,
+    pub description: String,
+    pub created_at: DateTime<Utc>,
+}
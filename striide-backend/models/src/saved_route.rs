@@ -0,0 +1,25 @@
+/*
+ * External Imports
+ */
+use chrono::{DateTime, Utc};
+
+/*
+ *  NewSavedRoute : computed route about to be saved for its owner
+ *  share_token   : unguessable token of the share link
+ *  parameters    : JSON of the route query the route was computed for
+ *  route         : JSON of the computed route
+ *  graph_version : version of the navigation graph the route was computed on
+ *  expires_at    : the share link stops working after this time
+ */
+#[derive(Debug, Clone)]
+pub struct NewSavedRoute {
+    pub userid: String,
+    pub share_token: String,
+    pub origin: Vec<f64>,
+    pub destination: Vec<f64>,
+    pub parameters: String,
+    pub route: String,
+    pub distance_meters: f64,
+    pub graph_version: String,
+    pub expires_at: DateTime<Utc>,
+}
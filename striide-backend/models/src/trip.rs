@@ -0,0 +1,128 @@
+/*
+ * External Imports
+ */
+use chrono::{DateTime, Utc};
+use rocket::serde::Deserialize;
+use std::fmt;
+
+/*
+ *  TripStatus : Active until the user reaches the destination or ends the trip
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TripStatus {
+    Active,
+    Arrived,
+    Ended,
+}
+/* required implementation to call the to_string() method on self */
+impl fmt::Display for TripStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+impl TripStatus {
+    pub fn from_string(status: String) -> TripStatus {
+        match status.as_str() {
+            "Active" => TripStatus::Active,
+            "Arrived" => TripStatus::Arrived,
+            _ => TripStatus::Ended,
+        }
+    }
+}
+
+/*
+ *  TripEventKind : changes of a trip worth telling the people watching it about
+ *  OffRoute      : the user moved further from the route than the off route threshold
+ *  Stalled       : the user has not moved for the stall duration
+ *  Moving        : the user moved again after stalling
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TripEventKind {
+    Started,
+    OffRoute,
+    BackOnRoute,
+    Stalled,
+    Moving,
+    Arrived,
+    Ended,
+}
+impl fmt::Display for TripEventKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+impl TripEventKind {
+    pub fn from_string(kind: String) -> TripEventKind {
+        match kind.as_str() {
+            "Started" => TripEventKind::Started,
+            "OffRoute" => TripEventKind::OffRoute,
+            "BackOnRoute" => TripEventKind::BackOnRoute,
+            "Stalled" => TripEventKind::Stalled,
+            "Moving" => TripEventKind::Moving,
+            "Arrived" => TripEventKind::Arrived,
+            _ => TripEventKind::Ended,
+        }
+    }
+}
+
+/*
+ *  Trip                : walk of a user along a computed route
+ *  region              : key of the region the route was computed in
+ *  route               : [lng, lat] coordinates of the route
+ *  route_edges         : graph edge every route segment was matched to on the graph the route was computed on
+ *  route_progress      : route segment the user last walked along, only the route from there on is still to walk
+ *  distance_from_route : meters between the last reported location and the rest of the route
+ *  moved_location      : location the user was at when they last moved, the stall timer runs from moved_at
+ */
+#[derive(Debug, Clone)]
+pub struct Trip {
+    pub id: i32,
+    pub userid: String,
+    pub region: String,
+    pub graph_version: String,
+    pub route: Vec<(f64, f64)>,
+    pub route_edges: Vec<Option<usize>>,
+    pub route_progress: usize,
+    pub destination: Vec<f64>,
+    pub status: TripStatus,
+    pub off_route: bool,
+    pub stalled: bool,
+    pub distance_from_route: Option<f64>,
+    pub last_location: Option<Vec<f64>>,
+    pub last_seen_at: Option<DateTime<Utc>>,
+    pub moved_location: Vec<f64>,
+    pub moved_at: DateTime<Utc>,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
+}
+
+/*
+ *  NewTrip     : trip about to be started
+ *  route       : JSON of the [lng, lat] coordinates of the route
+ *  route_edges : JSON of the graph edge every route segment was matched to
+ */
+#[derive(Debug, Clone)]
+pub struct NewTrip {
+    pub userid: String,
+    pub region: String,
+    pub graph_version: String,
+    pub route: String,
+    pub route_edges: String,
+    pub origin: Vec<f64>,
+    pub destination: Vec<f64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TripEvent {
+    pub kind: TripEventKind,
+    pub location: Option<Vec<f64>>,
+    pub distance_from_route: Option<f64>,
+    pub created_at: DateTime<Utc>,
+}
+
+/* location report of a user on a trip */
+#[derive(Debug, Clone, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct TripLocation {
+    pub location: Vec<f64>,
+}
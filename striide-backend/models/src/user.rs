@@ -123,7 +123,7 @@ impl<'r> FromRequest<'r> for UserSession {
                             .await
                             .and_then(|row| {
                                 let expires: DateTime<Utc> = row.try_get("access_expires")?;
-                                if !(expires > Utc::now()) { return Err(sqlx::Error::RowNotFound) }
+                                if expires <= Utc::now() { return Err(sqlx::Error::RowNotFound) }
 
                                 let id: String = row.try_get("userid")?;
                                 let email = row.try_get("email")?;
@@ -154,11 +154,87 @@ impl<'r> FromRequest<'r> for UserSession {
     }
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde", rename_all = "lowercase")]
+pub enum RouteProfile {
+    Fastest,
+    Balanced,
+    Safest,
+}
+
+/* a route preference is either a 0-1 slider value or one of the named profiles */
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde", untagged)]
+pub enum RoutePreference {
+    Slider(f64),
+    Profile(RouteProfile),
+}
+
+impl RoutePreference {
+    /* 0.0 only cares about distance, 1.0 cares the most about safety */
+    pub fn safety_level(&self) -> f64 {
+        match self {
+            RoutePreference::Slider(value) => *value,
+            RoutePreference::Profile(RouteProfile::Fastest) => 0.0,
+            RoutePreference::Profile(RouteProfile::Balanced) => 0.5,
+            RoutePreference::Profile(RouteProfile::Safest) => 1.0,
+        }
+    }
+}
+
+/* mobility needs a route has to respect - stairs and narrow or rough sidewalks are avoided */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde", rename_all = "lowercase")]
+pub enum AccessibilityProfile {
+    Wheelchair,
+    Stroller,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(crate = "rocket::serde")]
 pub struct UserRouteQuery {
     pub origin: Vec<f64>,
     pub destination: Vec<f64>,
+    pub preference: Option<RoutePreference>,
+    pub alternatives: Option<usize>,
+    pub departure_time: Option<DateTime<Utc>>,
+    pub waypoints: Option<Vec<Vec<f64>>>,
+    pub optimize_waypoints: Option<bool>,
+    pub compare: Option<bool>,
+    pub accessibility: Option<AccessibilityProfile>,
+}
+
+/*
+ *  RouteMatrixQuery : walking routes from every origin to every destination
+ *  geometries       : also return the [lng, lat] coordinates of every route
+ */
+#[derive(Debug, Clone, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct RouteMatrixQuery {
+    pub origins: Vec<Vec<f64>>,
+    pub destinations: Vec<Vec<f64>>,
+    pub preference: Option<RoutePreference>,
+    pub departure_time: Option<DateTime<Utc>>,
+    pub geometries: Option<bool>,
+}
+
+/*
+ *  SafePlaceQuery : open businesses closest on foot to the user
+ *  count          : number of places to return
+ */
+#[derive(Debug, Clone, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct SafePlaceQuery {
+    pub origin: Vec<f64>,
+    pub count: Option<usize>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct IsochroneQuery {
+    pub origin: Vec<f64>,
+    pub minutes: Vec<f64>,
+    pub well_lit_only: Option<bool>,
 }
 
 
@@ -22,6 +22,11 @@ pub enum ResponseBody {
     BasicReportInfo(BasicReportInfo),
     ReportString(ReportString),
     ReportLikes(UserReportLikes),
+    SavedRoute(SavedRoute),
+    SavedRoutes(SavedRoutes),
+    TripResponse(TripResponse),
+    RouteMatrix(RouteMatrix),
+    SafePlaces(SafePlaces),
 
 }
 /* Custom serialize implemented to remove struct name from response body */
@@ -47,6 +52,11 @@ impl Serialize for ResponseBody {
             ResponseBody::BasicReportInfo(path) => path.serialize(serializer),
             ResponseBody::ReportString(path) => path.serialize(serializer),
             ResponseBody::ReportLikes(path) => path.serialize(serializer),
+            ResponseBody::SavedRoute(route) => route.serialize(serializer),
+            ResponseBody::SavedRoutes(routes) => routes.serialize(serializer),
+            ResponseBody::TripResponse(trip) => trip.serialize(serializer),
+            ResponseBody::RouteMatrix(matrix) => matrix.serialize(serializer),
+            ResponseBody::SafePlaces(places) => places.serialize(serializer),
         }
     }
 }
@@ -145,11 +155,189 @@ impl<'r> Responder<'r, 'static> for ErrorResponse {
     }
 }
 
+/*
+ *  RouteError : error returned by the routing endpoints
+ *  code       : machine readable reason the frontend can switch on (e.g. outside_service_area)
+ *  field      : request field that caused the error, if any (e.g. origin)
+ */
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct RouteError {
+    pub status: rocket::http::Status,
+    pub code: String,
+    pub message: String,
+    pub field: Option<String>,
+}
+#[rocket::async_trait]
+impl<'r> Responder<'r, 'static> for RouteError {
+    fn respond_to(self, _: &'r Request<'_>) -> response::Result<'static> {
+        let res = serde_json::to_string(&self).unwrap();
+        response::Response::build()
+            .header(ContentType::JSON)
+            .status(self.status)
+            .sized_body(res.len(), Cursor::new(res))
+            .ok()
+    }
+}
+
+/* plain errors get their status reason as code, e.g. bad_request */
+impl From<ErrorResponse> for RouteError {
+    fn from(error: ErrorResponse) -> Self {
+        RouteError {
+            code: error.status.reason().unwrap_or("error").to_lowercase().replace(' ', "_"),
+            status: error.status,
+            message: error.message,
+            field: None,
+        }
+    }
+}
+
+/*
+ *  ComputedPath      : route between the stops of a query with its alternatives
+ *  ascent_meters     : total climb along the route, 0 when the graph has no elevations
+ *  elevation_profile : [meters from the start, elevation in meters] along the route, empty without elevations
+ */
 #[derive(Serialize, Debug)]
 #[serde(crate = "rocket::serde")]
 pub struct ComputedPath {
     pub striide_route: Vec<(f64, f64)>, 
-    pub mapbox_route: Vec<(f64, f64)>
+    pub mapbox_route: Vec<(f64, f64)>, 
+    pub distance_meters: f64, 
+    pub eta_seconds: f64, 
+    pub lit_percentage: f64, 
+    pub safety_score: f64, 
+    pub origin_snap_meters: f64, 
+    pub destination_snap_meters: f64, 
+    pub instructions: Vec<Maneuver>, 
+    pub hazards: Vec<RouteHazard>, 
+    pub legs: Vec<RouteLeg>, 
+    pub waypoint_order: Vec<usize>, 
+    pub segments: SegmentCollection, 
+    pub alternatives: Vec<RouteAlternative>, 
+    pub comparison: Option<RouteComparison>, 
+    pub ascent_meters: f64, 
+    pub descent_meters: f64, 
+    pub elevation_profile: Vec<(f64, f64)>, 
+}
+
+/*
+ *  RouteComparison           : striide route compared with the baseline route of an external router
+ *  extra_distance_meters     : meters the striide route is longer than the baseline (negative when shorter)
+ *  baseline_lit_percentage   : percentage of the baseline walked along well lit sidewalks
+ *  lit_percentage_difference : percentage points more of the striide route that is well lit
+ */
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct RouteComparison {
+    pub provider: String, 
+    pub baseline_distance_meters: f64, 
+    pub extra_distance_meters: f64, 
+    pub baseline_lit_percentage: f64, 
+    pub lit_percentage_difference: f64, 
+}
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct RouteAlternative {
+    pub route: Vec<(f64, f64)>, 
+    pub distance_meters: f64, 
+    pub eta_seconds: f64, 
+    pub lit_percentage: f64, 
+    pub safety_score: f64, 
+    pub instructions: Vec<Maneuver>, 
+    pub hazards: Vec<RouteHazard>, 
+    pub segments: SegmentCollection, 
+    pub ascent_meters: f64, 
+    pub descent_meters: f64, 
+    pub elevation_profile: Vec<(f64, f64)>, 
+}
+
+/* GeoJSON FeatureCollection with one LineString feature per walked sidewalk segment */
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct SegmentCollection {
+    #[serde(rename = "type")]
+    pub collection_type: String, 
+    pub features: Vec<SegmentFeature>, 
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct SegmentFeature {
+    #[serde(rename = "type")]
+    pub feature_type: String, 
+    pub geometry: LineGeometry, 
+    pub properties: SegmentProperties, 
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct LineGeometry {
+    #[serde(rename = "type")]
+    pub geometry_type: String, 
+    pub coordinates: Vec<(f64, f64)>, 
+}
+
+/*
+ *  SegmentProperties   : safety breakdown of a single segment of a route
+ *  open_business_count : businesses nearby that are open at the departure time, None when opening hours are unknown
+ *  hazard_reports      : ids of the active user reports near the segment
+ *  safety_score        : 0-100 score combining lights, businesses and hazard reports
+ *  sidewalk_width_meters, curb_ramps, surface : accessibility attributes of the sidewalk, None when unknown
+ */
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct SegmentProperties {
+    pub street_name: Option<String>, 
+    pub distance_meters: f64, 
+    pub light_count: usize, 
+    pub building_count: usize, 
+    pub open_business_count: Option<usize>, 
+    pub hazard_reports: Vec<String>, 
+    pub safety_score: f64, 
+    pub sidewalk_width_meters: Option<f64>, 
+    pub curb_ramps: Option<bool>, 
+    pub stairs: bool, 
+    pub surface: Option<String>, 
+}
+
+/* part of a route between two consecutive stops (origin, waypoints, destination) */
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct RouteLeg {
+    pub route: Vec<(f64, f64)>, 
+    pub distance_meters: f64, 
+    pub eta_seconds: f64, 
+    pub lit_percentage: f64, 
+    pub safety_score: f64, 
+}
+
+/*
+ *  RouteHazard : user report that influenced a route
+ *  report_type : duration category of the report (Short, Medium, Long)
+ *  avoided     : true when the route was diverted around the report, false when it still passes it
+ */
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct RouteHazard {
+    pub report_id: String, 
+    pub report_type: String, 
+    pub description: String, 
+    pub location: (f64, f64), 
+    pub avoided: bool, 
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct Maneuver {
+    pub maneuver_type: String, 
+    pub modifier: Option<String>, 
+    pub instruction: String, 
+    pub street_name: Option<String>, 
+    pub bearing_before: f64, 
+    pub bearing_after: f64, 
+    pub distance_meters: f64, 
+    pub location: (f64, f64), 
 }
 
 #[derive(Serialize, Debug, Deserialize)]
@@ -235,4 +423,115 @@ pub struct InsertReportBody {
 #[serde(crate = "rocket::serde")]
 pub struct ReportRequest {
     pub reportID: String,
-}
\ No newline at end of file
+}
+
+/*
+ *  SavedRoute    : computed route saved by a user, also returned to anyone with its share link
+ *  parameters    : route query the route was computed for
+ *  route         : the route as it was computed when it was saved
+ *  graph_version : version of the navigation graph the route was computed on
+ *  created_at    : RFC 3339 time the route was saved
+ *  expires_at    : RFC 3339 time the share link stops working
+ *  revoked       : the owner turned the share link off
+ */
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct SavedRoute {
+    pub share_token: String,
+    pub origin: Vec<f64>,
+    pub destination: Vec<f64>,
+    pub parameters: serde_json::Value,
+    pub route: serde_json::Value,
+    pub distance_meters: f64,
+    pub graph_version: String,
+    pub created_at: String,
+    pub expires_at: String,
+    pub revoked: bool,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct SavedRoutes {
+    pub routes: Vec<SavedRoute>,
+}
+
+/*
+ *  TripResponse               : state of a trip for the user walking it
+ *  status                     : Active, Arrived or Ended
+ *  off_route                  : the last location was further from the route than the off route threshold
+ *  stalled                    : the user has not moved for the stall duration - checked on every location report
+ *                               and, for phones that stopped reporting, once a minute in the background
+ *  distance_from_route_meters : meters between the last location and the part of the route still to walk
+ *  events                     : everything that happened on the trip, oldest first
+ */
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct TripResponse {
+    pub id: i32,
+    pub region: String,
+    pub graph_version: String,
+    pub status: String,
+    pub route: Vec<(f64, f64)>,
+    pub destination: Vec<f64>,
+    pub off_route: bool,
+    pub stalled: bool,
+    pub distance_from_route_meters: Option<f64>,
+    pub last_location: Option<Vec<f64>>,
+    pub last_seen_at: Option<String>,
+    pub started_at: String,
+    pub ended_at: Option<String>,
+    pub events: Vec<TripEventResponse>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct TripEventResponse {
+    pub kind: String,
+    pub location: Option<Vec<f64>>,
+    pub distance_from_route_meters: Option<f64>,
+    pub created_at: String,
+}
+
+/* [lng, lat] coordinates of one route of the matrix */
+pub type MatrixGeometry = Vec<(f64, f64)>;
+
+/*
+ *  RouteMatrix       : walking routes from every origin (rows) to every destination (columns)
+ *  distances_meters  : None when the destination cannot be reached from the origin
+ *  costs             : meters plus the penalties of the preference - only comparable within one matrix
+ *  geometries        : [lng, lat] coordinates of every route, only when they were asked for
+ */
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct RouteMatrix {
+    pub distances_meters: Vec<Vec<Option<f64>>>,
+    pub durations_seconds: Vec<Vec<Option<f64>>>,
+    pub costs: Vec<Vec<Option<f64>>>,
+    pub geometries: Option<Vec<Vec<Option<MatrixGeometry>>>>,
+}
+
+/*
+ *  SafePlace               : open business the user can walk to
+ *  location                : [lng, lat] of the business
+ *  walking_distance_meters : length of the route along the sidewalks
+ *  route                   : [lng, lat] coordinates of the walking route from the user
+ */
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct SafePlace {
+    pub id: String,
+    pub name: String,
+    pub address: Option<String>,
+    pub location: (f64, f64),
+    pub walking_distance_meters: f64,
+    pub eta_seconds: f64,
+    pub closes_in_minutes: i32,
+    pub route: Vec<(f64, f64)>,
+}
+
+/* closest place first */
+#[derive(Serialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct SafePlaces {
+    pub places: Vec<SafePlace>,
+}
@@ -38,19 +38,13 @@ impl PartialEq for F64Wrapper {
  *                 the part of the street that is querying the spatial r-tree. Therefore, a HashSet
  *                 is used to avoid duplicates.
  */
-#[derive(Eq, Hash, Debug)]
+#[derive(PartialEq, Eq, Hash, Debug)]
 #[allow(dead_code)]
 pub struct HashablePoint {
     pub x: F64Wrapper,
     pub y: F64Wrapper,
 }
 
-impl PartialEq for HashablePoint {
-    fn eq(&self, other: &Self) -> bool {
-        self.x.0.to_bits() == other.x.0.to_bits() && self.y.0.to_bits() == other.y.0.to_bits()
-    }
-}
-
 impl HashablePoint {
     pub fn new(x: f64, y: f64) -> Self {
         Self { x: F64Wrapper(x), y: F64Wrapper(y) }
@@ -5,7 +5,6 @@ use dotenv::dotenv;
 
 use infrastructure::cors;
 use infrastructure::database;
-use api;
 
 #[launch]
 fn rocket() -> _ {
@@ -16,6 +15,9 @@ fn rocket() -> _ {
     rocket::build()
         .attach(cors::CORS)
         .attach(database::stage())
-        .manage(api::load_graph())
+        .attach(api::graph_stage())
+        .attach(api::routing_provider_stage())
+        .attach(api::route_cache_stage())
+        .attach(api::trip_stage())
         .mount("/api", api::routes())
 }